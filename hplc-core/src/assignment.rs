@@ -0,0 +1,119 @@
+#[derive(Clone, Copy)]
+enum Step {
+    Start,
+    SkipExpected,
+    SkipFound,
+    Match,
+}
+
+/// Pairs expected locations with found locations, both sorted in elution order.
+///
+/// Only pairs closer than `tolerance` may be matched and matches never cross,
+/// if expected `a` elutes before expected `b` then so does the peak matched to `a`.
+/// Of all such assignments the one with the most matches is chosen, ties are broken by the smallest total deviation.
+/// This is solved exactly by dynamic programming in O(n * m).
+///
+/// Returns the `(expected, found)` index pairs in elution order.
+pub(crate) fn assign(expected: &[f64], found: &[f64], tolerance: f64) -> Vec<(usize, usize)> {
+    let columns = found.len() + 1;
+
+    // best[i * columns + j] is the optimum for the first i expected and first j found locations
+    let mut best = vec![(0usize, 0.0f64); (expected.len() + 1) * columns];
+    let mut steps = vec![Step::Start; (expected.len() + 1) * columns];
+
+    let better =
+        |lhs: (usize, f64), rhs: (usize, f64)| lhs.0 > rhs.0 || (lhs.0 == rhs.0 && lhs.1 < rhs.1);
+
+    for i in 0..=expected.len() {
+        for j in 0..=found.len() {
+            let cell = i * columns + j;
+            if i > 0 {
+                best[cell] = best[cell - columns];
+                steps[cell] = Step::SkipExpected;
+            }
+
+            if j > 0 && (i == 0 || better(best[cell - 1], best[cell])) {
+                best[cell] = best[cell - 1];
+                steps[cell] = Step::SkipFound;
+            }
+
+            if i > 0 && j > 0 {
+                let deviation = (expected[i - 1] - found[j - 1]).abs();
+                if deviation <= tolerance {
+                    let (matches, total) = best[cell - columns - 1];
+                    let candidate = (matches + 1, total + deviation);
+                    if better(candidate, best[cell]) {
+                        best[cell] = candidate;
+                        steps[cell] = Step::Match;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut pairs = vec![];
+    let (mut i, mut j) = (expected.len(), found.len());
+    loop {
+        match steps[i * columns + j] {
+            Step::Start => break,
+            Step::SkipExpected => i -= 1,
+            Step::SkipFound => j -= 1,
+            Step::Match => {
+                i -= 1;
+                j -= 1;
+                pairs.push((i, j));
+            }
+        }
+    }
+
+    pairs.reverse();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_inputs() {
+        assert_eq!(assign(&[], &[], 1.0), vec![]);
+        assert_eq!(assign(&[1.0, 2.0], &[], 1.0), vec![]);
+        assert_eq!(assign(&[], &[1.0, 2.0], 1.0), vec![]);
+    }
+
+    #[test]
+    fn outside_tolerance() {
+        assert_eq!(assign(&[1.0], &[1.6], 0.5), vec![]);
+        assert_eq!(assign(&[1.0], &[1.5], 0.5), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn most_matches_over_closest() {
+        // Matching 10.5 to its closest peak would leave 10.0 without one
+        assert_eq!(
+            assign(&[10.0, 10.5], &[10.45, 10.9], 0.5),
+            vec![(0, 0), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn matches_never_cross() {
+        // Both references are closest to the second peak, only one of them can have it
+        let pairs = assign(&[10.0, 10.2], &[9.7, 10.15], 0.5);
+        assert_eq!(pairs, vec![(0, 0), (1, 1)]);
+
+        let pairs = assign(&[1.0, 2.0, 3.0], &[1.1, 2.9, 3.05], 1.0);
+        assert_eq!(pairs, vec![(0, 0), (1, 1), (2, 2)]);
+        for pair in pairs.windows(2) {
+            assert!(pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1);
+        }
+    }
+
+    #[test]
+    fn ties_resolved_by_deviation() {
+        // One match either way, the closer one wins
+        assert_eq!(assign(&[10.0, 10.2], &[10.15], 0.5), vec![(1, 0)]);
+        assert_eq!(assign(&[5.0], &[4.8, 5.1], 0.5), vec![(0, 1)]);
+        assert_eq!(assign(&[4.8, 5.1], &[5.0], 0.5), vec![(1, 0)]);
+    }
+}
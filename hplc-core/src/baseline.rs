@@ -0,0 +1,484 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::linear::solve_pentadiagonal;
+use crate::vector::{Point2D, Vector2, interpolate};
+
+/// Estimates the baseline below the data, from which heights and areas are measured.
+///
+/// Implementations return one point per data point at the same times as the data.
+pub trait Baseline {
+    fn calculate(&self, data: &[Point2D]) -> Vec<Point2D>;
+}
+
+/// Lower convex hull of the data, the steepest straight lines which pass below every point.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConvexHull;
+
+/// Asymmetric least squares (Eilers & Boelens).
+///
+/// A smooth curve is fitted to the data where points above the curve are weighted by `asymmetry`
+/// and points below by `1 - asymmetry`, so peaks barely pull the baseline up.
+/// `lambda` penalises the curvature of the baseline, larger values give stiffer baselines.
+#[derive(Clone, Copy, Debug)]
+pub struct AsymmetricLeastSquares {
+    pub lambda: f64,
+    pub asymmetry: f64,
+    pub iterations: usize,
+}
+
+/// Asymmetrically reweighted penalized least squares (Baek et al.).
+///
+/// Same fit as [`AsymmetricLeastSquares`] but the weights are derived from the noise of the points below the
+/// baseline, so there is no asymmetry to tune.
+#[derive(Clone, Copy, Debug)]
+pub struct Arpls {
+    pub lambda: f64,
+    pub iterations: usize,
+}
+
+/// Morphological opening of the data (the path of a flat ball rolled below it), smoothed with a mean of the same radius.
+#[derive(Clone, Copy, Debug)]
+pub struct RollingBall {
+    pub radius: usize,
+}
+
+/// Statistics-sensitive non-linear iterative peak-clipping.
+///
+/// Every point is repeatedly clipped to the mean of its neighbours `p` points away for `p` up to `iterations`,
+/// which should be about half the width of the widest peak in points.
+#[derive(Clone, Copy, Debug)]
+pub struct Snip {
+    pub iterations: usize,
+}
+
+/// Piecewise linear baseline through anchor points placed by the user.
+///
+/// The baseline is flat before the first and after the last anchor.
+#[derive(Clone, Debug)]
+pub struct ManualBaseline {
+    anchors: Vec<Point2D>,
+}
+
+impl ManualBaseline {
+    pub fn new(anchors: &[Point2D]) -> Self {
+        let mut anchors = anchors.to_vec();
+        anchors.sort_by(|lhs, rhs| lhs.x().total_cmp(&rhs.x()));
+
+        Self { anchors }
+    }
+}
+
+impl Baseline for ConvexHull {
+    fn calculate(&self, data: &[Point2D]) -> Vec<Point2D> {
+        let mut origin = &data[0];
+        let mut orgin_index = 0;
+
+        let mut next = &data[1];
+        let mut next_index = 1;
+
+        let mut baseline = vec![];
+
+        while next_index + 1 < data.len() {
+            let mut best_gradient = f64::INFINITY;
+
+            for (i, point) in data.iter().enumerate().skip(orgin_index) {
+                let gradient = origin.gradient(point);
+                if gradient < best_gradient {
+                    next = point;
+                    next_index = i;
+                    best_gradient = gradient;
+                }
+            }
+
+            for index in orgin_index..next_index {
+                let time = data[index].x();
+                let x = time - data[orgin_index].x();
+                let height = best_gradient * x + origin.y();
+                baseline.push(Point2D::new(time, height));
+            }
+
+            origin = next;
+            orgin_index = next_index;
+        }
+
+        baseline.push(*next);
+        baseline
+    }
+}
+
+impl Baseline for ManualBaseline {
+    fn calculate(&self, data: &[Point2D]) -> Vec<Point2D> {
+        let (first, last) = match (self.anchors.first(), self.anchors.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return vec![],
+        };
+
+        data.iter()
+            .map(|point| {
+                let height = if point.x() <= first.x() {
+                    first.y()
+                } else if point.x() >= last.x() {
+                    last.y()
+                } else {
+                    interpolate(&self.anchors, point.x()).unwrap_or(last.y())
+                };
+
+                Point2D::new(point.x(), height)
+            })
+            .collect()
+    }
+}
+
+impl Baseline for AsymmetricLeastSquares {
+    fn calculate(&self, data: &[Point2D]) -> Vec<Point2D> {
+        let heights: Vec<f64> = data.iter().map(|point| point.y()).collect();
+        let mut weights = vec![1.0; data.len()];
+        let mut fit = heights.clone();
+
+        for _ in 0..self.iterations.max(1) {
+            fit = match penalized_fit(&heights, &weights, self.lambda) {
+                Some(value) => value,
+                None => break,
+            };
+
+            for (i, weight) in weights.iter_mut().enumerate() {
+                *weight = if heights[i] > fit[i] {
+                    self.asymmetry
+                } else {
+                    1.0 - self.asymmetry
+                };
+            }
+        }
+
+        with_times(data, &fit)
+    }
+}
+
+impl Baseline for Arpls {
+    fn calculate(&self, data: &[Point2D]) -> Vec<Point2D> {
+        let heights: Vec<f64> = data.iter().map(|point| point.y()).collect();
+        let mut weights = vec![1.0; data.len()];
+        let mut fit = heights.clone();
+
+        for _ in 0..self.iterations.max(1) {
+            fit = match penalized_fit(&heights, &weights, self.lambda) {
+                Some(value) => value,
+                None => break,
+            };
+
+            let negative: Vec<f64> = heights
+                .iter()
+                .zip(&fit)
+                .map(|(height, fit)| height - fit)
+                .filter(|residual| *residual < 0.0)
+                .collect();
+
+            if negative.len() < 2 {
+                break;
+            }
+
+            let mean = negative.iter().sum::<f64>() / negative.len() as f64;
+            let variance = negative
+                .iter()
+                .map(|residual| (residual - mean).powi(2))
+                .sum::<f64>()
+                / negative.len() as f64;
+            let deviation = variance.sqrt();
+            if deviation == 0.0 {
+                break;
+            }
+
+            let mut change = 0.0;
+            let mut total = 0.0;
+            for (i, weight) in weights.iter_mut().enumerate() {
+                let residual = heights[i] - fit[i];
+                let exponent = 2.0 * (residual - (2.0 * deviation - mean)) / deviation;
+                let updated = 1.0 / (1.0 + exponent.exp());
+
+                change += (updated - *weight).powi(2);
+                total += weight.powi(2);
+                *weight = updated;
+            }
+
+            if (change / total).sqrt() < 1e-3 {
+                break;
+            }
+        }
+
+        with_times(data, &fit)
+    }
+}
+
+impl Baseline for RollingBall {
+    fn calculate(&self, data: &[Point2D]) -> Vec<Point2D> {
+        let heights: Vec<f64> = data.iter().map(|point| point.y()).collect();
+
+        let eroded = window_map(&heights, self.radius, |window| {
+            window.iter().copied().fold(f64::INFINITY, f64::min)
+        });
+        let opened = window_map(&eroded, self.radius, |window| {
+            window.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+        });
+        let smoothed = window_map(&opened, self.radius, |window| {
+            window.iter().sum::<f64>() / window.len() as f64
+        });
+
+        with_times(data, &smoothed)
+    }
+}
+
+impl Baseline for Snip {
+    fn calculate(&self, data: &[Point2D]) -> Vec<Point2D> {
+        let mut heights: Vec<f64> = data.iter().map(|point| point.y()).collect();
+
+        for p in 1..=self.iterations {
+            if 2 * p >= heights.len() {
+                break;
+            }
+
+            let previous = heights.clone();
+            for i in p..previous.len() - p {
+                let mean = 0.5 * (previous[i - p] + previous[i + p]);
+                heights[i] = previous[i].min(mean);
+            }
+        }
+
+        with_times(data, &heights)
+    }
+}
+
+/// Minimises `sum(w (y - z)^2) + lambda * sum((second difference of z)^2)`.
+fn penalized_fit(heights: &[f64], weights: &[f64], lambda: f64) -> Option<Vec<f64>> {
+    let order = heights.len();
+    if order < 3 {
+        return Some(heights.to_vec());
+    }
+
+    // W + lambda D^T D, where every row of D is [1, -2, 1]
+    let mut diagonal = weights.to_vec();
+    let mut upper1 = vec![0.0; order - 1];
+    let mut upper2 = vec![0.0; order - 2];
+
+    for k in 0..order - 2 {
+        diagonal[k] += lambda;
+        diagonal[k + 1] += 4.0 * lambda;
+        diagonal[k + 2] += lambda;
+
+        upper1[k] -= 2.0 * lambda;
+        upper1[k + 1] -= 2.0 * lambda;
+        upper2[k] += lambda;
+    }
+
+    let values: Vec<f64> = heights
+        .iter()
+        .zip(weights)
+        .map(|(height, weight)| height * weight)
+        .collect();
+
+    solve_pentadiagonal(&diagonal, &upper1, &upper2, &values)
+}
+
+/// Applies `map` to the window of `radius` points on either side of every point, clamped to the ends of the data.
+fn window_map<F: Fn(&[f64]) -> f64>(values: &[f64], radius: usize, map: F) -> Vec<f64> {
+    (0..values.len())
+        .map(|i| {
+            let start = i.saturating_sub(radius);
+            let end = usize::min(i + radius + 1, values.len());
+            map(&values[start..end])
+        })
+        .collect()
+}
+
+fn with_times(data: &[Point2D], heights: &[f64]) -> Vec<Point2D> {
+    data.iter()
+        .zip(heights)
+        .map(|(point, height)| Point2D::new(point.x(), *height))
+        .collect()
+}
+
+/// The baseline algorithms selectable by the user.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BaselineMethod {
+    #[default]
+    ConvexHull,
+    AsymmetricLeastSquares,
+    Arpls,
+    RollingBall,
+    Snip,
+}
+
+impl BaselineMethod {
+    pub const ALL: [BaselineMethod; 5] = [
+        BaselineMethod::ConvexHull,
+        BaselineMethod::AsymmetricLeastSquares,
+        BaselineMethod::Arpls,
+        BaselineMethod::RollingBall,
+        BaselineMethod::Snip,
+    ];
+}
+
+impl Display for BaselineMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BaselineMethod::ConvexHull => "Convex Hull",
+            BaselineMethod::AsymmetricLeastSquares => "AsLS",
+            BaselineMethod::Arpls => "arPLS",
+            BaselineMethod::RollingBall => "Rolling Ball",
+            BaselineMethod::Snip => "SNIP",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for BaselineMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "hull" | "convex-hull" => Ok(BaselineMethod::ConvexHull),
+            "asls" => Ok(BaselineMethod::AsymmetricLeastSquares),
+            "arpls" => Ok(BaselineMethod::Arpls),
+            "rolling-ball" => Ok(BaselineMethod::RollingBall),
+            "snip" => Ok(BaselineMethod::Snip),
+            other => Err(format!("Unknown baseline method {}", other)),
+        }
+    }
+}
+
+/// Baseline configuration of a sample, parameters which do not apply to the method are ignored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BaselineSettings {
+    pub method: BaselineMethod,
+    /// Stiffness of AsLS and arPLS.
+    pub lambda: f64,
+    /// Weight of points above the AsLS baseline.
+    pub asymmetry: f64,
+    /// Maximum number of reweighting steps of AsLS and arPLS.
+    pub iterations: usize,
+    /// Radius of the rolling ball, or the largest clipping window of SNIP, in points.
+    pub radius: usize,
+}
+
+impl Default for BaselineSettings {
+    fn default() -> Self {
+        Self {
+            method: BaselineMethod::ConvexHull,
+            lambda: 1e6,
+            asymmetry: 0.01,
+            iterations: 10,
+            radius: 50,
+        }
+    }
+}
+
+impl Baseline for BaselineSettings {
+    fn calculate(&self, data: &[Point2D]) -> Vec<Point2D> {
+        match self.method {
+            BaselineMethod::ConvexHull => ConvexHull.calculate(data),
+            BaselineMethod::AsymmetricLeastSquares => AsymmetricLeastSquares {
+                lambda: self.lambda,
+                asymmetry: self.asymmetry,
+                iterations: self.iterations,
+            }
+            .calculate(data),
+            BaselineMethod::Arpls => Arpls {
+                lambda: self.lambda,
+                iterations: self.iterations,
+            }
+            .calculate(data),
+            BaselineMethod::RollingBall => RollingBall {
+                radius: self.radius,
+            }
+            .calculate(data),
+            BaselineMethod::Snip => Snip {
+                iterations: self.radius,
+            }
+            .calculate(data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drift(time: f64) -> f64 {
+        2.0 + 0.1 * time
+    }
+
+    /// Linear drift with a Gaussian peak of height 50 at 15 minutes.
+    fn signal() -> Vec<Point2D> {
+        (0..600)
+            .map(|i| {
+                let time = i as f64 * 0.05;
+                let peak = 50.0 * (-0.5 * ((time - 15.0) / 0.3).powi(2)).exp();
+                Point2D::new(time, drift(time) + peak)
+            })
+            .collect()
+    }
+
+    /// Largest distance between the baseline and the drift, away from the ends of the data.
+    fn deviation<B: Baseline>(method: &B) -> f64 {
+        let data = signal();
+        let baseline = method.calculate(&data);
+        assert_eq!(baseline.len(), data.len());
+
+        baseline[50..550]
+            .iter()
+            .zip(&data[50..550])
+            .map(|(point, original)| {
+                assert_eq!(point.x(), original.x());
+                (point.y() - drift(point.x())).abs()
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn convex_hull() {
+        assert!(deviation(&ConvexHull) < 1e-9);
+    }
+
+    #[test]
+    fn asls() {
+        let method = AsymmetricLeastSquares {
+            lambda: 1e6,
+            asymmetry: 0.01,
+            iterations: 10,
+        };
+
+        assert!(deviation(&method) < 1.0);
+    }
+
+    #[test]
+    fn arpls() {
+        let method = Arpls {
+            lambda: 1e6,
+            iterations: 10,
+        };
+
+        assert!(deviation(&method) < 1.0);
+    }
+
+    #[test]
+    fn rolling_ball() {
+        assert!(deviation(&RollingBall { radius: 50 }) < 1.0);
+    }
+
+    #[test]
+    fn snip() {
+        assert!(deviation(&Snip { iterations: 50 }) < 1.0);
+    }
+
+    #[test]
+    fn manual_baseline() {
+        let anchors = [
+            Point2D::new(30.0, drift(30.0)),
+            Point2D::new(0.0, drift(0.0)),
+        ];
+        assert!(deviation(&ManualBaseline::new(&anchors)) < 1e-9);
+    }
+}
@@ -0,0 +1,1781 @@
+use std::ffi::OsString;
+use std::fs;
+use std::iter::Iterator;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::assignment::assign;
+use crate::baseline::{Baseline, BaselineSettings, ManualBaseline};
+use crate::component::{Component, MatchConfidence, Peak};
+use crate::deconvolution::{PeakModel, deconvolve};
+use crate::derivative::Derivatives;
+use crate::editing::{PeakEdit, apply_edits};
+use crate::import::{self, DataFormat, ImportProfile, decode_text};
+use crate::integration::{
+    EventKind, IntegrationEvent, IntegrationMode, IntegrationSettings, IntegrationWindow,
+    apply_events, integrate,
+};
+use crate::label::{LabelOverride, peak_at};
+use crate::metadata::{SampleMetadata, parse_header};
+use crate::metrics::calculate_metrics;
+use crate::noise::NoiseSettings;
+use crate::reference::Reference;
+use crate::smoothing::{Smoother, Smoothing};
+use crate::spline::Spline;
+use crate::vector::*;
+
+/// Role of a sample within a sample set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SampleType {
+    #[default]
+    Data,
+    Blank,
+    Dex,
+    Standard,
+}
+
+/// Selects which kinds of [`Component`] are returned by [`Chromatography::get_components`].
+pub struct ComponentFilter {
+    pub unknown: bool,
+    pub located: bool,
+    pub reference: bool,
+}
+
+impl ComponentFilter {
+    pub const ALL: ComponentFilter = ComponentFilter {
+        unknown: true,
+        located: true,
+        reference: true,
+    };
+
+    pub const EXISTING_ONLY: ComponentFilter = ComponentFilter {
+        unknown: true,
+        located: true,
+        reference: false,
+    };
+
+    pub const EXPECTED_ONLY: ComponentFilter = ComponentFilter {
+        unknown: false,
+        located: true,
+        reference: true,
+    };
+}
+
+static DEX_RANGE: Range<f64> = 0.0..38.7;
+
+// Points before the first one searched for peaks, the derivatives need as many behind it
+const PEAK_PIVOT: usize = 4;
+
+/// Everything the peaks of the blank depend on, they are only searched for again once it changes.
+#[derive(Clone, Debug, PartialEq)]
+struct BlankSettings {
+    data: Rc<[Point2D]>,
+    data_range: Option<Range<f64>>,
+    height_requirement: f64,
+    inflection_requirement: f64,
+    smoothing: Smoothing,
+    derivatives: Derivatives,
+    baseline_settings: BaselineSettings,
+    integration: IntegrationSettings,
+    noise_settings: NoiseSettings,
+    signal_to_noise_threshold: bool,
+}
+
+/// A single HPLC sample and every stage of its analysis.
+///
+/// All derived data is recalculated eagerly whenever a parameter is changed through one of the setters.
+#[derive(Clone, Debug, Default)]
+pub struct Chromatography {
+    // Transformations of the data
+    raw_data: Vec<Point2D>,
+    cleaned_data: Vec<Point2D>,
+    first_derivative: Vec<Point2D>,
+    second_derivative: Vec<Point2D>,
+    /// Baseline below the data, one point per data point.
+    pub baseline: Vec<Point2D>,
+    /// Area between the data and the baseline.
+    pub total_area: f64,
+    noise: f64,
+
+    // Derived components
+    existing_components: Vec<Peak>,
+    qualified_components: Vec<Component>,
+    blank_components: Vec<Peak>,
+    blank_settings: Option<BlankSettings>,
+
+    // Chromatography configuration
+    sample_type: SampleType,
+    data_range: Option<Range<f64>>,
+    include_unknowns: bool,
+    height_requirement: f64,
+    inflection_requirement: f64,
+    retention_time_tolerance: f64,
+    glucose_unit_tolerance: f64,
+    subtract_blank: bool,
+    smoothing: Smoothing,
+    derivatives: Derivatives,
+    baseline_settings: BaselineSettings,
+    baseline_anchors: Vec<Point2D>,
+    integration: IntegrationSettings,
+    peak_model: PeakModel,
+    noise_settings: NoiseSettings,
+    signal_to_noise_threshold: bool,
+    label_overrides: Vec<LabelOverride>,
+    peak_edits: Vec<PeakEdit>,
+
+    // External references
+    lipid_references: Rc<[Reference]>,
+    blank_data: Option<Rc<[Point2D]>>,
+    /// RT to GU spline of the sample set, see [`Chromatography::set_glucose_transformer`].
+    pub glucose_transformer: Option<Spline>,
+
+    // Sample identification
+    /// `SampleName` of the file, or its file name if absent.
+    pub title: String,
+    pub file_name: OsString,
+    /// Path the sample was loaded from.
+    pub file_path: PathBuf,
+    format: DataFormat,
+    import_profile: Option<ImportProfile>,
+    metadata: SampleMetadata,
+    channels: Vec<Channel>,
+    channel: usize,
+}
+
+/// One signal column of a data file, such as a detector or a wavelength.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Channel {
+    /// Name given by the file, or `Channel N` (counting from 1) if it has none.
+    pub name: String,
+    /// Unit of the signal, if the file gives one.
+    pub unit: Option<String>,
+    /// Every point of the column in the file, before any processing.
+    pub data: Vec<Point2D>,
+}
+
+// Headers naming the signal columns, one value per column
+const CHANNEL_HEADERS: [&str; 4] = [
+    "Channel",
+    "Channel Name",
+    "Channel Description",
+    "Wavelength",
+];
+
+/// Splits every line of `time, signal, signal, ...` into one channel per signal column.
+///
+/// Names are taken from a channel header or an unquoted row of column titles before the data.
+fn parse_channels(file: &str, splitter: &str) -> Vec<Channel> {
+    let mut names: Vec<String> = vec![];
+    let mut columns: Vec<Vec<Point2D>> = vec![];
+
+    for line in file.split(splitter) {
+        let separator = if line.contains('\t') { '\t' } else { ',' };
+        let mut fields = line.split(separator);
+        let Some(first) = fields.next() else {
+            continue;
+        };
+
+        let Ok(x) = first.parse::<f64>() else {
+            let key = first.trim().trim_matches('"');
+            let titles = columns.is_empty() && !first.starts_with('"') && !first.is_empty();
+            if CHANNEL_HEADERS.contains(&key) || (titles && names.is_empty()) {
+                names = fields
+                    .map(|name| name.trim().trim_matches('"').to_string())
+                    .collect();
+            }
+
+            continue;
+        };
+
+        for (i, field) in fields.enumerate() {
+            if columns.len() <= i {
+                columns.resize(i + 1, vec![]);
+            }
+
+            if let Ok(y) = field.parse::<f64>() {
+                columns[i].push(Point2D::new(x, y));
+            }
+        }
+    }
+
+    let mut channels: Vec<Channel> = vec![];
+    for (i, data) in columns.into_iter().enumerate() {
+        // Trailing separators produce empty columns
+        if data.is_empty() {
+            continue;
+        }
+
+        let mut name = match names.get(i) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => format!("Channel {}", i + 1),
+        };
+        if channels.iter().any(|channel| channel.name == name) {
+            name = format!("{} ({})", name, i + 1);
+        }
+
+        channels.push(Channel {
+            name,
+            unit: None,
+            data,
+        });
+    }
+
+    channels
+}
+
+impl Chromatography {
+    /// Parses an Empower `arw` export (or a csv/tsv of time and height) and runs the analysis with default parameters.
+    ///
+    /// Every column after the time is a [`Channel`], the first one is analysed.
+    /// The [`DataFormat`] is detected from the content, so AIA/ANDI netCDF files and the ASCII exports
+    /// of ChemStation, LabSolutions and Chromeleon are read as well.
+    /// Returns why if the file could not be read.
+    pub fn from_file<T: AsRef<Path>>(path: &T) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|err| err.to_string())?;
+        let format = DataFormat::detect(&bytes);
+        if format != DataFormat::Empower {
+            let (metadata, channels) = import::parse(format, &bytes)?;
+            return Ok(Self::from_channels(path, format, metadata, channels));
+        }
+
+        let file = String::from_utf8(bytes).map_err(|_| "Invalid UTF-8 text".to_string())?;
+
+        let mut empty = Chromatography {
+            file_name: path.as_ref().file_name().unwrap().to_os_string(),
+            file_path: path.as_ref().to_path_buf(),
+            ..Default::default()
+        };
+
+        let mut splitter = String::new();
+        if file.contains('\r') {
+            splitter += "\r";
+        }
+        if file.contains('\n') {
+            splitter += "\n";
+        }
+
+        empty.title = {
+            let mut name = empty.file_name.clone().into_string().unwrap();
+
+            for line in file.split(&splitter) {
+                let mut pair = line.split("\t");
+                if let Some(key) = pair.next()
+                    && key == "\"SampleName\""
+                {
+                    name = pair.next().unwrap().to_string();
+                }
+            }
+
+            name
+        };
+
+        let headers = file.split(&splitter).filter_map(parse_header).collect();
+        empty.metadata = SampleMetadata::from_headers(headers);
+
+        empty.channels = parse_channels(&file, &splitter);
+        if let Some(channel) = empty.channels.first() {
+            empty.raw_data = channel.data.clone();
+        }
+
+        empty.update_data();
+
+        Ok(empty)
+    }
+
+    /// Reads a delimited text file laid out as described by `profile` and runs the analysis with default parameters.
+    ///
+    /// Unlike [`Chromatography::from_file`] nothing is guessed, lines of data that cannot be read are reported
+    /// by [`ImportProfile::read`] and only fail the import if no data is left.
+    pub fn from_delimited_file<T: AsRef<Path>>(
+        path: &T,
+        profile: &ImportProfile,
+    ) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|err| err.to_string())?;
+        let data = profile.read(&decode_text(&bytes)?)?;
+
+        let mut sample =
+            Self::from_channels(path, DataFormat::Delimited, data.metadata, data.channels);
+        sample.import_profile = Some(profile.clone());
+
+        Ok(sample)
+    }
+
+    /// A sample of already parsed channels, titled by the sample name of the metadata or else the file name.
+    fn from_channels<T: AsRef<Path>>(
+        path: &T,
+        format: DataFormat,
+        metadata: SampleMetadata,
+        channels: Vec<Channel>,
+    ) -> Self {
+        let mut empty = Chromatography::default();
+
+        empty.file_name = path.as_ref().file_name().unwrap_or_default().to_os_string();
+        empty.file_path = path.as_ref().to_path_buf();
+        empty.title = metadata
+            .sample_name
+            .clone()
+            .unwrap_or(empty.file_name.to_string_lossy().to_string());
+        empty.format = format;
+        empty.metadata = metadata;
+        empty.channels = channels;
+        if let Some(channel) = empty.channels.first() {
+            empty.raw_data = channel.data.clone();
+        }
+
+        empty.update_data();
+
+        empty
+    }
+
+    /// Every signal column of the file, see [`Chromatography::set_channel`].
+    pub fn get_channels(&self) -> &[Channel] {
+        &self.channels
+    }
+
+    /// Index of the channel being analysed.
+    pub fn get_channel(&self) -> usize {
+        self.channel
+    }
+
+    /// Analyses the channel at `index` instead, indices past the last channel are ignored.
+    ///
+    /// Every other setting (anchors, edits, labels) is kept.
+    pub fn set_channel(&mut self, index: &usize) -> &mut Self {
+        if let Some(channel) = self.channels.get(*index)
+            && *index != self.channel
+        {
+            self.raw_data = channel.data.clone();
+            self.channel = *index;
+            self.update_data();
+        }
+
+        self
+    }
+
+    /// Format the file was read as.
+    pub fn get_format(&self) -> DataFormat {
+        self.format
+    }
+
+    /// Profile the file was read with, `None` unless loaded by [`Chromatography::from_delimited_file`].
+    pub fn get_import_profile(&self) -> Option<&ImportProfile> {
+        self.import_profile.as_ref()
+    }
+
+    /// Every header of the file, with the acquisition dates parsed.
+    pub fn get_metadata(&self) -> &SampleMetadata {
+        &self.metadata
+    }
+
+    /// The unprocessed data points of the whole file.
+    pub fn get_raw_data(&self) -> &[Point2D] {
+        &self.raw_data
+    }
+
+    /// How the noise of the sample is estimated.
+    pub fn set_noise_settings(&mut self, settings: &NoiseSettings) -> &mut Self {
+        self.noise_settings = *settings;
+        self.update_data();
+
+        self
+    }
+
+    /// Whether the height requirement (and height events) are multiples of the noise rather than detector units.
+    pub fn set_signal_to_noise_threshold(&mut self, enable: &bool) -> &mut Self {
+        self.signal_to_noise_threshold = *enable;
+        self.update_components();
+
+        self
+    }
+
+    /// Standard deviation of the detector noise, before smoothing.
+    pub fn get_noise(&self) -> f64 {
+        self.noise
+    }
+
+    /// The smoothed data points within the data range.
+    pub fn get_data(&self) -> Vec<Point2D> {
+        self.cleaned_data.clone()
+    }
+
+    /// Time range (in minutes) considered for analysis.
+    pub fn get_data_range(&self) -> Range<f64> {
+        if let Some(range) = &self.data_range {
+            range.clone()
+        } else {
+            let default = &Point2D::default();
+            let end = self.raw_data.last().unwrap_or(default);
+            0.0..end.x()
+        }
+    }
+
+    /// Restricts analysis to the given time range (in minutes).
+    /// Dex samples always use their own fixed range.
+    pub fn set_data_range(&mut self, value: &Range<f64>) -> &mut Self {
+        self.data_range = Some(value.clone());
+        self.update_data();
+
+        self
+    }
+
+    /// Height of the tallest data point.
+    pub fn get_highest_point(&self) -> f64 {
+        let mut highest = 0.0;
+        for point in &self.cleaned_data {
+            if point.y() > highest {
+                highest = point.y();
+            }
+        }
+
+        highest
+    }
+
+    /// Every peak found in the data, in order of retention time.
+    pub fn get_unqualified_components(&self) -> Vec<Peak> {
+        self.existing_components.clone()
+    }
+
+    /// Peaks labelled against the lipid references, merged with the references that were not found.
+    pub fn get_components(&self, filter: &ComponentFilter) -> Vec<Component> {
+        self.qualified_components
+            .iter()
+            .filter(|component| match component {
+                Component::Unknown(_) => filter.unknown,
+                Component::Located(_, _) => filter.located,
+                Component::Reference(_) => filter.reference,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Sets the lipid references used to label peaks.
+    pub fn set_lipid_references(&mut self, value: Rc<[Reference]>) -> &mut Self {
+        self.lipid_references = value;
+        self.qualified_components = self.identify_components();
+
+        self
+    }
+
+    /// Whether lipids which the reference does not name should be considered.
+    pub fn set_include_unknowns(&mut self, show: &bool) -> &mut Self {
+        self.include_unknowns = *show;
+        self.update_components();
+
+        self
+    }
+
+    /// Minimum height above the baseline for a maximum to count as a peak.
+    pub fn set_height_requirement(&mut self, value: &f64) -> &mut Self {
+        self.height_requirement = *value;
+        self.update_components();
+
+        self
+    }
+
+    /// Minimum change of the second derivative for an inflection to split a peak.
+    pub fn set_inflection_requirement(&mut self, value: &f64) -> &mut Self {
+        self.inflection_requirement = *value;
+        self.update_components();
+
+        self
+    }
+
+    /// Maximum distance (in minutes) between a peak and a reference to be labelled, used when no GU are available.
+    pub fn set_retention_time_tolerance(&mut self, value: &f64) -> &mut Self {
+        self.retention_time_tolerance = *value;
+        self.qualified_components = self.identify_components();
+
+        self
+    }
+
+    /// Maximum distance (in GU) between a peak and a reference to be labelled.
+    pub fn set_glucose_unit_tolerance(&mut self, value: &f64) -> &mut Self {
+        self.glucose_unit_tolerance = *value;
+        self.qualified_components = self.identify_components();
+
+        self
+    }
+
+    /// Builds the RT to GU spline assuming this sample is a dex ladder.
+    pub fn get_glucose_transformer(&self) -> Option<Spline> {
+        let mut peaks = self.existing_components.clone();
+        peaks.reverse();
+
+        let mut height = 0.0;
+        let mut intersections: Vec<Point2D> = vec![];
+        for peak in &peaks {
+            if peak.height > height {
+                height = peak.height;
+                intersections.push(peak.retention_point);
+            }
+        }
+
+        intersections.reverse();
+        let points: Vec<Point2D> = intersections
+            .iter()
+            .enumerate()
+            .map(|(i, point)| Point2D::new(point.x(), (i + 1) as f64))
+            .collect();
+
+        Spline::new(&points)
+    }
+
+    /// Sets the RT to GU spline, if present GU are used instead of RT for labelling.
+    pub fn set_glucose_transformer(&mut self, transformer: &Option<Spline>) -> &mut Self {
+        self.glucose_transformer = transformer.clone();
+        self.update_components();
+
+        self
+    }
+
+    /// Factor converting area to concentration (nmol/ml) assuming this sample is the standard.
+    ///
+    /// Returns `None` if no peak was found.
+    pub fn get_concentration_multiplier(
+        &self,
+        injected_volume: f64,
+        sample_dilution: f64,
+    ) -> Option<f64> {
+        let area = self.existing_components.first()?.area;
+        Some(1000.0 * (1.0 / injected_volume) * sample_dilution * 0.0025 * (1.0 / area))
+    }
+
+    /// Role of this sample within the sample set.
+    pub fn get_sample_type(&self) -> SampleType {
+        self.sample_type
+    }
+
+    /// Sets the role of this sample, dex samples switch to their own data range.
+    pub fn set_sample_type(&mut self, value: &SampleType) -> &mut Self {
+        self.sample_type = *value;
+
+        if *value == SampleType::Dex {
+            self.set_data_range(&DEX_RANGE);
+        } else {
+            self.update_data();
+        }
+
+        self
+    }
+
+    /// Raw data of the blank of the sample set.
+    /// Peaks which are also found in the blank are flagged as system peaks.
+    pub fn set_blank(&mut self, blank: Option<Rc<[Point2D]>>) -> &mut Self {
+        self.blank_data = blank;
+        self.update_data();
+
+        self
+    }
+
+    /// Whether the blank is subtracted from data samples before any analysis.
+    pub fn set_subtract_blank(&mut self, subtract: &bool) -> &mut Self {
+        self.subtract_blank = *subtract;
+        self.update_data();
+
+        self
+    }
+
+    /// Filter used to reduce noise before any analysis.
+    pub fn set_smoothing(&mut self, smoothing: &Smoothing) -> &mut Self {
+        self.smoothing = *smoothing;
+        self.update_data();
+
+        self
+    }
+
+    /// How the derivatives used to find peaks and inflections are calculated.
+    pub fn set_derivatives(&mut self, derivatives: &Derivatives) -> &mut Self {
+        self.derivatives = *derivatives;
+        self.update_data();
+
+        self
+    }
+
+    /// Algorithm used to calculate the baseline and its parameters.
+    pub fn set_baseline_settings(&mut self, settings: &BaselineSettings) -> &mut Self {
+        self.baseline_settings = *settings;
+        self.update_data();
+
+        self
+    }
+
+    /// Anchor points of a manual baseline, replacing the calculated one unless empty.
+    pub fn set_baseline_anchors(&mut self, anchors: &[Point2D]) -> &mut Self {
+        self.baseline_anchors = anchors.to_vec();
+        self.update_data();
+
+        self
+    }
+
+    /// Anchor points of the manual baseline in the order they were placed.
+    pub fn get_baseline_anchors(&self) -> &[Point2D] {
+        &self.baseline_anchors
+    }
+
+    /// Replaces the manual peak edits, which are applied in order after peak detection.
+    pub fn set_peak_edits(&mut self, edits: &[PeakEdit]) -> &mut Self {
+        self.peak_edits = edits.to_vec();
+        self.update_components();
+
+        self
+    }
+
+    /// Manual peak edits in the order they were made.
+    pub fn get_peak_edits(&self) -> &[PeakEdit] {
+        &self.peak_edits
+    }
+
+    /// Labels the peak containing `time` by hand, `None` marks it as unknown.
+    ///
+    /// Replaces earlier labels of the same peak and of the same reference.
+    pub fn set_manual_label(&mut self, time: &f64, label: Option<String>) -> &mut Self {
+        let Some(index) = peak_at(&self.existing_components, *time) else {
+            return self;
+        };
+
+        let peak = &self.existing_components[index];
+        let (start, end) = (peak.start.x(), peak.end.x());
+        let retention_time = peak.retention_point.x();
+
+        self.label_overrides.retain(|other| {
+            let same_peak = start <= other.retention_time && other.retention_time <= end;
+            let same_reference = label.is_some() && other.label == label;
+            !same_peak && !same_reference
+        });
+        self.label_overrides.push(LabelOverride {
+            retention_time,
+            label,
+        });
+        self.qualified_components = self.identify_components();
+
+        self
+    }
+
+    /// Returns the peak containing `time` to automatic labelling.
+    pub fn clear_manual_label(&mut self, time: &f64) -> &mut Self {
+        if let Some(index) = peak_at(&self.existing_components, *time) {
+            let peak = &self.existing_components[index];
+            let (start, end) = (peak.start.x(), peak.end.x());
+            self.label_overrides
+                .retain(|other| other.retention_time < start || end < other.retention_time);
+            self.qualified_components = self.identify_components();
+        }
+
+        self
+    }
+
+    /// Replaces every manual label, e.g. when a project is opened.
+    pub fn set_label_overrides(&mut self, overrides: &[LabelOverride]) -> &mut Self {
+        self.label_overrides = overrides.to_vec();
+        self.qualified_components = self.identify_components();
+
+        self
+    }
+
+    /// Manual labels in the order they were chosen.
+    pub fn get_label_overrides(&self) -> &[LabelOverride] {
+        &self.label_overrides
+    }
+
+    /// The found component whose peak contains `time`.
+    pub fn get_component_at(&self, time: f64) -> Option<&Component> {
+        self.qualified_components
+            .iter()
+            .filter(|component| {
+                component
+                    .get_peak()
+                    .is_some_and(|peak| peak.start.x() <= time && time <= peak.end.x())
+            })
+            .min_by(|lhs, rhs| {
+                let distance = |component: &Component| {
+                    component
+                        .get_experimental_rt()
+                        .map_or(f64::INFINITY, |rt| (rt - time).abs())
+                };
+                distance(lhs).total_cmp(&distance(rhs))
+            })
+    }
+
+    /// The located or missing component of `reference`.
+    pub fn get_reference_component(&self, reference: &Reference) -> Option<&Component> {
+        self.qualified_components
+            .iter()
+            .find(|component| match component {
+                Component::Located(_, other) => other == reference,
+                Component::Reference(other) => other == reference,
+                Component::Unknown(_) => false,
+            })
+    }
+
+    /// Integration mode of peaks outside of every integration window.
+    pub fn set_integration_mode(&mut self, mode: &IntegrationMode) -> &mut Self {
+        self.integration.mode = *mode;
+        self.update_components();
+
+        self
+    }
+
+    pub fn get_integration_mode(&self) -> IntegrationMode {
+        self.integration.mode
+    }
+
+    /// Time windows with their own integration mode.
+    pub fn set_integration_windows(&mut self, windows: &[IntegrationWindow]) -> &mut Self {
+        self.integration.windows = windows.to_vec();
+        self.update_components();
+
+        self
+    }
+
+    /// How many times higher a peak must be than its neighbour for the neighbour to be skimmed.
+    pub fn set_skim_ratio(&mut self, ratio: &f64) -> &mut Self {
+        self.integration.skim_ratio = *ratio;
+        self.update_components();
+
+        self
+    }
+
+    /// Timed events such as inhibiting integration or changing the height requirement after some time.
+    pub fn set_integration_events(&mut self, events: &[IntegrationEvent]) -> &mut Self {
+        self.integration.events = events.to_vec();
+        self.update_components();
+
+        self
+    }
+
+    /// Shape fitted to clusters of overlapping peaks, `PeakModel::None` disables deconvolution.
+    pub fn set_peak_model(&mut self, model: &PeakModel) -> &mut Self {
+        self.peak_model = *model;
+        self.update_components();
+
+        self
+    }
+
+    fn update_data(&mut self) {
+        let data = match &self.blank_data {
+            Some(blank) if self.subtract_blank && self.sample_type == SampleType::Data => self
+                .raw_data
+                .iter()
+                .map(|point| {
+                    let blank_height = interpolate(blank, point.x()).unwrap_or(0.0);
+                    Point2D::new(point.x(), point.y() - blank_height)
+                })
+                .collect(),
+            _ => self.raw_data.clone(),
+        };
+
+        self.cleaned_data = self.smoothing.smooth(&data);
+        if let Some(range) = self.get_analysis_range() {
+            self.cleaned_data.retain(|point| range.contains(&point.x()));
+        }
+
+        self.noise = self
+            .noise_settings
+            .estimate(&data, self.get_analysis_range().as_ref());
+
+        (self.first_derivative, self.second_derivative) =
+            self.derivatives.calculate(&self.cleaned_data);
+        // Too little of the data lies in the analysis range for a baseline, let alone peaks
+        self.baseline = if self.cleaned_data.len() <= PEAK_PIVOT {
+            self.cleaned_data.clone()
+        } else if self.baseline_anchors.is_empty() {
+            self.baseline_settings.calculate(&self.cleaned_data)
+        } else {
+            ManualBaseline::new(&self.baseline_anchors).calculate(&self.cleaned_data)
+        };
+        self.total_area = self.calculate_area();
+        self.update_components();
+    }
+
+    fn update_components(&mut self) {
+        let blank_settings = self.get_blank_settings();
+        if blank_settings != self.blank_settings {
+            self.blank_components = blank_settings
+                .as_ref()
+                .map(calculate_blank_components)
+                .unwrap_or_default();
+            self.blank_settings = blank_settings;
+        }
+
+        self.existing_components = self.calculate_components();
+        self.qualified_components = self.identify_components();
+    }
+
+    fn get_analysis_range(&self) -> Option<Range<f64>> {
+        if self.sample_type == SampleType::Dex {
+            Some(DEX_RANGE.clone())
+        } else {
+            self.data_range.clone()
+        }
+    }
+
+    fn get_blank_settings(&self) -> Option<BlankSettings> {
+        let data = self.blank_data.clone()?;
+
+        Some(BlankSettings {
+            data,
+            data_range: self.get_analysis_range(),
+            height_requirement: self.height_requirement,
+            inflection_requirement: self.inflection_requirement,
+            smoothing: self.smoothing,
+            derivatives: self.derivatives,
+            baseline_settings: self.baseline_settings,
+            integration: self.integration.clone(),
+            noise_settings: self.noise_settings,
+            signal_to_noise_threshold: self.signal_to_noise_threshold,
+        })
+    }
+
+    /// Tolerance in GU if the sample set has a dex ladder, else in minutes.
+    fn get_location_tolerance(&self) -> f64 {
+        match self.glucose_transformer {
+            Some(_) => self.glucose_unit_tolerance,
+            None => self.retention_time_tolerance,
+        }
+    }
+
+    fn calculate_area(&self) -> f64 {
+        let mut area = 0.0;
+        for i in 1..self.cleaned_data.len() {
+            let prev = self.cleaned_data[i - 1];
+            let next = self.cleaned_data[i];
+
+            let width = next.x() - prev.x();
+            let a = prev.y() - self.baseline[i - 1].y();
+            let b = next.y() - self.baseline[i].y();
+            area += 0.5 * width * (a + b);
+        }
+
+        area
+    }
+
+    fn calculate_components(&self) -> Vec<Peak> {
+        let pivot = PEAK_PIVOT;
+        let mut result = vec![];
+        if self.cleaned_data.len() <= pivot {
+            return result;
+        }
+
+        let mut peak = Peak {
+            start: self.cleaned_data[pivot],
+            ..Default::default()
+        };
+
+        let mut found_maximum = false;
+        // Noiseless data would otherwise accept every maximum
+        let height_scale = if self.signal_to_noise_threshold && self.noise > 0.0 {
+            self.noise
+        } else {
+            1.0
+        };
+        let mut prev_min = self.cleaned_data[pivot].y() - self.baseline[pivot].y();
+
+        for index in pivot..self.cleaned_data.len() {
+            let prev = &self.cleaned_data[index - 1];
+            let next = &self.cleaned_data[index];
+            let height = prev.y() - self.baseline[index - 1].y();
+
+            let height_requirement = self
+                .integration
+                .event_value(EventKind::HeightRequirement, prev.x())
+                .unwrap_or(self.height_requirement)
+                * height_scale;
+            let inflection_requirement = self
+                .integration
+                .event_value(EventKind::InflectionRequirement, prev.x())
+                .unwrap_or(self.inflection_requirement);
+
+            let area = {
+                let h = next.x() - prev.x();
+                let a = prev.y() - self.baseline[index - 1].y();
+                let b = next.y() - self.baseline[index].y();
+                h * (a + b) / 2.0
+            };
+
+            peak.area += area;
+
+            let prev_drv = &self.first_derivative[index - 2];
+            let next_drv = &self.first_derivative[index - 1];
+
+            // Minimum
+            if prev_drv.y() <= 0.0 && next_drv.y() >= 0.0 {
+                // Real peak
+                if found_maximum {
+                    found_maximum = false;
+                    prev_min = height;
+
+                    peak.end = *prev;
+
+                    result.push(peak);
+                    peak = Peak::default();
+                    peak.start = *prev;
+                    // Lower minimum but without a peak, merge with prev peak
+                } else if height < prev_min {
+                    prev_min = height;
+
+                    if let Some(prev_peak) = result.last_mut() {
+                        prev_peak.end = *prev;
+                        prev_peak.area += peak.area;
+                    }
+
+                    peak = Peak::default();
+                    peak.start = *prev;
+                }
+            } else if prev_drv.y() >= 0.0 && next_drv.y() <= 0.0 {
+                // Maximum
+                peak.height = prev.y() - self.baseline[index - 1].y();
+                if peak.height > height_requirement {
+                    found_maximum = true;
+                }
+
+                if prev.y() > next.y() {
+                    peak.retention_point = *prev;
+                } else {
+                    peak.retention_point = *next;
+                }
+            }
+
+            let prev_drv2 = &self.second_derivative[index - 3];
+            let next_drv2 = &self.second_derivative[index - 2];
+
+            let difference = f64::abs(prev_drv2.y() - next_drv2.y());
+
+            if difference < inflection_requirement || height < height_requirement {
+                continue;
+            }
+
+            let rising_zero = prev_drv2.y() <= 0.0 && next_drv2.y() >= 0.0;
+
+            if rising_zero && prev_drv.y() >= 0.0 {
+                peak.height = next.y() - self.baseline[index].y();
+                peak.retention_point = *next;
+                peak.end = *next;
+                result.push(peak);
+
+                peak = Peak::default();
+                peak.start = *next;
+            }
+
+            let falling_zero = prev_drv2.y() >= 0.0 && next_drv2.y() <= 0.0;
+
+            if falling_zero && prev_drv.y() <= 0.0 {
+                peak.end = *next;
+                result.push(peak);
+
+                peak = Peak::default();
+                peak.start = *next;
+                peak.retention_point = *next;
+                peak.height = next.y() - self.baseline[index].y();
+            }
+        }
+
+        apply_events(
+            &mut result,
+            &self.cleaned_data,
+            &self.baseline,
+            &self.integration.events,
+        );
+
+        apply_edits(
+            &mut result,
+            &self.cleaned_data,
+            &self.baseline,
+            &self.peak_edits,
+        );
+
+        integrate(
+            &mut result,
+            &self.cleaned_data,
+            &self.baseline,
+            &self.integration,
+        );
+
+        deconvolve(
+            &mut result,
+            &self.cleaned_data,
+            &self.baseline,
+            self.peak_model,
+        );
+
+        calculate_metrics(&mut result, &self.cleaned_data, &self.baseline, self.noise);
+
+        result
+    }
+
+    /// Flags the peaks which are also found in the blank as system peaks.
+    fn flag_system_peaks(&mut self) {
+        // Compared in GU like the references whenever the sample set has a dex ladder
+        let spline = self.glucose_transformer.as_ref();
+        let tolerance = self.get_location_tolerance();
+        let blank_locations: Vec<f64> = self
+            .blank_components
+            .iter()
+            .filter_map(|blank| blank.get_retention_location(spline))
+            .collect();
+        for peak in self.existing_components.iter_mut() {
+            peak.system = peak.get_retention_location(spline).is_some_and(|location| {
+                blank_locations
+                    .iter()
+                    .any(|blank| (blank - location).abs() <= tolerance)
+            });
+        }
+    }
+
+    fn identify_components(&mut self) -> Vec<Component> {
+        self.flag_system_peaks();
+
+        // We need to create 3 lists here
+        // * Only components present in the reference (Located + Reference)
+        // * Only components present in the sample but with metadata attached (Located + Unknown)
+        // * All components we could possibly include (Unknown + Located + Reference)
+
+        if self.existing_components.is_empty() && self.lipid_references.is_empty() {
+            return vec![];
+        }
+
+        if self.lipid_references.is_empty() {
+            return self
+                .existing_components
+                .iter()
+                .map(|peak| Component::Unknown(peak.clone()))
+                .collect();
+        }
+
+        if self.existing_components.is_empty() {
+            return self
+                .lipid_references
+                .iter()
+                .map(|reference| Component::Reference(reference.clone()))
+                .collect();
+        }
+
+        let tolerance = self.get_location_tolerance();
+
+        // Manual labels take their peak and reference out of the automatic assignment
+        let mut manual: Vec<(usize, Option<&Reference>)> = vec![];
+        for label_override in &self.label_overrides {
+            let Some(index) = peak_at(&self.existing_components, label_override.retention_time)
+            else {
+                continue;
+            };
+
+            let reference = match &label_override.label {
+                None => None,
+                Some(name) => match self
+                    .lipid_references
+                    .iter()
+                    .find(|reference| reference.name.as_ref() == Some(name))
+                {
+                    Some(reference) => Some(reference),
+                    None => continue,
+                },
+            };
+
+            manual.retain(|(other_index, other_reference)| {
+                let same_reference = match (reference, other_reference) {
+                    (Some(reference), Some(other)) => std::ptr::eq(reference, *other),
+                    _ => false,
+                };
+                *other_index != index && !same_reference
+            });
+            manual.push((index, reference));
+        }
+
+        let is_manual_reference = |reference: &Reference| {
+            manual
+                .iter()
+                .any(|(_, other)| other.is_some_and(|other| std::ptr::eq(other, reference)))
+        };
+
+        // System peaks come from the blank and can never be a lipid
+        let mut peaks: Vec<(&Peak, f64)> = self
+            .existing_components
+            .iter()
+            .enumerate()
+            .filter(|(index, peak)| !peak.system && !manual.iter().any(|(other, _)| other == index))
+            .map(|(_, peak)| peak)
+            .filter_map(|peak| {
+                peak.get_retention_location(self.glucose_transformer.as_ref())
+                    .map(|location| (peak, location))
+            })
+            .collect();
+        peaks.sort_by(|left, right| left.1.total_cmp(&right.1));
+
+        let mut references: Vec<(&Reference, f64)> = self
+            .lipid_references
+            .iter()
+            .filter(|reference| !is_manual_reference(reference))
+            .filter_map(|reference| {
+                reference
+                    .get_expected_location(self.glucose_transformer.as_ref())
+                    .map(|location| (reference, location))
+            })
+            .collect();
+        references.sort_by(|left, right| left.1.total_cmp(&right.1));
+
+        let expected: Vec<f64> = references.iter().map(|(_, location)| *location).collect();
+        let found: Vec<f64> = peaks.iter().map(|(_, location)| *location).collect();
+
+        // Competitors are counted among every candidate, including manually labelled ones
+        let spline = self.glucose_transformer.as_ref();
+        let mut all_expected: Vec<f64> = self
+            .lipid_references
+            .iter()
+            .filter_map(|reference| reference.get_expected_location(spline))
+            .collect();
+        all_expected.sort_by(f64::total_cmp);
+
+        let mut all_found: Vec<f64> = self
+            .existing_components
+            .iter()
+            .enumerate()
+            .filter(|(index, peak)| !peak.system || manual.iter().any(|(other, _)| other == index))
+            .filter_map(|(_, peak)| peak.get_retention_location(spline))
+            .collect();
+        all_found.sort_by(f64::total_cmp);
+
+        let mut located_components: Vec<(&Peak, &Reference)> = assign(&expected, &found, tolerance)
+            .into_iter()
+            .map(|(reference, peak)| (peaks[peak].0, references[reference].0))
+            .collect();
+
+        // Manual labels may cross the automatic ones
+        located_components.extend(manual.iter().filter_map(|(index, reference)| {
+            reference.map(|reference| (&self.existing_components[*index], reference))
+        }));
+        located_components.sort_by(|left, right| {
+            left.0
+                .retention_point
+                .x()
+                .total_cmp(&right.0.retention_point.x())
+        });
+
+        let located_references: Vec<&Reference> = located_components
+            .iter()
+            .map(|(_, reference)| *reference)
+            .collect();
+
+        // Time to iterate across 3 vectors at once
+        // It won't be pretty.
+        let mut complete_components = vec![];
+
+        let mut unknown_components = self
+            .existing_components
+            .iter()
+            .filter_map(|peak| {
+                peak.get_retention_location(self.glucose_transformer.as_ref())
+                    .map(|location| (peak, location))
+            })
+            .peekable();
+
+        let mut located_components = located_components
+            .iter()
+            .filter_map(|(peak, reference)| {
+                peak.get_retention_location(self.glucose_transformer.as_ref())
+                    .map(|location| (*peak, *reference, location))
+            })
+            .peekable();
+
+        // Located references are placed by their peak
+        let mut reference_components = self
+            .lipid_references
+            .iter()
+            .filter(|reference| {
+                !located_references
+                    .iter()
+                    .any(|located| std::ptr::eq(*located, *reference))
+            })
+            .filter_map(|reference| {
+                reference
+                    .get_expected_location(self.glucose_transformer.as_ref())
+                    .map(|location| (reference, location))
+            })
+            .peekable();
+
+        loop {
+            match (
+                unknown_components.peek(),
+                located_components.peek(),
+                reference_components.peek(),
+            ) {
+                (None, None, None) => break,
+                (None, None, Some((reference, _))) => {
+                    complete_components.push(Component::Reference((*reference).clone()));
+                    reference_components.next();
+                }
+                (None, Some((located_peak, located_reference, _)), None) => {
+                    complete_components.push(Component::Located(
+                        (*located_peak).clone(),
+                        (*located_reference).clone(),
+                    ));
+                    located_components.next();
+                }
+                (
+                    None,
+                    Some((located_peak, located_reference, located_location)),
+                    Some((reference, expected_location)),
+                ) => {
+                    if located_reference == reference {
+                        complete_components.push(Component::Located(
+                            (*located_peak).clone(),
+                            (*located_reference).clone(),
+                        ));
+                        located_components.next();
+                        reference_components.next();
+                        continue;
+                    }
+
+                    if located_location < expected_location {
+                        complete_components.push(Component::Located(
+                            (*located_peak).clone(),
+                            (*located_reference).clone(),
+                        ));
+                        located_components.next();
+                    } else {
+                        complete_components.push(Component::Reference((*reference).clone()));
+                        reference_components.next();
+                    }
+                }
+                (Some((unknown, _)), None, None) => {
+                    complete_components.push(Component::Unknown((*unknown).clone()));
+                    unknown_components.next();
+                }
+                (Some((unknown, unknown_location)), None, Some((reference, expected_location))) => {
+                    if unknown_location < expected_location {
+                        complete_components.push(Component::Unknown((*unknown).clone()));
+                        unknown_components.next();
+                    } else {
+                        complete_components.push(Component::Reference((*reference).clone()));
+                        reference_components.next();
+                    }
+                }
+                (
+                    Some((unknown, unknown_location)),
+                    Some((located_peak, located_reference, located_location)),
+                    None,
+                ) => {
+                    if located_peak == unknown {
+                        complete_components.push(Component::Located(
+                            (*located_peak).clone(),
+                            (*located_reference).clone(),
+                        ));
+                        located_components.next();
+                        unknown_components.next();
+                        continue;
+                    }
+
+                    if unknown_location < located_location {
+                        complete_components.push(Component::Unknown((*unknown).clone()));
+                        unknown_components.next();
+                    } else {
+                        complete_components.push(Component::Located(
+                            (*located_peak).clone(),
+                            (*located_reference).clone(),
+                        ));
+                        located_components.next();
+                    }
+                }
+                (
+                    Some((unknown, unknown_location)),
+                    Some((located_peak, located_reference, located_location)),
+                    Some((reference, expected_location)),
+                ) => {
+                    if located_peak == unknown && located_reference == reference {
+                        complete_components.push(Component::Located(
+                            (*located_peak).clone(),
+                            (*located_reference).clone(),
+                        ));
+                        located_components.next();
+                        unknown_components.next();
+                        reference_components.next();
+                        continue;
+                    }
+
+                    let merge_peaks = located_peak == unknown;
+                    let merge_references = located_reference == reference;
+
+                    if located_location <= unknown_location && located_location <= expected_location
+                    {
+                        complete_components.push(Component::Located(
+                            (*located_peak).clone(),
+                            (*located_reference).clone(),
+                        ));
+                        located_components.next();
+
+                        if merge_peaks {
+                            unknown_components.next();
+                        }
+
+                        if merge_references {
+                            reference_components.next();
+                        }
+
+                        continue;
+                    }
+
+                    if unknown_location < expected_location {
+                        complete_components.push(Component::Unknown((*unknown).clone()));
+                        unknown_components.next();
+                    } else {
+                        complete_components.push(Component::Reference((*reference).clone()));
+                        reference_components.next();
+                    }
+                }
+            }
+        }
+
+        for component in complete_components.iter_mut() {
+            match component {
+                Component::Located(peak, reference) => {
+                    let confidence = self.match_confidence(
+                        peak,
+                        reference,
+                        &all_expected,
+                        &all_found,
+                        tolerance,
+                    );
+                    peak.confidence = Some(confidence);
+                    peak.manual = manual.iter().any(|(index, _)| {
+                        self.existing_components[*index].retention_point == peak.retention_point
+                    });
+                }
+                Component::Unknown(peak) => {
+                    peak.manual = manual.iter().any(|(index, reference)| {
+                        reference.is_none()
+                            && self.existing_components[*index].retention_point
+                                == peak.retention_point
+                    });
+                }
+                Component::Reference(_) => {}
+            }
+        }
+
+        complete_components
+    }
+
+    /// Scores a located peak against its reference.
+    ///
+    /// `expected` and `found` are the sorted locations of every reference and peak, including the pair itself.
+    fn match_confidence(
+        &self,
+        peak: &Peak,
+        reference: &Reference,
+        expected: &[f64],
+        found: &[f64],
+        tolerance: f64,
+    ) -> MatchConfidence {
+        let spline = self.glucose_transformer.as_ref();
+        let retention_time = peak.retention_point.x();
+
+        let rt_deviation = reference
+            .get_expected_rt()
+            .map(|expected_rt| retention_time - expected_rt);
+        let gu_deviation = match (
+            spline.and_then(|spline| spline.evaluate(retention_time)),
+            reference.get_expected_gu(spline),
+        ) {
+            (Some(found_gu), Some(expected_gu)) => Some(found_gu - expected_gu),
+            _ => None,
+        };
+
+        let score = |deviation: f64, tolerance: f64| {
+            if tolerance > 0.0 {
+                (1.0 - deviation.abs() / tolerance).max(0.0)
+            } else if deviation == 0.0 {
+                1.0
+            } else {
+                0.0
+            }
+        };
+
+        let scores: Vec<f64> = [
+            rt_deviation.map(|deviation| score(deviation, self.retention_time_tolerance)),
+            gu_deviation.map(|deviation| score(deviation, self.glucose_unit_tolerance)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        // Number of sorted locations within tolerance of centre
+        let within = |locations: &[f64], centre: f64| {
+            let start = locations.partition_point(|location| *location < centre - tolerance);
+            let end = locations.partition_point(|location| *location <= centre + tolerance);
+            end - start
+        };
+
+        let peak_location = peak.get_retention_location(spline);
+        let reference_location = reference.get_expected_location(spline);
+
+        // The pair only counts itself if it lies within tolerance, manual labels may not
+        let own = match (peak_location, reference_location) {
+            (Some(lhs), Some(rhs)) => usize::from((lhs - rhs).abs() <= tolerance),
+            _ => 0,
+        };
+
+        let competing_references =
+            peak_location.map_or(0, |location| within(expected, location).saturating_sub(own));
+        let competing_peaks =
+            reference_location.map_or(0, |location| within(found, location).saturating_sub(own));
+
+        MatchConfidence {
+            score: if scores.is_empty() {
+                0.0
+            } else {
+                scores.iter().sum::<f64>() / scores.len() as f64
+            },
+            rt_deviation,
+            gu_deviation,
+            competing_references,
+            competing_peaks,
+        }
+    }
+}
+
+/// Peaks of the blank analysed like the sample, none if too little of it lies in the analysis range.
+fn calculate_blank_components(settings: &BlankSettings) -> Vec<Peak> {
+    let in_range = |point: &&Point2D| {
+        settings
+            .data_range
+            .as_ref()
+            .is_none_or(|range| range.contains(&point.x()))
+    };
+    if settings.data.iter().filter(in_range).count() <= PEAK_PIVOT {
+        return vec![];
+    }
+
+    let mut blank = Chromatography {
+        raw_data: settings.data.to_vec(),
+        sample_type: SampleType::Blank,
+        data_range: settings.data_range.clone(),
+        height_requirement: settings.height_requirement,
+        inflection_requirement: settings.inflection_requirement,
+        smoothing: settings.smoothing,
+        derivatives: settings.derivatives,
+        baseline_settings: settings.baseline_settings,
+        integration: settings.integration.clone(),
+        noise_settings: settings.noise_settings,
+        signal_to_noise_threshold: settings.signal_to_noise_threshold,
+        ..Default::default()
+    };
+
+    blank.update_data();
+    blank.existing_components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Gaussian peaks of `(time, height)` with a standard deviation of 0.1 minutes,
+    /// sampled every 0.01 minutes from 0 to 20 minutes.
+    fn signal(peaks: &[(f64, f64)]) -> Vec<Point2D> {
+        (0..=2000)
+            .map(|i| {
+                let time = i as f64 / 100.0;
+                let height: f64 = peaks
+                    .iter()
+                    .map(|(centre, height)| height * (-0.5 * ((time - centre) / 0.1).powi(2)).exp())
+                    .sum();
+                Point2D::new(time, height)
+            })
+            .collect()
+    }
+
+    fn sample(data: Vec<Point2D>) -> Chromatography {
+        let channel = Channel {
+            name: String::from("Signal"),
+            unit: None,
+            data,
+        };
+
+        let mut sample = Chromatography::from_channels(
+            &"synthetic.csv",
+            DataFormat::Delimited,
+            SampleMetadata::default(),
+            vec![channel],
+        );
+        sample
+            .set_height_requirement(&1.0)
+            .set_inflection_requirement(&f64::INFINITY)
+            .set_retention_time_tolerance(&0.5);
+        sample
+    }
+
+    /// References of `(name, retention time)`, read from a file like the user's.
+    fn references(entries: &[(&str, f64)]) -> Rc<[Reference]> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "hplc_core_{}_{}.csv",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let mut content = String::from("Name,RT\n");
+        for (name, time) in entries {
+            content.push_str(&format!("{},{}\n", name, time));
+        }
+        fs::write(&path, content).unwrap();
+        let references = Reference::parse_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        Rc::from(references)
+    }
+
+    fn label(sample: &Chromatography, time: f64) -> Option<String> {
+        match sample.get_component_at(time)? {
+            Component::Located(_, reference) => reference.name.clone(),
+            _ => None,
+        }
+    }
+
+    fn confidence(sample: &Chromatography, time: f64) -> MatchConfidence {
+        *sample
+            .get_component_at(time)
+            .and_then(Component::get_confidence)
+            .expect("peak should be located")
+    }
+
+    #[test]
+    fn manual_label_competitors() {
+        let mut sample = sample(signal(&[(5.0, 100.0), (5.6, 100.0), (10.0, 100.0)]));
+        sample.set_lipid_references(references(&[("A", 5.0), ("B", 5.3), ("C", 10.0)]));
+        sample.set_manual_label(&5.0, Some(String::from("B")));
+
+        // A is within tolerance of the peak and the peak at 5.6 within tolerance of B
+        assert_eq!(label(&sample, 5.0).as_deref(), Some("B"));
+        let manual = confidence(&sample, 5.0);
+        assert_eq!(manual.competing_references, 1);
+        assert_eq!(manual.competing_peaks, 1);
+        assert!(manual.is_ambiguous());
+    }
+
+    fn labelled() -> Chromatography {
+        let mut sample = sample(signal(&[(5.0, 100.0), (7.0, 100.0)]));
+        sample.set_lipid_references(references(&[("A", 5.0), ("B", 7.0)]));
+        sample
+    }
+
+    #[test]
+    fn manual_label_moves_reference() {
+        let mut sample = labelled();
+        assert_eq!(label(&sample, 5.0).as_deref(), Some("A"));
+        assert_eq!(label(&sample, 7.0).as_deref(), Some("B"));
+
+        sample.set_manual_label(&7.0, Some(String::from("A")));
+
+        assert_eq!(label(&sample, 7.0).as_deref(), Some("A"));
+        assert!(sample.get_component_at(7.0).unwrap().is_manual());
+        assert!(matches!(
+            sample.get_component_at(5.0),
+            Some(Component::Unknown(_))
+        ));
+
+        // B lost its peak to the label
+        let references = references(&[("B", 7.0)]);
+        assert!(matches!(
+            sample.get_reference_component(&references[0]),
+            Some(Component::Reference(_))
+        ));
+    }
+
+    #[test]
+    fn manual_label_survives_parameters() {
+        let mut sample = labelled();
+        sample.set_manual_label(&7.0, Some(String::from("A")));
+
+        sample
+            .set_retention_time_tolerance(&0.1)
+            .set_height_requirement(&2.0);
+
+        assert_eq!(label(&sample, 7.0).as_deref(), Some("A"));
+        assert_eq!(sample.get_label_overrides().len(), 1);
+    }
+
+    #[test]
+    fn manual_unknown() {
+        let mut sample = labelled();
+        sample.set_manual_label(&5.0, None);
+
+        let component = sample.get_component_at(5.0).unwrap();
+        assert!(matches!(component, Component::Unknown(_)));
+        assert!(component.is_manual());
+        assert_eq!(label(&sample, 7.0).as_deref(), Some("B"));
+    }
+
+    #[test]
+    fn clear_manual_label() {
+        let mut sample = labelled();
+        sample.set_manual_label(&7.0, Some(String::from("A")));
+        sample.clear_manual_label(&7.0);
+
+        assert!(sample.get_label_overrides().is_empty());
+        assert_eq!(label(&sample, 5.0).as_deref(), Some("A"));
+        assert_eq!(label(&sample, 7.0).as_deref(), Some("B"));
+        assert!(!sample.get_component_at(7.0).unwrap().is_manual());
+    }
+
+    #[test]
+    fn relabel_reference() {
+        let mut sample = labelled();
+        sample.set_manual_label(&7.0, Some(String::from("A")));
+        sample.set_manual_label(&5.0, Some(String::from("A")));
+
+        assert_eq!(sample.get_label_overrides().len(), 1);
+        assert_eq!(sample.get_label_overrides()[0].retention_time, 5.0);
+        assert_eq!(label(&sample, 5.0).as_deref(), Some("A"));
+        assert_eq!(label(&sample, 7.0).as_deref(), Some("B"));
+        assert!(!sample.get_component_at(7.0).unwrap().is_manual());
+    }
+
+    #[test]
+    fn confidence_score() {
+        let mut sample = sample(signal(&[(5.0, 100.0), (10.0, 100.0)]));
+        sample.set_lipid_references(references(&[("A", 5.0), ("B", 10.25)]));
+
+        let exact = confidence(&sample, 5.0);
+        assert!((exact.score - 1.0).abs() < 1e-9);
+        assert!(exact.rt_deviation.unwrap().abs() < 1e-9);
+        assert!(!exact.is_ambiguous());
+
+        let halfway = confidence(&sample, 10.0);
+        assert!((halfway.score - 0.5).abs() < 1e-9);
+        assert!((halfway.rt_deviation.unwrap() + 0.25).abs() < 1e-9);
+
+        // Forced out to the tolerance edge
+        sample.set_lipid_references(references(&[("A", 5.5)]));
+        sample.set_manual_label(&5.0, Some(String::from("A")));
+        assert!(confidence(&sample, 5.0).score.abs() < 1e-9);
+    }
+
+    #[test]
+    fn confidence_zero_tolerance() {
+        let mut sample = sample(signal(&[(5.0, 100.0), (10.0, 100.0)]));
+        sample.set_retention_time_tolerance(&0.0);
+        sample.set_lipid_references(references(&[("A", 5.0), ("B", 10.1)]));
+
+        assert_eq!(label(&sample, 5.0).as_deref(), Some("A"));
+        assert_eq!(confidence(&sample, 5.0).score, 1.0);
+        assert_eq!(label(&sample, 10.0), None);
+
+        sample.set_manual_label(&10.0, Some(String::from("B")));
+        let forced = confidence(&sample, 10.0);
+        assert_eq!(forced.score, 0.0);
+        assert!(!forced.is_ambiguous());
+    }
+
+    #[test]
+    fn confidence_competing_references() {
+        let mut sample = sample(signal(&[(5.0, 100.0)]));
+        sample.set_lipid_references(references(&[("A", 5.0), ("B", 5.2)]));
+
+        assert_eq!(label(&sample, 5.0).as_deref(), Some("A"));
+        let confidence = confidence(&sample, 5.0);
+        assert_eq!(confidence.competing_references, 1);
+        assert_eq!(confidence.competing_peaks, 0);
+        assert!(confidence.is_ambiguous());
+    }
+
+    #[test]
+    fn confidence_competing_peaks() {
+        let mut sample = sample(signal(&[(5.0, 100.0), (5.4, 100.0)]));
+        sample.set_lipid_references(references(&[("A", 5.0)]));
+
+        assert_eq!(label(&sample, 5.0).as_deref(), Some("A"));
+        let confidence = confidence(&sample, 5.0);
+        assert_eq!(confidence.competing_references, 0);
+        assert_eq!(confidence.competing_peaks, 1);
+        assert!(confidence.is_ambiguous());
+    }
+
+    #[test]
+    fn blank_interpolated() {
+        // Sample every 0.01 minutes, blank only every 0.5 minutes
+        let line = |step: f64, count: usize, slope: f64| -> Vec<Point2D> {
+            (0..=count)
+                .map(|i| Point2D::new(i as f64 * step, 10.0 + slope * i as f64 * step))
+                .collect()
+        };
+        let mut sample = sample(line(0.01, 2000, 3.0));
+        sample
+            .set_blank(Some(Rc::from(line(0.5, 40, 1.0))))
+            .set_subtract_blank(&true);
+
+        let data = sample.get_data();
+        assert!(!data.is_empty());
+        for point in data {
+            assert!((point.y() - 2.0 * point.x()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn blank_system_peak() {
+        let mut sample = sample(signal(&[(5.0, 100.0), (10.0, 100.0)]));
+        sample.set_lipid_references(references(&[("A", 5.0), ("B", 10.0)]));
+        sample.set_blank(Some(Rc::from(signal(&[(5.05, 50.0)]))));
+
+        let system = sample.get_component_at(5.0).unwrap();
+        assert!(matches!(system, Component::Unknown(_)));
+        assert!(system.is_system_peak());
+        assert_eq!(label(&sample, 10.0).as_deref(), Some("B"));
+        assert!(!sample.get_component_at(10.0).unwrap().is_system_peak());
+
+        // Out of tolerance of the blank peak it is a lipid again
+        sample.set_retention_time_tolerance(&0.01);
+        assert!(!sample.get_component_at(5.0).unwrap().is_system_peak());
+        assert_eq!(label(&sample, 5.0).as_deref(), Some("A"));
+    }
+
+    #[test]
+    fn blank_only_subtracted_from_data() {
+        let blank: Rc<[Point2D]> = Rc::from(signal(&[(5.0, 50.0)]));
+        for sample_type in [SampleType::Blank, SampleType::Dex, SampleType::Standard] {
+            let mut sample = sample(signal(&[(5.0, 100.0)]));
+            sample.set_sample_type(&sample_type);
+            let expected = sample.get_data();
+
+            sample
+                .set_blank(Some(blank.clone()))
+                .set_subtract_blank(&true);
+            assert_eq!(sample.get_data(), expected, "{:?}", sample_type);
+        }
+
+        let mut sample = sample(signal(&[(5.0, 100.0)]));
+        let expected = sample.get_data();
+        sample.set_blank(Some(blank)).set_subtract_blank(&true);
+        assert_ne!(sample.get_data(), expected);
+    }
+
+    #[test]
+    fn signal_to_noise_requirement() {
+        let peaks = [(5.0, 100.0), (10.0, 0.5)];
+
+        // Without noise the requirement stays absolute
+        let flat: Vec<Point2D> = signal(&peaks)
+            .into_iter()
+            .map(|point| Point2D::new(point.x(), if point.y() < 1e-6 { 0.0 } else { point.y() }))
+            .collect();
+        let mut noiseless = sample(flat);
+        noiseless.set_signal_to_noise_threshold(&true);
+        assert_eq!(noiseless.get_noise(), 0.0);
+        assert_eq!(noiseless.get_unqualified_components().len(), 1);
+
+        let noisy: Vec<Point2D> = signal(&peaks)
+            .into_iter()
+            .enumerate()
+            .map(|(i, point)| {
+                let offset = [0.05, 0.0, -0.05, 0.0][i % 4];
+                Point2D::new(point.x(), point.y() + offset)
+            })
+            .collect();
+        let mut noisy = sample(noisy);
+        noisy.set_height_requirement(&3.0);
+        assert_eq!(noisy.get_unqualified_components().len(), 1);
+
+        noisy.set_signal_to_noise_threshold(&true);
+        assert!(noisy.get_noise() > 0.0 && noisy.get_noise() < 0.5 / 3.0);
+        assert_eq!(noisy.get_unqualified_components().len(), 2);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{
+    deconvolution::PeakFit, metrics::PeakMetrics, reference::Reference, spline::Spline, vector::*,
+};
+
+/// A peak found in the data, points are (time, height) pairs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Peak {
+    pub start: Point2D,
+    pub retention_point: Point2D,
+    pub end: Point2D,
+    /// Height of the retention point above the baseline.
+    pub height: f64,
+    /// Area between start and end above the baseline.
+    pub area: f64,
+    /// Whether the peak is also present in the blank.
+    pub system: bool,
+    /// Baseline the peak was integrated against, from start to end.
+    pub baseline: Vec<Point2D>,
+    /// Fitted shape if the peak overlaps its neighbours and deconvolution is enabled.
+    pub fit: Option<PeakFit>,
+    /// Width, shape and separation figures used for column performance monitoring.
+    pub metrics: PeakMetrics,
+    /// How well the peak matches its reference, only set for located peaks.
+    pub confidence: Option<MatchConfidence>,
+    /// Whether the label of the peak was chosen by hand, see [`LabelOverride`](crate::label::LabelOverride).
+    pub manual: bool,
+}
+
+/// How well a located peak matches its reference.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MatchConfidence {
+    /// 1 for an exact match falling to 0 at the tolerance, averaged over RT and GU when both are known.
+    pub score: f64,
+    /// Found minus expected retention time, if the reference has one.
+    pub rt_deviation: Option<f64>,
+    /// Found minus expected GU, if a dex ladder is known.
+    pub gu_deviation: Option<f64>,
+    /// Other references within tolerance of the peak.
+    pub competing_references: usize,
+    /// Other peaks within tolerance of the reference.
+    pub competing_peaks: usize,
+}
+
+impl MatchConfidence {
+    /// Whether another reference or another peak could also have been matched.
+    pub fn is_ambiguous(&self) -> bool {
+        self.competing_references > 0 || self.competing_peaks > 0
+    }
+}
+
+impl Peak {
+    /// Retention time of the peak, or its GU if a spline is given.
+    pub fn get_retention_location(&self, spline: Option<&Spline>) -> Option<f64> {
+        match spline {
+            None => Some(self.retention_point.x()),
+            Some(spline) => spline.evaluate(self.retention_point.x()),
+        }
+    }
+}
+
+/// A peak, a reference, or a peak which has been identified as a reference.
+#[derive(Clone, Debug)]
+pub enum Component {
+    Unknown(Peak),
+    Located(Peak, Reference),
+    Reference(Reference),
+}
+
+impl Component {
+    /// Retention time at which the component was found.
+    pub fn get_experimental_rt(&self) -> Option<f64> {
+        match self {
+            Component::Unknown(peak) => Some(peak.retention_point.x()),
+            Component::Located(peak, _) => Some(peak.retention_point.x()),
+            Component::Reference(_) => None,
+        }
+    }
+
+    /// Retention time at which the reference expects the component.
+    pub fn get_expected_rt(&self) -> Option<f64> {
+        match self {
+            Component::Unknown(_) => None,
+            Component::Located(_, reference) => reference.get_expected_rt(),
+            Component::Reference(reference) => reference.get_expected_rt(),
+        }
+    }
+
+    /// GU at which the component was found.
+    pub fn get_experimental_gu(&self, maybe_spline: Option<&Spline>) -> Option<f64> {
+        match self {
+            Component::Unknown(peak) => {
+                maybe_spline.and_then(|spline| spline.evaluate(peak.retention_point.x()))
+            }
+            Component::Located(peak, _) => {
+                maybe_spline.and_then(|spline| spline.evaluate(peak.retention_point.x()))
+            }
+            Component::Reference(_) => None,
+        }
+    }
+
+    /// GU at which the reference expects the component.
+    pub fn get_expected_gu(&self, spline: Option<&Spline>) -> Option<f64> {
+        match self {
+            Component::Unknown(_) => None,
+            Component::Located(_, reference) => reference.get_expected_gu(spline),
+            Component::Reference(reference) => reference.get_expected_gu(spline),
+        }
+    }
+
+    /// The peak found in the data, `None` for references which were not found.
+    pub fn get_peak(&self) -> Option<&Peak> {
+        match self {
+            Component::Unknown(peak) => Some(peak),
+            Component::Located(peak, _) => Some(peak),
+            Component::Reference(_) => None,
+        }
+    }
+
+    /// Match confidence of a located component.
+    pub fn get_confidence(&self) -> Option<&MatchConfidence> {
+        match self {
+            Component::Located(peak, _) => peak.confidence.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the label of the component was chosen by hand.
+    pub fn is_manual(&self) -> bool {
+        self.get_peak().is_some_and(|peak| peak.manual)
+    }
+
+    /// Area of the component above the baseline.
+    pub fn get_area(&self) -> Option<f64> {
+        match self {
+            Component::Unknown(peak) => Some(peak.area),
+            Component::Located(peak, _) => Some(peak.area),
+            Component::Reference(_) => None,
+        }
+    }
+
+    /// Whether the component is a peak which is also present in the blank.
+    pub fn is_system_peak(&self) -> bool {
+        match self {
+            Component::Unknown(peak) => peak.system,
+            Component::Located(peak, _) => peak.system,
+            Component::Reference(_) => false,
+        }
+    }
+
+    /// Label drawn next to the peak on a chart, `None` for references which were not found.
+    pub fn point_label(&self, spline: Option<&Spline>) -> Option<String> {
+        let mut builder = String::from("[");
+        match &self {
+            Component::Unknown(peak) => {
+                // [Unknown, {}, {}] = rt, gu
+                if peak.system {
+                    builder.push_str("System, ");
+                } else if peak.manual {
+                    builder.push_str("Unknown (manual), ");
+                } else {
+                    builder.push_str("Unknown, ");
+                }
+                builder.push_str(&format!("{:.3}", peak.retention_point.x()));
+
+                if let Some(gu) =
+                    spline.and_then(|spline| spline.evaluate(peak.retention_point.x()))
+                {
+                    builder.push_str(&format!(", {:.3}", gu));
+                }
+            }
+            Component::Located(peak, reference) => {
+                // [{}, {}, {}] = name, rt, gu
+                if let Some(name) = &reference.name {
+                    builder.push_str(&format!("{}, ", name));
+                } else {
+                    builder.push_str("Unknown, ");
+                }
+
+                builder.push_str(&format!("{:.3}", peak.retention_point.x()));
+
+                if let Some(gu) =
+                    spline.and_then(|spline| spline.evaluate(peak.retention_point.x()))
+                {
+                    builder.push_str(&format!(", {:.3}", gu));
+                }
+
+                // [{}, {}, {}, {}%, ambiguous] = name, rt, gu, confidence
+                if peak.manual {
+                    builder.push_str(", manual");
+                } else if let Some(confidence) = &peak.confidence {
+                    builder.push_str(&format!(", {:.0}%", confidence.score * 100.0));
+                    if confidence.is_ambiguous() {
+                        builder.push_str(", ambiguous");
+                    }
+                }
+            }
+            Component::Reference(_) => return None,
+        }
+
+        builder.push(']');
+        Some(builder)
+    }
+}
@@ -0,0 +1,647 @@
+use std::f64::consts::{PI, SQRT_2};
+use std::fmt::{self, Display};
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::component::Peak;
+use crate::linear::solve_matrix;
+use crate::vector::{Point2D, Vector2};
+
+/// Shape fitted to every peak of an overlapping cluster.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PeakModel {
+    /// Peaks are not fitted, areas come from integration only.
+    #[default]
+    None,
+    /// Symmetric peak described by its area, centre and standard deviation.
+    Gaussian,
+    /// Gaussian convolved with an exponential decay, for peaks which tail.
+    ExponentiallyModifiedGaussian,
+    /// Gaussian with a different standard deviation either side of the apex.
+    BiGaussian,
+}
+
+impl PeakModel {
+    pub const ALL: [PeakModel; 4] = [
+        PeakModel::None,
+        PeakModel::Gaussian,
+        PeakModel::ExponentiallyModifiedGaussian,
+        PeakModel::BiGaussian,
+    ];
+
+    /// Number of parameters of a single peak.
+    fn parameters(&self) -> usize {
+        match self {
+            PeakModel::None => 0,
+            PeakModel::Gaussian => 3,
+            PeakModel::ExponentiallyModifiedGaussian | PeakModel::BiGaussian => 4,
+        }
+    }
+
+    /// Height of a single peak at `time`, parameters start with the area and centre.
+    fn evaluate(&self, parameters: &[f64], time: f64) -> f64 {
+        let (area, centre) = (parameters[0], parameters[1]);
+        let offset = time - centre;
+
+        match self {
+            PeakModel::None => 0.0,
+            PeakModel::Gaussian => {
+                let sigma = parameters[2];
+                area / (sigma * (2.0 * PI).sqrt()) * (-0.5 * (offset / sigma).powi(2)).exp()
+            }
+            PeakModel::BiGaussian => {
+                let (left, right) = (parameters[2], parameters[3]);
+                let sigma = if offset < 0.0 { left } else { right };
+                let scale = 2.0 / ((left + right) * (2.0 * PI).sqrt());
+                area * scale * (-0.5 * (offset / sigma).powi(2)).exp()
+            }
+            PeakModel::ExponentiallyModifiedGaussian => {
+                let (sigma, tau) = (parameters[2], parameters[3]);
+                let z = (sigma / tau - offset / sigma) / SQRT_2;
+
+                // Two forms of the same expression, each free of overflow on its side of z = 0
+                if z < 0.0 {
+                    let exponent = 0.5 * (sigma / tau).powi(2) - offset / tau;
+                    area / (2.0 * tau) * exponent.exp() * (2.0 - erfcx(-z) * (-z * z).exp())
+                } else {
+                    let gaussian = (-0.5 * (offset / sigma).powi(2)).exp();
+                    area / (2.0 * tau) * gaussian * erfcx(z)
+                }
+            }
+        }
+    }
+}
+
+impl Display for PeakModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PeakModel::None => "None",
+            PeakModel::Gaussian => "Gaussian",
+            PeakModel::ExponentiallyModifiedGaussian => "EMG",
+            PeakModel::BiGaussian => "Bi-Gaussian",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for PeakModel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Ok(PeakModel::None),
+            "gaussian" => Ok(PeakModel::Gaussian),
+            "emg" | "exponentially-modified-gaussian" => {
+                Ok(PeakModel::ExponentiallyModifiedGaussian)
+            }
+            "bi-gaussian" | "bigaussian" => Ok(PeakModel::BiGaussian),
+            other => Err(format!("Unknown peak model {}", other)),
+        }
+    }
+}
+
+/// Result of fitting a peak as part of its cluster.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeakFit {
+    pub model: PeakModel,
+    /// Area under the fitted peak, including the parts hidden under its neighbours.
+    pub area: f64,
+    /// Time of the apex of the fitted peak.
+    pub retention_time: f64,
+    /// Full width at half maximum of the fitted peak.
+    pub width: f64,
+    /// Root mean square difference between the data and the sum of the cluster's fitted peaks.
+    pub residual: f64,
+    /// Fitted peak on top of the baseline, across the whole cluster.
+    pub curve: Vec<Point2D>,
+}
+
+// Valleys lower than this fraction of the smaller neighbour separate clusters
+const RESOLVED_VALLEY: f64 = 0.05;
+// Larger clusters are split at their deepest valley, the cost of a fit grows with the cube of its size
+const MAX_CLUSTER: usize = 6;
+const MAX_ITERATIONS: usize = 200;
+
+/// Fits every cluster of overlapping peaks with the sum of one `model` per peak.
+///
+/// Fitted peaks receive a [`PeakFit`] and their area is replaced with the area of the fitted peak,
+/// peaks which are well resolved or whose cluster fails to converge are left untouched.
+pub(crate) fn deconvolve(
+    peaks: &mut [Peak],
+    data: &[Point2D],
+    baseline: &[Point2D],
+    model: PeakModel,
+) {
+    if model == PeakModel::None || data.len() < 2 {
+        return;
+    }
+
+    // Depth of the valley before every peak relative to its neighbours, None if they are resolved
+    let valleys: Vec<Option<f64>> = (0..peaks.len())
+        .map(|i| {
+            if i == 0 {
+                return None;
+            }
+
+            let (lhs, rhs) = (&peaks[i - 1], &peaks[i]);
+            if lhs.end.x() != rhs.start.x() {
+                return None;
+            }
+
+            let index = data
+                .partition_point(|point| point.x() < rhs.start.x())
+                .min(data.len() - 1);
+            let valley = (data[index].y() - baseline[index].y()) / lhs.height.min(rhs.height);
+
+            (valley > RESOLVED_VALLEY).then_some(valley)
+        })
+        .collect();
+
+    let mut clusters = vec![];
+    let mut first = 0;
+    for i in 1..=peaks.len() {
+        if i == peaks.len() || valleys[i].is_none() {
+            split_cluster(first..i, &valleys, &mut clusters);
+            first = i;
+        }
+    }
+
+    for cluster in clusters {
+        if cluster.len() > 1 {
+            fit_cluster(&mut peaks[cluster], data, baseline, model);
+        }
+    }
+}
+
+fn split_cluster(cluster: Range<usize>, valleys: &[Option<f64>], clusters: &mut Vec<Range<usize>>) {
+    if cluster.len() <= MAX_CLUSTER {
+        clusters.push(cluster);
+        return;
+    }
+
+    let depth = |i: &usize| valleys[*i].unwrap_or(0.0);
+    let deepest = (cluster.start + 1..cluster.end)
+        .min_by(|lhs, rhs| depth(lhs).total_cmp(&depth(rhs)))
+        .unwrap_or(cluster.start + MAX_CLUSTER);
+
+    split_cluster(cluster.start..deepest, valleys, clusters);
+    split_cluster(deepest..cluster.end, valleys, clusters);
+}
+
+fn fit_cluster(peaks: &mut [Peak], data: &[Point2D], baseline: &[Point2D], model: PeakModel) {
+    let index = |x: f64| {
+        data.partition_point(|point| point.x() < x)
+            .min(data.len() - 1)
+    };
+    let start = index(peaks[0].start.x());
+    let end = index(peaks[peaks.len() - 1].end.x());
+
+    let times: Vec<f64> = data[start..=end].iter().map(|point| point.x()).collect();
+    let values: Vec<f64> = (start..=end)
+        .map(|i| data[i].y() - baseline[i].y())
+        .collect();
+
+    if times.len() <= model.parameters() * peaks.len() {
+        return;
+    }
+
+    let spacing = (times[times.len() - 1] - times[0]) / (times.len() - 1) as f64;
+    let limits = Limits {
+        from: times[0],
+        to: times[times.len() - 1],
+        min_width: spacing / 2.0,
+    };
+
+    let mut initial = Vec::with_capacity(model.parameters() * peaks.len());
+    for peak in peaks.iter() {
+        let area = peak.area.max(f64::EPSILON);
+        let sigma = (area / (peak.height.max(f64::EPSILON) * (2.0 * PI).sqrt()))
+            .clamp(spacing, (limits.to - limits.from) / 2.0);
+
+        initial.extend([area, peak.retention_point.x()]);
+        match model {
+            PeakModel::None => {}
+            PeakModel::Gaussian => initial.push(sigma),
+            PeakModel::BiGaussian => initial.extend([sigma, sigma]),
+            PeakModel::ExponentiallyModifiedGaussian => initial.extend([sigma, sigma / 2.0]),
+        }
+    }
+
+    let Some((parameters, error)) = levenberg_marquardt(model, &times, &values, initial, &limits)
+    else {
+        return;
+    };
+
+    let residual = (error / times.len() as f64).sqrt();
+    let size = model.parameters();
+    for (peak, parameters) in peaks.iter_mut().zip(parameters.chunks(size)) {
+        let curve = (start..=end)
+            .map(|i| {
+                Point2D::new(
+                    data[i].x(),
+                    baseline[i].y() + model.evaluate(parameters, data[i].x()),
+                )
+            })
+            .collect();
+
+        let (retention_time, width) = shape(model, parameters, &limits);
+        peak.area = parameters[0];
+        peak.fit = Some(PeakFit {
+            model,
+            area: parameters[0],
+            retention_time,
+            width,
+            residual,
+            curve,
+        });
+    }
+}
+
+struct Limits {
+    from: f64,
+    to: f64,
+    min_width: f64,
+}
+
+impl Limits {
+    /// Keeps areas positive, centres within the cluster and widths above half the spacing.
+    fn clamp(&self, parameters: &mut [f64], size: usize) {
+        for peak in parameters.chunks_mut(size) {
+            peak[0] = peak[0].max(f64::EPSILON);
+            peak[1] = peak[1].clamp(self.from, self.to);
+            for width in &mut peak[2..] {
+                *width = width.clamp(self.min_width, self.to - self.from);
+            }
+        }
+    }
+}
+
+/// Sum of squared residuals of the cluster.
+fn squared_error(model: PeakModel, times: &[f64], values: &[f64], parameters: &[f64]) -> f64 {
+    times
+        .iter()
+        .zip(values)
+        .map(|(time, value)| {
+            let fitted: f64 = parameters
+                .chunks(model.parameters())
+                .map(|peak| model.evaluate(peak, *time))
+                .sum();
+            (value - fitted).powi(2)
+        })
+        .sum()
+}
+
+/// Nonlinear least squares fit of the sum of peaks, returns the parameters and the squared error.
+fn levenberg_marquardt(
+    model: PeakModel,
+    times: &[f64],
+    values: &[f64],
+    mut parameters: Vec<f64>,
+    limits: &Limits,
+) -> Option<(Vec<f64>, f64)> {
+    let size = model.parameters();
+    let count = parameters.len();
+    limits.clamp(&mut parameters, size);
+
+    let mut error = squared_error(model, times, values, &parameters);
+    let mut damping = 1e-3;
+
+    for _ in 0..MAX_ITERATIONS {
+        // Residuals and numerical jacobian, each parameter only affects its own peak
+        let mut residuals = Vec::with_capacity(times.len());
+        let mut jacobian = vec![vec![0.0; count]; times.len()];
+        for (row, (time, value)) in times.iter().zip(values).enumerate() {
+            let mut fitted = 0.0;
+            for (peak, chunk) in parameters.chunks(size).enumerate() {
+                let base = model.evaluate(chunk, *time);
+                fitted += base;
+
+                let mut shifted = chunk.to_vec();
+                for j in 0..size {
+                    let step = 1e-6 * chunk[j].abs().max(limits.min_width);
+                    shifted[j] = chunk[j] + step;
+                    jacobian[row][peak * size + j] =
+                        (model.evaluate(&shifted, *time) - base) / step;
+                    shifted[j] = chunk[j];
+                }
+            }
+            residuals.push(value - fitted);
+        }
+
+        // Normal equations, scaled so the diagonal is 1 before damping
+        let mut normal = vec![vec![0.0; count]; count];
+        let mut gradient = vec![0.0; count];
+        for (row, residual) in jacobian.iter().zip(&residuals) {
+            for i in 0..count {
+                gradient[i] += row[i] * residual;
+                for j in 0..count {
+                    normal[i][j] += row[i] * row[j];
+                }
+            }
+        }
+
+        let scale: Vec<f64> = (0..count)
+            .map(|i| normal[i][i].sqrt().max(f64::MIN_POSITIVE))
+            .collect();
+
+        let mut improved = false;
+        while damping < 1e10 {
+            let mut matrix: Vec<Vec<f64>> = (0..count)
+                .map(|i| {
+                    (0..count)
+                        .map(|j| {
+                            let value = normal[i][j] / (scale[i] * scale[j]);
+                            if i == j { value + damping } else { value }
+                        })
+                        .collect()
+                })
+                .collect();
+            let mut rhs: Vec<f64> = (0..count).map(|i| gradient[i] / scale[i]).collect();
+
+            let Some(step) = solve_matrix(&mut matrix, &mut rhs) else {
+                damping *= 10.0;
+                continue;
+            };
+
+            let mut candidate: Vec<f64> = (0..count)
+                .map(|i| parameters[i] + step[i] / scale[i])
+                .collect();
+            limits.clamp(&mut candidate, size);
+
+            let candidate_error = squared_error(model, times, values, &candidate);
+            if candidate_error.is_finite() && candidate_error < error {
+                let converged = (error - candidate_error) <= 1e-10 * error;
+                parameters = candidate;
+                error = candidate_error;
+                damping = (damping / 10.0).max(1e-12);
+                improved = !converged;
+                break;
+            }
+
+            damping *= 10.0;
+        }
+
+        if !improved {
+            break;
+        }
+    }
+
+    if parameters.iter().all(|value| value.is_finite()) && error.is_finite() {
+        Some((parameters, error))
+    } else {
+        None
+    }
+}
+
+/// Apex and full width at half maximum of a single fitted peak.
+fn shape(model: PeakModel, parameters: &[f64], limits: &Limits) -> (f64, f64) {
+    const STEPS: usize = 2000;
+
+    let step = (limits.to - limits.from) / STEPS as f64;
+    let curve: Vec<Point2D> = (0..=STEPS)
+        .map(|i| {
+            let time = limits.from + step * i as f64;
+            Point2D::new(time, model.evaluate(parameters, time))
+        })
+        .collect();
+
+    let apex = curve
+        .iter()
+        .enumerate()
+        .max_by(|lhs, rhs| lhs.1.y().total_cmp(&rhs.1.y()))
+        .map_or(0, |(i, _)| i);
+
+    let half = curve[apex].y() / 2.0;
+    let crossing = |lhs: &Point2D, rhs: &Point2D| {
+        lhs.x() + (half - lhs.y()) * (rhs.x() - lhs.x()) / (rhs.y() - lhs.y())
+    };
+
+    let left = (1..=apex)
+        .rev()
+        .find(|i| curve[i - 1].y() <= half)
+        .map_or(curve[0].x(), |i| crossing(&curve[i - 1], &curve[i]));
+    let right = (apex + 1..curve.len())
+        .find(|i| curve[*i].y() <= half)
+        .map_or(curve[curve.len() - 1].x(), |i| {
+            crossing(&curve[i - 1], &curve[i])
+        });
+
+    (curve[apex].x(), right - left)
+}
+
+/// Scaled complementary error function `exp(x^2) * erfc(x)` for `x >= 0`.
+///
+/// Chebyshev approximation with a relative error below 1.2e-7 (Numerical Recipes `erfcc`).
+fn erfcx(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * x);
+    let polynomial = -1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398
+                                + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+
+    t * polynomial.exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::peak_between;
+
+    const MODELS: [PeakModel; 3] = [
+        PeakModel::Gaussian,
+        PeakModel::ExponentiallyModifiedGaussian,
+        PeakModel::BiGaussian,
+    ];
+
+    /// Two overlapping peaks of every model with areas 10 and 6 at 4.0 and 4.8 minutes.
+    fn known_parameters(model: PeakModel) -> Vec<f64> {
+        match model {
+            PeakModel::None => vec![],
+            PeakModel::Gaussian => vec![10.0, 4.0, 0.2, 6.0, 4.8, 0.25],
+            PeakModel::ExponentiallyModifiedGaussian => {
+                vec![10.0, 4.0, 0.15, 0.1, 6.0, 4.8, 0.2, 0.15]
+            }
+            PeakModel::BiGaussian => vec![10.0, 4.0, 0.15, 0.25, 6.0, 4.8, 0.2, 0.3],
+        }
+    }
+
+    fn times() -> Vec<f64> {
+        (0..=400).map(|i| 2.0 + i as f64 * 0.01).collect()
+    }
+
+    fn sum(model: PeakModel, parameters: &[f64], time: f64) -> f64 {
+        parameters
+            .chunks(model.parameters())
+            .map(|peak| model.evaluate(peak, time))
+            .sum()
+    }
+
+    #[test]
+    fn erfcx_reference_values() {
+        let expected = [
+            (0.0, 1.0),
+            (0.5, 0.6156903441929259),
+            (1.0, 0.42758357615580705),
+            (2.0, 0.2553956763105057),
+            (10.0, 0.05614099274382259),
+        ];
+
+        for (x, value) in expected {
+            assert!((erfcx(x) / value - 1.0).abs() < 2e-7, "erfcx({})", x);
+        }
+    }
+
+    #[test]
+    fn models_integrate_to_area() {
+        for model in MODELS {
+            let parameters = &known_parameters(model)[..model.parameters()];
+            let area: f64 = (0..=10000)
+                .map(|i| model.evaluate(parameters, i as f64 * 0.001) * 0.001)
+                .sum();
+
+            assert!((area - 10.0).abs() < 1e-3, "{}: {}", model, area);
+        }
+    }
+
+    #[test]
+    fn emg_moments() {
+        // Mean of an EMG is centre + tau and its variance sigma^2 + tau^2
+        let parameters = [1.0, 4.0, 0.15, 0.3];
+        let model = PeakModel::ExponentiallyModifiedGaussian;
+        let step = 0.0005;
+        let times: Vec<f64> = (0..=16000).map(|i| i as f64 * step).collect();
+
+        let mean: f64 = times
+            .iter()
+            .map(|time| time * model.evaluate(&parameters, *time) * step)
+            .sum();
+        let variance: f64 = times
+            .iter()
+            .map(|time| (time - mean).powi(2) * model.evaluate(&parameters, *time) * step)
+            .sum();
+
+        assert!((mean - 4.3).abs() < 1e-4);
+        assert!((variance - (0.15f64.powi(2) + 0.3f64.powi(2))).abs() < 1e-4);
+    }
+
+    #[test]
+    fn bigaussian_shape() {
+        let parameters = [10.0, 4.0, 0.15, 0.25];
+        let limits = Limits {
+            from: 2.0,
+            to: 6.0,
+            min_width: 0.005,
+        };
+
+        let (apex, width) = shape(PeakModel::BiGaussian, &parameters, &limits);
+        let half_width = (2.0 * 2.0f64.ln()).sqrt();
+
+        assert!((apex - 4.0).abs() < 2e-3);
+        assert!((width - half_width * 0.4).abs() < 5e-3);
+    }
+
+    #[test]
+    fn levenberg_marquardt_recovers_parameters() {
+        let times = times();
+        let limits = Limits {
+            from: times[0],
+            to: times[times.len() - 1],
+            min_width: 0.005,
+        };
+
+        for model in MODELS {
+            let known = known_parameters(model);
+            let values: Vec<f64> = times.iter().map(|time| sum(model, &known, *time)).collect();
+
+            // Start 20% away from every parameter and 0.1 minutes away from every centre
+            let initial: Vec<f64> = known
+                .chunks(model.parameters())
+                .flat_map(|peak| {
+                    let mut peak = peak.to_vec();
+                    peak[0] *= 0.8;
+                    peak[1] -= 0.1;
+                    peak[2..].iter_mut().for_each(|width| *width *= 1.2);
+                    peak
+                })
+                .collect();
+
+            let (fitted, error) = levenberg_marquardt(model, &times, &values, initial, &limits)
+                .expect("fit should converge");
+
+            assert!(error < 1e-8, "{}: error {}", model, error);
+            for (fitted, known) in fitted.iter().zip(&known) {
+                assert!(
+                    (fitted - known).abs() < 1e-3 * known,
+                    "{}: {:?}",
+                    model,
+                    fitted
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn deconvolve_overlapping_peaks() {
+        let times = times();
+        let known = known_parameters(PeakModel::Gaussian);
+        let data: Vec<Point2D> = times
+            .iter()
+            .map(|time| Point2D::new(*time, 1.0 + sum(PeakModel::Gaussian, &known, *time)))
+            .collect();
+        let baseline: Vec<Point2D> = times.iter().map(|time| Point2D::new(*time, 1.0)).collect();
+
+        // Split at the valley between the apexes, like a perpendicular drop
+        let valley = (200..280)
+            .min_by(|lhs, rhs| data[*lhs].y().total_cmp(&data[*rhs].y()))
+            .unwrap();
+        let mut peaks = vec![
+            peak_between(&data, &baseline, 0, valley),
+            peak_between(&data, &baseline, valley, times.len() - 1),
+        ];
+        let dropped = [peaks[0].area, peaks[1].area];
+
+        deconvolve(&mut peaks, &data, &baseline, PeakModel::Gaussian);
+
+        for (peak, (area, dropped)) in peaks.iter().zip([(10.0, dropped[0]), (6.0, dropped[1])]) {
+            let fit = peak
+                .fit
+                .as_ref()
+                .expect("overlapping peaks should be fitted");
+            assert!((peak.area - area).abs() < 1e-3);
+            assert!((peak.area - area).abs() < (dropped - area).abs());
+            assert_eq!(fit.curve.len(), times.len());
+            assert!(fit.residual < 1e-6);
+        }
+
+        let fit = peaks[0].fit.as_ref().unwrap();
+        assert!((fit.retention_time - 4.0).abs() < 2e-3);
+        assert!((fit.width - 0.2 * (8.0 * 2.0f64.ln()).sqrt()).abs() < 5e-3);
+    }
+
+    #[test]
+    fn resolved_peaks_are_not_fitted() {
+        let times = times();
+        let data: Vec<Point2D> = times
+            .iter()
+            .map(|time| {
+                let peaks = [10.0, 3.0, 0.1, 6.0, 5.0, 0.1];
+                Point2D::new(*time, sum(PeakModel::Gaussian, &peaks, *time))
+            })
+            .collect();
+        let baseline: Vec<Point2D> = times.iter().map(|time| Point2D::new(*time, 0.0)).collect();
+
+        let mut peaks = vec![
+            peak_between(&data, &baseline, 0, 200),
+            peak_between(&data, &baseline, 200, 400),
+        ];
+        deconvolve(&mut peaks, &data, &baseline, PeakModel::Gaussian);
+
+        assert!(peaks.iter().all(|peak| peak.fit.is_none()));
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::smoothing::SavitzkyGolay;
+use crate::vector::{Point2D, Vector2};
+
+/// How the first and second derivatives used for peak detection are calculated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DerivativeMethod {
+    /// Forward differences between neighbouring points, differentiated twice for the second derivative.
+    #[default]
+    FiniteDifference,
+    /// Derivatives of a local polynomial fit, far less sensitive to noise.
+    SavitzkyGolay,
+}
+
+impl DerivativeMethod {
+    pub const ALL: [DerivativeMethod; 2] = [
+        DerivativeMethod::FiniteDifference,
+        DerivativeMethod::SavitzkyGolay,
+    ];
+}
+
+impl Display for DerivativeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DerivativeMethod::FiniteDifference => "Finite Difference",
+            DerivativeMethod::SavitzkyGolay => "Savitzky-Golay",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for DerivativeMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "finite-difference" => Ok(DerivativeMethod::FiniteDifference),
+            "savitzky-golay" | "sg" => Ok(DerivativeMethod::SavitzkyGolay),
+            other => Err(format!("Unknown derivative method {}", other)),
+        }
+    }
+}
+
+/// Derivative configuration of a sample.
+///
+/// `radius` and `order` only apply to Savitzky–Golay derivatives,
+/// `order` must be at least 2 and below the window size for the second derivative to exist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Derivatives {
+    pub method: DerivativeMethod,
+    pub radius: usize,
+    pub order: usize,
+}
+
+impl Default for Derivatives {
+    fn default() -> Self {
+        Self {
+            method: DerivativeMethod::FiniteDifference,
+            radius: 5,
+            order: 3,
+        }
+    }
+}
+
+impl Derivatives {
+    /// First and second derivative of the data.
+    ///
+    /// Both follow the layout of forward differences and lie at the time of point `i`:
+    /// with forward differences `first[i]` is the gradient between points `i` and `i + 1`
+    /// and `second[i]` the curvature at point `i + 1`, Savitzky–Golay takes both at point `i + 1`.
+    /// Savitzky–Golay derivatives fall back to forward differences if the data is shorter than the window.
+    pub fn calculate(&self, data: &[Point2D]) -> (Vec<Point2D>, Vec<Point2D>) {
+        if self.method == DerivativeMethod::SavitzkyGolay {
+            let filter = SavitzkyGolay {
+                radius: self.radius,
+                order: self.order,
+            };
+
+            if let (Some(first), Some(second)) = (
+                savitzky_golay_derivative(data, &filter, 1),
+                savitzky_golay_derivative(data, &filter, 2),
+            ) {
+                // Centred derivatives, shifted to line up with forward differences
+                let shift = |derivative: &[Point2D], len: usize| -> Vec<Point2D> {
+                    data.iter()
+                        .zip(&derivative[1..])
+                        .take(len)
+                        .map(|(point, value)| Point2D::new(point.x(), value.y()))
+                        .collect()
+                };
+                return (
+                    shift(&first, data.len() - 1),
+                    shift(&second, data.len() - 2),
+                );
+            }
+        }
+
+        let first = finite_difference(data);
+        let second = finite_difference(&first);
+        (first, second)
+    }
+}
+
+/// Gradient between every pair of neighbouring points.
+pub fn finite_difference(graph: &[Point2D]) -> Vec<Point2D> {
+    if graph.len() < 2 {
+        return vec![];
+    }
+
+    let mut derivative = Vec::with_capacity(graph.len());
+
+    for i in 1..graph.len() {
+        let prev = &graph[i - 1];
+        let next = &graph[i];
+
+        let point = Point2D::new(prev.x(), prev.gradient(next));
+        derivative.push(point);
+    }
+
+    derivative
+}
+
+/// Derivative of the local polynomial fit at every point, assuming evenly spaced data.
+///
+/// Points closer to the ends than the radius use the window at the end of the data.
+fn savitzky_golay_derivative(
+    data: &[Point2D],
+    filter: &SavitzkyGolay,
+    derivative: usize,
+) -> Option<Vec<Point2D>> {
+    let size = 2 * filter.radius + 1;
+    if data.len() < size.max(3) {
+        return None;
+    }
+
+    let spacing = (data[data.len() - 1].x() - data[0].x()) / (data.len() - 1) as f64;
+    let scale = spacing.powi(derivative as i32);
+    let centre = filter.weights(derivative)?;
+
+    let mut result = Vec::with_capacity(data.len());
+    for i in 0..data.len() {
+        let start = i.saturating_sub(filter.radius).min(data.len() - size);
+
+        let edge_weights;
+        let weights = if i - start == filter.radius {
+            &centre
+        } else {
+            edge_weights = filter.weights_at(derivative, (i - start) as f64)?;
+            &edge_weights
+        };
+
+        let window = &data[start..start + size];
+        let value: f64 = window
+            .iter()
+            .zip(weights)
+            .map(|(point, weight)| point.y() * weight)
+            .sum();
+
+        result.push(Point2D::new(data[i].x(), value / scale));
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// y = x³ - 2x² + x every 0.1 from 0 to 5.
+    fn cubic() -> Vec<Point2D> {
+        (0..=50)
+            .map(|i| {
+                let x = i as f64 / 10.0;
+                Point2D::new(x, x.powi(3) - 2.0 * x.powi(2) + x)
+            })
+            .collect()
+    }
+
+    fn savitzky_golay(radius: usize, order: usize) -> Derivatives {
+        Derivatives {
+            method: DerivativeMethod::SavitzkyGolay,
+            radius,
+            order,
+        }
+    }
+
+    #[test]
+    fn savitzky_golay_cubic() {
+        let data = cubic();
+        let (first, second) = savitzky_golay(3, 3).calculate(&data);
+
+        let forward = finite_difference(&data);
+        assert_eq!(first.len(), forward.len());
+        assert_eq!(second.len(), finite_difference(&forward).len());
+
+        for (i, point) in first.iter().enumerate() {
+            let x = data[i + 1].x();
+            assert_eq!(point.x(), data[i].x());
+            assert!((point.y() - (3.0 * x * x - 4.0 * x + 1.0)).abs() < 1e-9);
+        }
+        for (i, point) in second.iter().enumerate() {
+            let x = data[i + 1].x();
+            assert_eq!(point.x(), data[i].x());
+            assert!((point.y() - (6.0 * x - 4.0)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn savitzky_golay_fallback() {
+        let forward = |data: &[Point2D]| {
+            let first = finite_difference(data);
+            let second = finite_difference(&first);
+            (first, second)
+        };
+
+        // Shorter than the window
+        let short = &cubic()[..5];
+        assert_eq!(savitzky_golay(5, 3).calculate(short), forward(short));
+
+        // Order too high for the window
+        let data = cubic();
+        assert_eq!(savitzky_golay(1, 3).calculate(&data), forward(&data));
+    }
+}
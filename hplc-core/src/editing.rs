@@ -0,0 +1,222 @@
+use crate::component::Peak;
+use crate::integration::{force_peak, peak_between, span};
+use crate::label::peak_at;
+use crate::vector::{Point2D, Vector2};
+
+/// A change made by hand to the detected peaks, times are in minutes.
+///
+/// Peaks are found by the time they contain, so edits keep applying when the detector runs again
+/// and only affect the peaks around them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "kebab-case"))]
+pub enum PeakEdit {
+    /// A new peak from `start` to `end`, replacing the peaks within it.
+    Add { start: f64, end: f64 },
+    /// Removes the peak containing `time`.
+    Delete { time: f64 },
+    /// Splits the peak containing `time` in two at `time`.
+    Split { time: f64 },
+    /// Joins the peaks containing `first` and `second` and every peak between them.
+    Merge { first: f64, second: f64 },
+    /// Moves the start and end of the peak containing `time`, neighbours are trimmed to make room.
+    Bounds { time: f64, start: f64, end: f64 },
+}
+
+/// Applies the edits in the order they were made, edits which no longer find their peak are skipped.
+pub(crate) fn apply_edits(
+    peaks: &mut Vec<Peak>,
+    data: &[Point2D],
+    baseline: &[Point2D],
+    edits: &[PeakEdit],
+) {
+    if data.is_empty() {
+        return;
+    }
+
+    let index = |x: f64| {
+        data.partition_point(|point| point.x() < x)
+            .min(data.len() - 1)
+    };
+
+    for edit in edits {
+        match *edit {
+            PeakEdit::Add { start, end } => {
+                force_peak(peaks, data, baseline, start.min(end), Some(start.max(end)));
+            }
+            PeakEdit::Delete { time } => {
+                if let Some(position) = peak_at(peaks, time) {
+                    peaks.remove(position);
+                }
+            }
+            PeakEdit::Split { time } => {
+                let Some(position) = peak_at(peaks, time) else {
+                    continue;
+                };
+
+                let (start, end) = span(data, &peaks[position]);
+                let middle = index(time);
+                if start < middle && middle < end {
+                    peaks[position] = peak_between(data, baseline, middle, end);
+                    peaks.insert(position, peak_between(data, baseline, start, middle));
+                }
+            }
+            PeakEdit::Merge { first, second } => {
+                let (Some(lhs), Some(rhs)) = (peak_at(peaks, first), peak_at(peaks, second)) else {
+                    continue;
+                };
+
+                let (first, last) = (lhs.min(rhs), lhs.max(rhs));
+                let start = span(data, &peaks[first]).0;
+                let end = span(data, &peaks[last]).1;
+
+                peaks.drain(first..=last);
+                peaks.insert(first, peak_between(data, baseline, start, end));
+            }
+            PeakEdit::Bounds { time, start, end } => {
+                let Some(position) = peak_at(peaks, time) else {
+                    continue;
+                };
+
+                peaks.remove(position);
+                force_peak(peaks, data, baseline, start.min(end), Some(start.max(end)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two triangles sampled every 0.1 minutes, apexes of 100 at 2 and 60 at 4.5 minutes.
+    fn trace() -> (Vec<Point2D>, Vec<Point2D>) {
+        let data: Vec<Point2D> = (0..=60)
+            .map(|i| {
+                let time = i as f64 / 10.0;
+                let first = (100.0 - 50.0 * (time - 2.0).abs()).max(0.0);
+                let second = (60.0 - 40.0 * (time - 4.5).abs()).max(0.0);
+                Point2D::new(time, first + second)
+            })
+            .collect();
+        let baseline = data
+            .iter()
+            .map(|point| Point2D::new(point.x(), 0.0))
+            .collect();
+
+        (data, baseline)
+    }
+
+    fn detected(data: &[Point2D], baseline: &[Point2D]) -> Vec<Peak> {
+        vec![
+            peak_between(data, baseline, 0, 40),
+            peak_between(data, baseline, 40, 60),
+        ]
+    }
+
+    fn edited(edits: &[PeakEdit]) -> Vec<Peak> {
+        let (data, baseline) = trace();
+        let mut peaks = detected(&data, &baseline);
+        apply_edits(&mut peaks, &data, &baseline, edits);
+        peaks
+    }
+
+    fn bounds(peaks: &[Peak]) -> Vec<(f64, f64)> {
+        peaks
+            .iter()
+            .map(|peak| (peak.start.x(), peak.end.x()))
+            .collect()
+    }
+
+    fn total_area(peaks: &[Peak]) -> f64 {
+        peaks.iter().map(|peak| peak.area).sum()
+    }
+
+    #[test]
+    fn split() {
+        let original = edited(&[]);
+        let peaks = edited(&[PeakEdit::Split { time: 2.5 }]);
+
+        assert_eq!(bounds(&peaks), [(0.0, 2.5), (2.5, 4.0), (4.0, 6.0)]);
+        assert_eq!(peaks[0].retention_point.x(), 2.0);
+        assert_eq!(peaks[1].retention_point.x(), 2.5);
+        assert!((peaks[0].area + peaks[1].area - original[0].area).abs() < 1e-9);
+        assert_eq!(peaks[2].area, original[1].area);
+    }
+
+    #[test]
+    fn split_at_edge_is_skipped() {
+        let peaks = edited(&[PeakEdit::Split { time: 0.0 }, PeakEdit::Split { time: 7.0 }]);
+
+        assert_eq!(bounds(&peaks), [(0.0, 4.0), (4.0, 6.0)]);
+    }
+
+    #[test]
+    fn merge() {
+        let original = edited(&[]);
+        let peaks = edited(&[PeakEdit::Merge {
+            first: 5.0,
+            second: 1.0,
+        }]);
+
+        assert_eq!(bounds(&peaks), [(0.0, 6.0)]);
+        assert_eq!(peaks[0].retention_point.x(), 2.0);
+        assert_eq!(peaks[0].height, 100.0);
+        assert!((peaks[0].area - total_area(&original)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn split_then_merge() {
+        let original = edited(&[]);
+        let peaks = edited(&[
+            PeakEdit::Split { time: 1.0 },
+            PeakEdit::Split { time: 3.0 },
+            PeakEdit::Merge {
+                first: 0.5,
+                second: 3.5,
+            },
+        ]);
+
+        assert_eq!(bounds(&peaks), bounds(&original));
+        assert!((peaks[0].area - original[0].area).abs() < 1e-9);
+    }
+
+    #[test]
+    fn add_delete_and_bounds() {
+        let peaks = edited(&[PeakEdit::Add {
+            start: 5.0,
+            end: 3.0,
+        }]);
+        assert_eq!(bounds(&peaks), [(0.0, 3.0), (3.0, 5.0)]);
+        assert_eq!(peaks[1].retention_point.x(), 4.5);
+
+        let peaks = edited(&[PeakEdit::Delete { time: 4.5 }]);
+        assert_eq!(bounds(&peaks), [(0.0, 4.0)]);
+
+        let peaks = edited(&[PeakEdit::Bounds {
+            time: 2.0,
+            start: 1.0,
+            end: 3.0,
+        }]);
+        assert_eq!(bounds(&peaks), [(1.0, 3.0), (4.0, 6.0)]);
+    }
+
+    #[test]
+    fn missing_peaks_are_skipped() {
+        let original = bounds(&edited(&[]));
+        let peaks = edited(&[
+            PeakEdit::Delete { time: 8.0 },
+            PeakEdit::Merge {
+                first: 1.0,
+                second: 8.0,
+            },
+            PeakEdit::Bounds {
+                time: -1.0,
+                start: 0.0,
+                end: 1.0,
+            },
+        ]);
+
+        assert_eq!(bounds(&peaks), original);
+    }
+}
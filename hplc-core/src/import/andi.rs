@@ -0,0 +1,165 @@
+use super::netcdf::{NetCdf, Values};
+use crate::chromatography::Channel;
+use crate::metadata::{SampleMetadata, Timestamp};
+use crate::vector::*;
+
+// netCDF fills missing floating point values with 9.97e36
+const FILL_LIMIT: f64 = 1e30;
+
+/// Reads an AIA/ANDI chromatography file (ASTM E1947), times are converted to minutes.
+///
+/// The detector signal is `ordinate_values`, timed by `raw_data_retention` if present
+/// or else by `actual_delay_time` and `actual_sampling_interval` (in seconds).
+/// Every global attribute is kept as a header of the metadata.
+pub(crate) fn parse(bytes: &[u8]) -> Result<(SampleMetadata, Vec<Channel>), String> {
+    let netcdf = NetCdf::parse(bytes)?;
+
+    let heights = match netcdf.variable("ordinate_values") {
+        Some(Ok(Values::Numbers(heights))) => heights,
+        Some(Err(err)) => return Err(err),
+        _ => return Err("No ordinate_values in the ANDI file".to_string()),
+    };
+
+    let times = match netcdf.variable("raw_data_retention") {
+        Some(Ok(Values::Numbers(times))) if times.len() == heights.len() => {
+            let unit = netcdf.text("retention_unit").unwrap_or_default();
+            let scale = if unit.to_ascii_lowercase().starts_with("min") {
+                1.0
+            } else {
+                1.0 / 60.0
+            };
+
+            times.into_iter().map(|time| time * scale).collect()
+        }
+        _ => {
+            let interval = netcdf
+                .scalar("actual_sampling_interval")
+                .filter(|interval| *interval > 0.0)
+                .ok_or("No actual_sampling_interval in the ANDI file")?;
+            let delay = netcdf
+                .scalar("actual_delay_time")
+                .filter(|delay| delay.abs() < FILL_LIMIT)
+                .unwrap_or(0.0);
+
+            (0..heights.len())
+                .map(|i| (delay + i as f64 * interval) / 60.0)
+                .collect::<Vec<f64>>()
+        }
+    };
+
+    let data: Vec<Point2D> = times
+        .into_iter()
+        .zip(heights)
+        .filter(|(_, height)| height.is_finite() && height.abs() < FILL_LIMIT)
+        .map(|(time, height)| Point2D::new(time, height))
+        .collect();
+
+    let name = netcdf
+        .text("detector_name")
+        .unwrap_or("Channel 1".to_string());
+    let unit = netcdf.text("detector_unit");
+
+    let headers = netcdf
+        .attributes
+        .iter()
+        .map(|attribute| {
+            let value = match &attribute.values {
+                Values::Text(text) => text.clone(),
+                Values::Numbers(numbers) => numbers
+                    .iter()
+                    .map(|number| number.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            };
+
+            (attribute.name.clone(), value)
+        })
+        .collect();
+
+    let metadata = SampleMetadata {
+        sample_name: netcdf.text("sample_name"),
+        system_name: netcdf.text("instrument_name"),
+        sample_set_name: netcdf.text("sequence_name"),
+        date_acquired: netcdf
+            .text("injection_date_time_stamp")
+            .and_then(|stamp| parse_timestamp(&stamp)),
+        sample_set_start_date: None,
+        headers,
+    };
+
+    Ok((metadata, vec![Channel { name, unit, data }]))
+}
+
+/// Parses the `YYYYMMDDhhmmss±hhmm` timestamps of ANDI files.
+fn parse_timestamp(stamp: &str) -> Option<Timestamp> {
+    let digits = stamp.get(..14)?;
+    if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    let iso = format!(
+        "{}-{}-{} {}:{}:{} {}",
+        &digits[0..4],
+        &digits[4..6],
+        &digits[6..8],
+        &digits[8..10],
+        &digits[10..12],
+        &digits[12..14],
+        &stamp[14..]
+    );
+
+    Timestamp::parse(&iso)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_fixture() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test/samples/ANDI/chromatogram_46739.cdf");
+        let (metadata, channels) = parse(&std::fs::read(path).unwrap()).unwrap();
+
+        let [channel] = channels.as_slice() else {
+            panic!("expected one channel, found {}", channels.len());
+        };
+        assert_eq!(channel.name, "FLR");
+        assert_eq!(channel.unit.as_deref(), Some("LU"));
+        assert_eq!(channel.data.len(), 3301);
+        assert!(channel.data[0].x().abs() < 1e-9);
+        assert!((channel.data[3300].x() - 55.0).abs() < 1e-6);
+
+        assert_eq!(
+            metadata.sample_name.as_deref(),
+            Some("9. PC-12 Atp2b2 shRNA C")
+        );
+        assert_eq!(metadata.system_name, None);
+        assert_eq!(
+            metadata
+                .date_acquired
+                .as_ref()
+                .map(|date| date.to_string())
+                .as_deref(),
+            Some("2025-05-15 22:18:57 +0100")
+        );
+        assert_eq!(metadata.get("operator_name"), Some("DTtV"));
+    }
+
+    #[test]
+    fn timestamps() {
+        let date = parse_timestamp("20250515221857+0100").unwrap();
+        assert_eq!((date.year, date.month, date.day), (2025, 5, 15));
+        assert_eq!((date.hour, date.minute, date.second), (22, 18, 57));
+        assert_eq!(date.zone.as_deref(), Some("+0100"));
+
+        assert_eq!(parse_timestamp("20250515221857").unwrap().zone, None);
+        assert_eq!(parse_timestamp("2025-05-15"), None);
+        assert_eq!(parse_timestamp("20251315221857"), None);
+    }
+
+    #[test]
+    fn truncated_file() {
+        assert!(parse(b"CDF\x01\x00\x00").is_err());
+    }
+}
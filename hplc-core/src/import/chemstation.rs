@@ -0,0 +1,155 @@
+use crate::chromatography::Channel;
+use crate::metadata::{SampleMetadata, Timestamp};
+use crate::vector::*;
+
+use super::{
+    delimiter, find_header, minutes_per_unit, parse_number, push_channel, split_fields, split_unit,
+};
+
+/// Reads an Agilent ChemStation or OpenLab CSV export.
+///
+/// Rows of `Key,Value` may precede the data, followed by an optional row of column titles
+/// (`Time (min),Signal (LU)`). Every column after the time is a channel, named after its title
+/// or, for a single column, the `Signal` header.
+pub(crate) fn parse(text: &str) -> Result<(SampleMetadata, Vec<Channel>), String> {
+    let mut headers: Vec<(String, String)> = vec![];
+    let mut titles: Vec<String> = vec![];
+    let mut scale = 1.0;
+    let mut columns: Vec<Vec<Point2D>> = vec![];
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let fields = split_fields(line, delimiter(line));
+
+        let Some(time) = parse_number(&fields[0]) else {
+            // Text after the data, such as a peak table, is not part of the chromatogram
+            if !columns.is_empty() {
+                break;
+            }
+
+            if fields[0].to_ascii_lowercase().starts_with("time") {
+                scale = minutes_per_unit(&fields[0]);
+                titles = fields[1..].to_vec();
+            } else if fields.len() > 1 {
+                let values: Vec<&str> = fields[1..]
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|value| !value.is_empty())
+                    .collect();
+                let key = fields[0].trim_end_matches(':').to_string();
+                headers.push((key, values.join(", ")));
+            }
+
+            continue;
+        };
+
+        for (i, field) in fields[1..].iter().enumerate() {
+            if columns.len() <= i {
+                columns.resize(i + 1, vec![]);
+            }
+
+            if let Some(height) = parse_number(field) {
+                columns[i].push(Point2D::new(time * scale, height));
+            }
+        }
+    }
+
+    let signal = find_header(&headers, &["Signal"]);
+    let single = columns.iter().filter(|data| !data.is_empty()).count() == 1;
+
+    let mut channels = vec![];
+    for (i, data) in columns.into_iter().enumerate() {
+        let (title, unit) = match titles.get(i) {
+            Some(title) => split_unit(title),
+            None => (String::new(), None),
+        };
+
+        let name = match &signal {
+            Some(signal) if single => signal.clone(),
+            _ if !title.is_empty() => title,
+            _ => format!("Channel {}", i + 1),
+        };
+
+        push_channel(&mut channels, name, unit, data);
+    }
+
+    let metadata = SampleMetadata {
+        sample_name: find_header(&headers, &["Sample Name", "Sample"]),
+        system_name: find_header(&headers, &["Instrument", "Instrument Name"]),
+        sample_set_name: find_header(&headers, &["Sequence", "Sequence Name"]),
+        date_acquired: find_header(&headers, &["Acq. Date-Time", "Injection Date"])
+            .and_then(|value| Timestamp::parse(&value)),
+        sample_set_start_date: None,
+        headers,
+    };
+
+    Ok((metadata, channels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::decode_text;
+
+    #[test]
+    fn reads_fixture() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test/samples/Agilent/PC12-9.csv");
+        let text = decode_text(&std::fs::read(path).unwrap()).unwrap();
+        let (metadata, channels) = parse(&text).unwrap();
+
+        let [channel] = channels.as_slice() else {
+            panic!("expected one channel, found {}", channels.len());
+        };
+        assert_eq!(channel.name, "FLD1 A, Ex=330, Em=420");
+        assert_eq!(channel.unit.as_deref(), Some("LU"));
+        assert_eq!(channel.data.len(), 3301);
+        assert_eq!(channel.data[0], Point2D::new(0.0, 0.0002456665));
+        assert_eq!(channel.data[3300], Point2D::new(55.0, -0.03911514));
+
+        assert_eq!(
+            metadata.sample_name.as_deref(),
+            Some("9. PC-12 Atp2b2 shRNA C")
+        );
+        assert_eq!(metadata.system_name.as_deref(), Some("1260 Infinity II"));
+        assert_eq!(
+            metadata.sample_set_name.as_deref(),
+            Some("DTtV_15052025 GSL new HPLC")
+        );
+        assert_eq!(
+            metadata
+                .date_acquired
+                .as_ref()
+                .map(|date| date.to_string())
+                .as_deref(),
+            Some("2025-05-15 22:18:57")
+        );
+        assert_eq!(metadata.get("Acq. Method"), Some("GSL_FLD.M"));
+    }
+
+    #[test]
+    fn seconds_and_several_signals() {
+        let text = "Sample Name:,Ladder\n\
+                    Time (s),DAD1 A (mAU),DAD1 B (mAU)\n\
+                    0,1.5,2.5\n\
+                    30,2.0,n.a.\n\
+                    60,2.5,3.5\n\
+                    Peak,RT,Area\n\
+                    1,0.5,10\n";
+        let (metadata, channels) = parse(text).unwrap();
+
+        assert_eq!(metadata.sample_name.as_deref(), Some("Ladder"));
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].name, "DAD1 A");
+        assert_eq!(channels[0].unit.as_deref(), Some("mAU"));
+        assert_eq!(
+            channels[0].data,
+            vec![
+                Point2D::new(0.0, 1.5),
+                Point2D::new(0.5, 2.0),
+                Point2D::new(1.0, 2.5)
+            ]
+        );
+        assert_eq!(channels[1].name, "DAD1 B");
+        assert_eq!(channels[1].data.len(), 2);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::chromatography::Channel;
+use crate::metadata::{SampleMetadata, Timestamp};
+use crate::vector::*;
+
+use super::{
+    delimiter, find_header, minutes_per_unit, parse_number, push_channel, split_fields, split_unit,
+};
+
+/// Reads a Thermo Chromeleon text export.
+///
+/// Sections such as `Injection Information:` hold `Key<tab>Value` lines, the trace follows
+/// `Chromatogram Data:` as `Time (min)`, `Step (s)` and `Value (EU)` columns.
+pub(crate) fn parse(text: &str) -> Result<(SampleMetadata, Vec<Channel>), String> {
+    let mut headers: Vec<(String, String)> = vec![];
+    let mut scale = 1.0;
+    let mut unit = None;
+    let mut data: Vec<Point2D> = vec![];
+    let mut in_data = false;
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if !in_data {
+            if line == "Chromatogram Data:" {
+                in_data = true;
+                continue;
+            }
+
+            let fields = split_fields(line, '\t');
+            if fields.len() > 1 && !fields[0].is_empty() {
+                let values: Vec<&str> = fields[1..]
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|value| !value.is_empty())
+                    .collect();
+                headers.push((fields[0].clone(), values.join(", ")));
+            }
+
+            continue;
+        }
+
+        let fields = split_fields(line, delimiter(line));
+        match parse_number(&fields[0]) {
+            Some(time) => {
+                // The value is the last column, the step between points comes before it
+                if fields.len() > 1
+                    && let Some(height) = fields.last().and_then(|field| parse_number(field))
+                {
+                    data.push(Point2D::new(time * scale, height));
+                }
+            }
+            None if data.is_empty() => {
+                scale = minutes_per_unit(&fields[0]);
+                unit = fields.last().and_then(|title| split_unit(title).1);
+            }
+            None => break,
+        }
+    }
+
+    let name = find_header(&headers, &["Channel", "Detector"]).unwrap_or("Channel 1".to_string());
+    let unit = find_header(&headers, &["Signal Unit"]).or(unit);
+
+    let mut channels = vec![];
+    push_channel(&mut channels, name, unit, data);
+
+    let date = find_header(&headers, &["Injection Date"]);
+    let time = find_header(&headers, &["Injection Time"]).unwrap_or_default();
+
+    let metadata = SampleMetadata {
+        sample_name: find_header(&headers, &["Injection", "Sample Name", "Name"]),
+        system_name: find_header(&headers, &["Instrument", "Timebase"]),
+        sample_set_name: find_header(&headers, &["Sequence", "Sequence Name"]),
+        date_acquired: date.and_then(|date| Timestamp::parse(&format!("{} {}", date, time))),
+        sample_set_start_date: None,
+        headers,
+    };
+
+    Ok((metadata, channels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_fixture() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test/samples/Chromeleon/PC12-9.txt");
+        let text = std::fs::read_to_string(path).unwrap();
+        let (metadata, channels) = parse(&text).unwrap();
+
+        let [channel] = channels.as_slice() else {
+            panic!("expected one channel, found {}", channels.len());
+        };
+        assert_eq!(channel.name, "FLD_330_420");
+        assert_eq!(channel.unit.as_deref(), Some("EU"));
+        assert_eq!(channel.data.len(), 3301);
+        assert_eq!(channel.data[0], Point2D::new(0.0, 0.0002456665));
+        assert!((channel.data[1].x() - 1.0 / 60.0).abs() < 1e-12);
+        assert!((channel.data[3300].x() - 55.0).abs() < 1e-12);
+
+        assert_eq!(
+            metadata.sample_name.as_deref(),
+            Some("9. PC-12 Atp2b2 shRNA C")
+        );
+        assert_eq!(metadata.system_name.as_deref(), Some("Vanquish 2"));
+        assert_eq!(
+            metadata
+                .date_acquired
+                .as_ref()
+                .map(|date| date.to_string())
+                .as_deref(),
+            Some("2025-05-15 22:18:57")
+        );
+        assert_eq!(metadata.get("Data Vault"), Some("ChromeleonLocal"));
+    }
+
+    #[test]
+    fn unit_from_column_title() {
+        let text = "Chromatogram Data:\n\
+                    Time (min)\tStep (s)\tValue (mAU)\n\
+                    0.5\tn.a.\t1.0\n\
+                    1.0\t30\t2.0\n";
+        let (_, channels) = parse(text).unwrap();
+
+        assert_eq!(channels[0].name, "Channel 1");
+        assert_eq!(channels[0].unit.as_deref(), Some("mAU"));
+        assert_eq!(
+            channels[0].data,
+            vec![Point2D::new(0.5, 1.0), Point2D::new(1.0, 2.0)]
+        );
+    }
+}
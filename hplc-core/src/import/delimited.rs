@@ -0,0 +1,440 @@
+use std::fmt::Display;
+
+use crate::chromatography::Channel;
+use crate::metadata::SampleMetadata;
+use crate::vector::*;
+
+use super::{push_channel, split_unit};
+
+/// Character separating the columns of a delimited text file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Delimiter {
+    #[default]
+    Tab,
+    Comma,
+    Semicolon,
+    /// Any run of spaces or tabs.
+    Whitespace,
+}
+
+impl Delimiter {
+    pub const ALL: [Delimiter; 4] = [
+        Delimiter::Tab,
+        Delimiter::Comma,
+        Delimiter::Semicolon,
+        Delimiter::Whitespace,
+    ];
+}
+
+impl Display for Delimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Delimiter::Tab => "Tab",
+                Delimiter::Comma => "Comma",
+                Delimiter::Semicolon => "Semicolon",
+                Delimiter::Whitespace => "Whitespace",
+            }
+        )
+    }
+}
+
+/// Character between the integer and fractional parts of a number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecimalSeparator {
+    #[default]
+    Point,
+    /// `0,25` as written by instruments set to most European locales.
+    Comma,
+}
+
+impl DecimalSeparator {
+    pub const ALL: [DecimalSeparator; 2] = [DecimalSeparator::Point, DecimalSeparator::Comma];
+}
+
+impl Display for DecimalSeparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                DecimalSeparator::Point => "Point (0.25)",
+                DecimalSeparator::Comma => "Comma (0,25)",
+            }
+        )
+    }
+}
+
+/// Unit of the time column, converted to minutes on import.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeUnit {
+    #[default]
+    Minutes,
+    Seconds,
+    Milliseconds,
+    Hours,
+}
+
+impl TimeUnit {
+    pub const ALL: [TimeUnit; 4] = [
+        TimeUnit::Minutes,
+        TimeUnit::Seconds,
+        TimeUnit::Milliseconds,
+        TimeUnit::Hours,
+    ];
+
+    /// Minutes in one of this unit.
+    pub fn minutes(&self) -> f64 {
+        match self {
+            TimeUnit::Minutes => 1.0,
+            TimeUnit::Seconds => 1.0 / 60.0,
+            TimeUnit::Milliseconds => 1.0 / 60000.0,
+            TimeUnit::Hours => 60.0,
+        }
+    }
+}
+
+impl Display for TimeUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                TimeUnit::Minutes => "Minutes",
+                TimeUnit::Seconds => "Seconds",
+                TimeUnit::Milliseconds => "Milliseconds",
+                TimeUnit::Hours => "Hours",
+            }
+        )
+    }
+}
+
+/// How to read a delimited text file that is not in a known [`DataFormat`](super::DataFormat).
+///
+/// A file is read as, in order, `metadata_lines` of `Key<delimiter>Value`, `skip_lines` ignored,
+/// an optional line of column titles and then the data. Blank lines are ignored everywhere.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ImportProfile {
+    /// Name the profile is saved under.
+    pub name: String,
+    pub delimiter: Delimiter,
+    pub decimal_separator: DecimalSeparator,
+    pub metadata_lines: usize,
+    pub skip_lines: usize,
+    /// The line before the data names the columns, used as channel names and units.
+    pub column_titles: bool,
+    /// Index of the time column, counting from 0.
+    pub time_column: usize,
+    /// Indices of the signal columns, every column but the time if empty.
+    pub signal_columns: Vec<usize>,
+    pub time_unit: TimeUnit,
+}
+
+/// Role of a line of the file under an [`ImportProfile`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineRole {
+    Blank,
+    Metadata,
+    Skipped,
+    Titles,
+    Data,
+}
+
+/// Everything read from a file with an [`ImportProfile`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DelimitedData {
+    pub metadata: SampleMetadata,
+    pub channels: Vec<Channel>,
+    /// Line numbers (counting from 1) of data lines without a readable time or signal.
+    pub rejected_lines: Vec<usize>,
+}
+
+impl ImportProfile {
+    /// Splits a line into trimmed fields, quotes removed.
+    pub fn split(&self, line: &str) -> Vec<String> {
+        let unquote = |field: &str| field.trim().trim_matches('"').to_string();
+        match self.delimiter {
+            Delimiter::Tab => line.split('\t').map(unquote).collect(),
+            Delimiter::Comma => line.split(',').map(unquote).collect(),
+            Delimiter::Semicolon => line.split(';').map(unquote).collect(),
+            Delimiter::Whitespace => line.split_whitespace().map(unquote).collect(),
+        }
+    }
+
+    /// Parses a field written with the decimal separator of the profile.
+    ///
+    /// A decimal comma is never read from a comma delimited field, [`ImportProfile::validate`] rejects the pair.
+    pub fn parse_number(&self, field: &str) -> Option<f64> {
+        let field = field.trim();
+        let number = match self.decimal_separator {
+            DecimalSeparator::Point => field.parse::<f64>(),
+            DecimalSeparator::Comma if self.validate().is_err() => return None,
+            // More than one comma is a thousands separator or two fields, not a number
+            DecimalSeparator::Comma if field.matches(',').count() > 1 => return None,
+            DecimalSeparator::Comma => field.replacen(',', ".", 1).parse::<f64>(),
+        };
+
+        number.ok().filter(|number| number.is_finite())
+    }
+
+    /// Role of every line of `text`, in order.
+    pub fn line_roles(&self, text: &str) -> Vec<LineRole> {
+        let mut roles = vec![];
+        let mut count = 0;
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                roles.push(LineRole::Blank);
+                continue;
+            }
+
+            let titles = self.metadata_lines + self.skip_lines;
+            roles.push(match count {
+                count if count < self.metadata_lines => LineRole::Metadata,
+                count if count < titles => LineRole::Skipped,
+                count if count == titles && self.column_titles => LineRole::Titles,
+                _ => LineRole::Data,
+            });
+            count += 1;
+        }
+
+        roles
+    }
+
+    /// Checks the settings can describe a file at all.
+    pub fn validate(&self) -> Result<(), String> {
+        let clash = matches!(
+            (self.delimiter, self.decimal_separator),
+            (Delimiter::Comma, DecimalSeparator::Comma)
+        );
+        if clash {
+            return Err("The decimal separator cannot be the delimiter".to_string());
+        }
+
+        if self.signal_columns.contains(&self.time_column) {
+            return Err(format!(
+                "Column {} cannot be both time and signal",
+                self.time_column + 1
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Reads `text` into its metadata and channels, times converted to minutes.
+    ///
+    /// Lines of data that cannot be read are listed rather than failing the import.
+    pub fn read(&self, text: &str) -> Result<DelimitedData, String> {
+        self.validate()?;
+
+        let mut headers: Vec<(String, String)> = vec![];
+        let mut titles: Vec<String> = vec![];
+        let mut columns: Vec<(usize, Vec<Point2D>)> = self
+            .signal_columns
+            .iter()
+            .map(|column| (*column, vec![]))
+            .collect();
+        let mut rejected_lines = vec![];
+
+        for ((i, line), role) in text.lines().enumerate().zip(self.line_roles(text)) {
+            let fields = self.split(line);
+            match role {
+                LineRole::Blank | LineRole::Skipped => {}
+                LineRole::Metadata => {
+                    let values: Vec<&str> = fields[1..]
+                        .iter()
+                        .map(String::as_str)
+                        .filter(|value| !value.is_empty())
+                        .collect();
+                    let key = fields[0].trim_end_matches(':').to_string();
+                    headers.push((key, values.join(", ")));
+                }
+                LineRole::Titles => titles = fields,
+                LineRole::Data => {
+                    // Every column but the time once the width of the data is known
+                    if columns.is_empty() {
+                        columns = (0..fields.len())
+                            .filter(|column| *column != self.time_column)
+                            .map(|column| (column, vec![]))
+                            .collect();
+                    }
+
+                    let time = fields
+                        .get(self.time_column)
+                        .and_then(|field| self.parse_number(field));
+                    let Some(time) = time else {
+                        rejected_lines.push(i + 1);
+                        continue;
+                    };
+
+                    let mut read = false;
+                    for (column, data) in columns.iter_mut() {
+                        let height = fields
+                            .get(*column)
+                            .and_then(|field| self.parse_number(field));
+                        if let Some(height) = height {
+                            data.push(Point2D::new(time * self.time_unit.minutes(), height));
+                            read = true;
+                        }
+                    }
+                    if !read {
+                        rejected_lines.push(i + 1);
+                    }
+                }
+            }
+        }
+
+        let mut channels = vec![];
+        for (i, (column, data)) in columns.into_iter().enumerate() {
+            let (name, unit) = match titles.get(column) {
+                Some(title) if !title.is_empty() => split_unit(title),
+                _ => (format!("Channel {}", i + 1), None),
+            };
+
+            push_channel(&mut channels, name, unit, data);
+        }
+
+        if channels.is_empty() {
+            return Err("No data found with this profile".to_string());
+        }
+
+        Ok(DelimitedData {
+            metadata: SampleMetadata::from_headers(headers),
+            channels,
+            rejected_lines,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(delimiter: Delimiter, decimal_separator: DecimalSeparator) -> ImportProfile {
+        ImportProfile {
+            delimiter,
+            decimal_separator,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn decimal_comma() {
+        let profile = profile(Delimiter::Semicolon, DecimalSeparator::Comma);
+        assert_eq!(profile.parse_number(" 0,25 "), Some(0.25));
+        assert_eq!(profile.parse_number("12"), Some(12.0));
+        assert_eq!(profile.parse_number("1,234,5"), None);
+        assert_eq!(profile.parse_number("0.25"), Some(0.25));
+
+        let data = profile.read("0;1,5\n0,5;2,5\n").unwrap();
+        assert_eq!(
+            data.channels[0].data,
+            vec![Point2D::new(0.0, 1.5), Point2D::new(0.5, 2.5)]
+        );
+    }
+
+    #[test]
+    fn decimal_comma_with_comma_delimiter() {
+        let profile = profile(Delimiter::Comma, DecimalSeparator::Comma);
+        let message = "The decimal separator cannot be the delimiter".to_string();
+
+        assert_eq!(profile.validate(), Err(message.clone()));
+        assert_eq!(profile.read("0,1,5\n1,2,5\n").err(), Some(message));
+        assert_eq!(profile.parse_number("1,5"), None);
+    }
+
+    #[test]
+    fn decimal_point() {
+        let profile = profile(Delimiter::Comma, DecimalSeparator::Point);
+        assert_eq!(profile.parse_number("1.5"), Some(1.5));
+        assert_eq!(profile.parse_number("1,5"), None);
+        assert_eq!(profile.parse_number("NaN"), None);
+        assert_eq!(profile.parse_number("inf"), None);
+    }
+
+    #[test]
+    fn time_and_signal_column() {
+        let profile = ImportProfile {
+            time_column: 1,
+            signal_columns: vec![0, 1],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            profile.validate(),
+            Err("Column 2 cannot be both time and signal".to_string())
+        );
+    }
+
+    #[test]
+    fn layout() {
+        let profile = ImportProfile {
+            delimiter: Delimiter::Whitespace,
+            metadata_lines: 2,
+            skip_lines: 1,
+            column_titles: true,
+            time_column: 1,
+            time_unit: TimeUnit::Seconds,
+            ..Default::default()
+        };
+        let text = "SampleName  Ladder\n\
+                    Operator: DTtV\n\
+                    ignored line\n\
+                    \n\
+                    Index  Time  FLR(LU)  UV\n\
+                    1  0  1.0  2.0\n\
+                    2  30  x  3.0\n\
+                    3  n.a.  1.0  1.0\n\
+                    4  60  y  z\n";
+
+        assert_eq!(
+            profile.line_roles(text),
+            vec![
+                LineRole::Metadata,
+                LineRole::Metadata,
+                LineRole::Skipped,
+                LineRole::Blank,
+                LineRole::Titles,
+                LineRole::Data,
+                LineRole::Data,
+                LineRole::Data,
+                LineRole::Data,
+            ]
+        );
+
+        let data = profile.read(text).unwrap();
+        assert_eq!(data.metadata.sample_name.as_deref(), Some("Ladder"));
+        assert_eq!(data.metadata.get("Operator"), Some("DTtV"));
+        // The index column is a signal as well, so only a line without time is rejected
+        assert_eq!(data.rejected_lines, vec![8]);
+
+        let [index, fluorescence, absorbance] = data.channels.as_slice() else {
+            panic!("expected three channels, found {}", data.channels.len());
+        };
+        assert_eq!(index.name, "Index");
+        assert_eq!(index.data.len(), 3);
+        assert_eq!(fluorescence.name, "FLR");
+        assert_eq!(fluorescence.unit.as_deref(), Some("LU"));
+        assert_eq!(fluorescence.data, vec![Point2D::new(0.0, 1.0)]);
+        assert_eq!(absorbance.name, "UV");
+        assert_eq!(
+            absorbance.data,
+            vec![Point2D::new(0.0, 2.0), Point2D::new(0.5, 3.0)]
+        );
+    }
+
+    #[test]
+    fn no_data() {
+        let profile = ImportProfile::default();
+        assert_eq!(
+            profile.read("Time\tSignal\n"),
+            Err("No data found with this profile".to_string())
+        );
+    }
+}
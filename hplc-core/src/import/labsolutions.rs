@@ -0,0 +1,198 @@
+use crate::chromatography::Channel;
+use crate::metadata::{SampleMetadata, Timestamp};
+use crate::vector::*;
+
+use super::{
+    delimiter, find_header, minutes_per_unit, parse_number, push_channel, split_fields, split_unit,
+};
+
+// Sections of results rather than of information about the run
+const SKIPPED_SECTIONS: [&str; 5] = ["table", "results", "spectrum", "3d", "group"];
+
+/// One `[... Chromatogram (...)]` section being read.
+struct Trace {
+    name: String,
+    unit: Option<String>,
+    multiplier: f64,
+    start: f64,
+    interval: f64,
+    scale: f64,
+    data: Vec<Point2D>,
+}
+
+impl Trace {
+    fn new(section: &str) -> Self {
+        // `Chromatogram (Ch1)` or `LC Chromatogram(Detector A-Ch1)`
+        let name = match split_unit(section) {
+            (_, Some(channel)) if !channel.is_empty() => channel,
+            (name, _) => name,
+        };
+
+        Trace {
+            name,
+            unit: None,
+            multiplier: 1.0,
+            start: 0.0,
+            interval: 0.0,
+            scale: 1.0,
+            data: vec![],
+        }
+    }
+
+    fn finish(self, channels: &mut Vec<Channel>) {
+        push_channel(channels, self.name, self.unit, self.data);
+    }
+}
+
+/// Reads a Shimadzu LabSolutions ASCII export, each chromatogram section is a channel.
+///
+/// The sections before them (`[Header]`, `[Sample Information]`, ...) are kept as headers.
+/// Sections of only intensities are timed by their `Start Time` and `Interval`.
+pub(crate) fn parse(text: &str) -> Result<(SampleMetadata, Vec<Channel>), String> {
+    let mut headers: Vec<(String, String)> = vec![];
+    let mut channels: Vec<Channel> = vec![];
+    let mut trace: Option<Trace> = None;
+    let mut skipped = false;
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(section) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            if let Some(trace) = trace.take() {
+                trace.finish(&mut channels);
+            }
+
+            let lowercase = section.to_ascii_lowercase();
+            skipped = SKIPPED_SECTIONS.iter().any(|name| lowercase.contains(name));
+            if lowercase.contains("chromatogram") && !skipped {
+                trace = Some(Trace::new(section));
+            }
+
+            continue;
+        }
+
+        let fields = split_fields(line, delimiter(line));
+        let Some(trace) = trace.as_mut() else {
+            if !skipped && fields.len() > 1 {
+                headers.push((fields[0].clone(), fields[1..].join(", ")));
+            }
+
+            continue;
+        };
+
+        let Some(first) = parse_number(&fields[0]) else {
+            let value = fields.get(1).and_then(|field| parse_number(field));
+            let key = fields[0].to_ascii_lowercase();
+
+            if key.starts_with("r.time") || key.starts_with("time") {
+                trace.scale = minutes_per_unit(&fields[0]);
+            } else if key.starts_with("interval") {
+                trace.interval = value.unwrap_or(0.0) * minutes_per_unit(&fields[0]);
+            } else if key.starts_with("start time") {
+                trace.start = value.unwrap_or(0.0) * minutes_per_unit(&fields[0]);
+            } else if key.starts_with("intensity units") {
+                trace.unit = fields.get(1).filter(|unit| !unit.is_empty()).cloned();
+            } else if key.starts_with("intensity multiplier") {
+                trace.multiplier = value.unwrap_or(1.0);
+            }
+
+            continue;
+        };
+
+        match fields.get(1).and_then(|field| parse_number(field)) {
+            Some(height) => trace
+                .data
+                .push(Point2D::new(first * trace.scale, height * trace.multiplier)),
+            None => {
+                let time = trace.start + trace.data.len() as f64 * trace.interval;
+                trace
+                    .data
+                    .push(Point2D::new(time, first * trace.multiplier));
+            }
+        }
+    }
+
+    if let Some(trace) = trace {
+        trace.finish(&mut channels);
+    }
+
+    let metadata = SampleMetadata {
+        sample_name: find_header(&headers, &["Sample Name", "Sample ID"]),
+        system_name: find_header(&headers, &["Instrument Name", "Instrument"]),
+        sample_set_name: find_header(&headers, &["Batch File Name", "Batch File"]),
+        date_acquired: find_header(&headers, &["Acquired", "Acquired Date"])
+            .and_then(|value| Timestamp::parse(&value)),
+        sample_set_start_date: None,
+        headers,
+    };
+
+    Ok((metadata, channels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_fixture() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test/samples/Shimadzu/PC12-9.txt");
+        let text = std::fs::read_to_string(path).unwrap();
+        let (metadata, channels) = parse(&text).unwrap();
+
+        let [fluorescence, absorbance] = channels.as_slice() else {
+            panic!("expected two channels, found {}", channels.len());
+        };
+
+        assert_eq!(fluorescence.name, "Detector A-Ch1");
+        assert_eq!(fluorescence.unit.as_deref(), Some("LU"));
+        assert_eq!(fluorescence.data.len(), 3301);
+        assert!((fluorescence.data[0].y() - 0.000246).abs() < 1e-12);
+        assert!((fluorescence.data[3300].x() - 55.0).abs() < 1e-9);
+
+        // Intensities only, timed by the interval
+        assert_eq!(absorbance.name, "Detector B-Ch1");
+        assert_eq!(absorbance.unit.as_deref(), Some("mAU"));
+        assert_eq!(absorbance.data.len(), 3301);
+        assert_eq!(absorbance.data[0], Point2D::new(0.0, 0.000123));
+        assert!((absorbance.data[60].x() - 1.0).abs() < 1e-9);
+        assert!((absorbance.data[3300].x() - 55.0).abs() < 1e-9);
+
+        assert_eq!(
+            metadata.sample_name.as_deref(),
+            Some("9. PC-12 Atp2b2 shRNA C")
+        );
+        assert_eq!(
+            metadata.sample_set_name.as_deref(),
+            Some("DTtV_15052025 GSL new HPLC.lcb")
+        );
+        assert_eq!(
+            metadata
+                .date_acquired
+                .as_ref()
+                .map(|date| date.to_string())
+                .as_deref(),
+            Some("2025-05-15 22:18:57")
+        );
+        assert_eq!(metadata.get("Application Name"), Some("LabSolutions"));
+
+        // The peak table is not a header
+        assert_eq!(metadata.get("# of Peaks"), None);
+    }
+
+    #[test]
+    fn times_in_seconds() {
+        let text = "[Chromatogram (Ch1)]\n\
+                    Time (sec),Intensity\n\
+                    0,1\n\
+                    30,2\n";
+        let (_, channels) = parse(text).unwrap();
+
+        assert_eq!(channels[0].name, "Ch1");
+        assert_eq!(
+            channels[0].data,
+            vec![Point2D::new(0.0, 1.0), Point2D::new(0.5, 2.0)]
+        );
+    }
+}
@@ -0,0 +1,413 @@
+//! Readers of the raw data exported by the chromatography data systems of each vendor.
+//!
+//! [`DataFormat::detect`] recognises a file by its content, the extension is never trusted.
+//! Empower exports and plain delimited text are read by [`Chromatography::from_file`](crate::Chromatography::from_file)
+//! itself, every other format by its own module. All of them convert times to minutes.
+//!
+//! Files in none of these formats can be described by an [`ImportProfile`] instead.
+
+use std::fmt::Display;
+
+use crate::chromatography::Channel;
+use crate::metadata::SampleMetadata;
+use crate::vector::*;
+
+mod andi;
+mod chemstation;
+mod chromeleon;
+mod delimited;
+mod labsolutions;
+mod netcdf;
+
+pub use delimited::{
+    DecimalSeparator, DelimitedData, Delimiter, ImportProfile, LineRole, TimeUnit,
+};
+
+// Keys written before the data by ChemStation and OpenLab CSV exports
+const CHEMSTATION_KEYS: [&str; 5] = [
+    "Data File",
+    "Acq. Date-Time",
+    "Acq. Method",
+    "Acq. Operator",
+    "Signal",
+];
+
+/// Vendor format of a raw data file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DataFormat {
+    /// Waters Empower `arw` export, or any delimited text of time and signal columns.
+    #[default]
+    Empower,
+    /// AIA/ANDI netCDF (ASTM E1947).
+    Andi,
+    /// Agilent ChemStation / OpenLab CSV export.
+    ChemStation,
+    /// Shimadzu LabSolutions ASCII export.
+    LabSolutions,
+    /// Thermo Chromeleon text export.
+    Chromeleon,
+    /// Delimited text read with an [`ImportProfile`], never detected.
+    Delimited,
+}
+
+impl DataFormat {
+    pub const ALL: [DataFormat; 6] = [
+        DataFormat::Empower,
+        DataFormat::Andi,
+        DataFormat::ChemStation,
+        DataFormat::LabSolutions,
+        DataFormat::Chromeleon,
+        DataFormat::Delimited,
+    ];
+
+    /// Recognises the format of a file from its content, [`DataFormat::Empower`] if nothing else matches.
+    pub fn detect(bytes: &[u8]) -> Self {
+        if netcdf::is_netcdf(bytes) {
+            return DataFormat::Andi;
+        }
+
+        let Ok(text) = decode_text(bytes) else {
+            return DataFormat::Empower;
+        };
+
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.clone().any(|line| line == "Chromatogram Data:") {
+            return DataFormat::Chromeleon;
+        }
+
+        let section = |line: &str| {
+            line.starts_with('[')
+                && line.ends_with(']')
+                && (line == "[Header]" || line.to_ascii_lowercase().contains("chromatogram"))
+        };
+        if lines.clone().any(section) {
+            return DataFormat::LabSolutions;
+        }
+
+        // ChemStation writes UTF-16, OpenLab a few keys before the data
+        let utf16 = bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]);
+        let keyed = lines.any(|line| {
+            let key = split_fields(line, delimiter(line)).into_iter().next();
+            key.is_some_and(|key| CHEMSTATION_KEYS.contains(&key.trim_end_matches(':')))
+        });
+        if utf16 || keyed {
+            return DataFormat::ChemStation;
+        }
+
+        DataFormat::Empower
+    }
+}
+
+impl Display for DataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                DataFormat::Empower => "Empower / Delimited Text",
+                DataFormat::Andi => "AIA/ANDI netCDF",
+                DataFormat::ChemStation => "Agilent ChemStation",
+                DataFormat::LabSolutions => "Shimadzu LabSolutions",
+                DataFormat::Chromeleon => "Thermo Chromeleon",
+                DataFormat::Delimited => "Delimited Text (Import Profile)",
+            }
+        )
+    }
+}
+
+/// Reads a file of any detected format but [`DataFormat::Empower`] into its header and channels.
+pub(crate) fn parse(
+    format: DataFormat,
+    bytes: &[u8],
+) -> Result<(SampleMetadata, Vec<Channel>), String> {
+    let (metadata, channels) = match format {
+        DataFormat::Empower => return Err("Empower files are read by Chromatography".to_string()),
+        DataFormat::Andi => andi::parse(bytes)?,
+        DataFormat::ChemStation => chemstation::parse(&decode_text(bytes)?)?,
+        DataFormat::LabSolutions => labsolutions::parse(&decode_text(bytes)?)?,
+        DataFormat::Chromeleon => chromeleon::parse(&decode_text(bytes)?)?,
+        DataFormat::Delimited => return Err("Delimited text needs an import profile".to_string()),
+    };
+
+    if channels.iter().all(|channel| channel.data.is_empty()) {
+        return Err(format!("No chromatogram found in the {} file", format));
+    }
+
+    Ok((metadata, channels))
+}
+
+/// Decodes UTF-8 text, or UTF-16 if the file starts with its byte order mark.
+pub fn decode_text(bytes: &[u8]) -> Result<String, String> {
+    let utf16 = |bytes: &[u8], decode: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| decode([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&units).map_err(|_| "Invalid UTF-16 text".to_string())
+    };
+
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => {
+            String::from_utf8(rest.to_vec()).map_err(|_| "Invalid UTF-8 text".to_string())
+        }
+        _ => String::from_utf8(bytes.to_vec()).map_err(|_| "Invalid UTF-8 text".to_string()),
+    }
+}
+
+/// Tab if the line has one, else comma.
+pub(crate) fn delimiter(line: &str) -> char {
+    if line.contains('\t') { '\t' } else { ',' }
+}
+
+/// Splits a delimited line, delimiters between double quotes are part of the field.
+///
+/// Fields are trimmed and their quotes removed.
+pub(crate) fn split_fields(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+        .iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+/// Factor converting the time unit named in a column title such as `Time (s)` to minutes.
+///
+/// Titles without a unit are assumed to be in minutes.
+pub(crate) fn minutes_per_unit(title: &str) -> f64 {
+    let unit = split_unit(title).1.unwrap_or_default().to_ascii_lowercase();
+    match unit.as_str() {
+        "ms" | "msec" => 1.0 / 60000.0,
+        "s" | "sec" | "second" | "seconds" => 1.0 / 60.0,
+        "h" | "hr" | "hour" | "hours" => 60.0,
+        _ => 1.0,
+    }
+}
+
+/// Separates a title from the unit written after it, `Signal (LU)` or `Value [mAU]`.
+pub(crate) fn split_unit(title: &str) -> (String, Option<String>) {
+    let title = title.trim();
+    for (open, close) in [('(', ')'), ('[', ']')] {
+        if title.ends_with(close)
+            && let Some(start) = title.rfind(open)
+        {
+            let unit = title[start + 1..title.len() - 1].trim();
+            return (title[..start].trim().to_string(), Some(unit.to_string()));
+        }
+    }
+
+    (title.to_string(), None)
+}
+
+/// Adds a channel unless it has no data, names already taken get the number of the channel appended.
+pub(crate) fn push_channel(
+    channels: &mut Vec<Channel>,
+    name: String,
+    unit: Option<String>,
+    data: Vec<Point2D>,
+) {
+    if data.is_empty() {
+        return;
+    }
+
+    let name = if channels.iter().any(|channel| channel.name == name) {
+        format!("{} ({})", name, channels.len() + 1)
+    } else {
+        name
+    };
+
+    channels.push(Channel { name, unit, data });
+}
+
+/// Value of the first of `keys` found in the headers.
+pub(crate) fn find_header(headers: &[(String, String)], keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| {
+        headers
+            .iter()
+            .find(|(name, value)| name == key && !value.is_empty())
+            .map(|(_, value)| value.clone())
+    })
+}
+
+/// Parses a number, `None` for text such as `n.a.`.
+pub(crate) fn parse_number(field: &str) -> Option<f64> {
+    field
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test/samples")
+            .join(name);
+        std::fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn detects_fixtures() {
+        let cases = [
+            (
+                "PC12/chromatogram_timeseries_46739.arw",
+                DataFormat::Empower,
+            ),
+            ("ANDI/chromatogram_46739.cdf", DataFormat::Andi),
+            ("Agilent/PC12-9.csv", DataFormat::ChemStation),
+            ("Shimadzu/PC12-9.txt", DataFormat::LabSolutions),
+            ("Chromeleon/PC12-9.txt", DataFormat::Chromeleon),
+        ];
+
+        for (name, format) in cases {
+            assert_eq!(DataFormat::detect(&fixture(name)), format, "{}", name);
+        }
+    }
+
+    #[test]
+    fn detects_text() {
+        let cases: [(&str, DataFormat); 6] = [
+            ("Time,Signal\n0,1\n", DataFormat::Empower),
+            ("0\t1\n1\t2\n", DataFormat::Empower),
+            (
+                "Acq. Operator:,DTtV\nTime (min),Signal\n",
+                DataFormat::ChemStation,
+            ),
+            (
+                "[Header]\nApplication Name\tLabSolutions\n",
+                DataFormat::LabSolutions,
+            ),
+            (
+                "[PDA Multi Chromatogram(Ch1)]\n0\t1\n",
+                DataFormat::LabSolutions,
+            ),
+            (
+                "Raw Data:\n\nChromatogram Data:\n0\t1\n",
+                DataFormat::Chromeleon,
+            ),
+        ];
+
+        for (text, format) in cases {
+            assert_eq!(DataFormat::detect(text.as_bytes()), format, "{:?}", text);
+        }
+
+        // Not text at all
+        assert_eq!(DataFormat::detect(&[0xC3, 0x28, 0xFF]), DataFormat::Empower);
+        assert_eq!(DataFormat::detect(b""), DataFormat::Empower);
+    }
+
+    #[test]
+    fn detects_utf16_as_chemstation() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "Time,Signal\n0,1\n".encode_utf16() {
+            bytes.extend(unit.to_le_bytes());
+        }
+
+        assert_eq!(DataFormat::detect(&bytes), DataFormat::ChemStation);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse(DataFormat::Empower, b"0\t1\n").is_err());
+        assert!(parse(DataFormat::Delimited, b"0\t1\n").is_err());
+
+        // Truncated in the middle of the netCDF header
+        let bytes = fixture("ANDI/chromatogram_46739.cdf");
+        assert_eq!(
+            parse(DataFormat::Andi, &bytes[..64]).err().as_deref(),
+            Some("Unexpected end of netCDF header")
+        );
+
+        assert_eq!(
+            parse(
+                DataFormat::Chromeleon,
+                b"Chromatogram Data:\nTime (min)\tValue\n"
+            )
+            .err()
+            .as_deref(),
+            Some("No chromatogram found in the Thermo Chromeleon file")
+        );
+    }
+
+    #[test]
+    fn decodes_text() {
+        assert_eq!(decode_text(b"\xEF\xBB\xBFa,b").as_deref(), Ok("a,b"));
+        assert_eq!(
+            decode_text(b"\xFF\xFEa\x00\xB5\x00").as_deref(),
+            Ok("a\u{b5}")
+        );
+        assert_eq!(decode_text(b"\xFE\xFF\x00a\x00b").as_deref(), Ok("ab"));
+        assert!(decode_text(&[0xC3, 0x28]).is_err());
+    }
+
+    #[test]
+    fn splits_fields() {
+        assert_eq!(
+            split_fields(r#"Signal,"FLD1 A, Ex=330", 2 "#, ','),
+            vec!["Signal", "FLD1 A, Ex=330", "2"]
+        );
+        assert_eq!(split_fields("a\t\tb", '\t'), vec!["a", "", "b"]);
+        assert_eq!(delimiter("a,b\tc"), '\t');
+        assert_eq!(delimiter("a,b"), ',');
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(
+            split_unit("Signal (LU)"),
+            ("Signal".to_string(), Some("LU".to_string()))
+        );
+        assert_eq!(
+            split_unit("Value [mAU]"),
+            ("Value".to_string(), Some("mAU".to_string()))
+        );
+        assert_eq!(split_unit("Signal"), ("Signal".to_string(), None));
+
+        assert_eq!(minutes_per_unit("Time (min)"), 1.0);
+        assert_eq!(minutes_per_unit("Time"), 1.0);
+        assert_eq!(minutes_per_unit("Time (s)"), 1.0 / 60.0);
+        assert_eq!(minutes_per_unit("Interval(msec)"), 1.0 / 60000.0);
+        assert_eq!(minutes_per_unit("Time [h]"), 60.0);
+    }
+
+    #[test]
+    fn channel_names() {
+        let mut channels = vec![];
+        push_channel(&mut channels, "A".to_string(), None, vec![]);
+        push_channel(
+            &mut channels,
+            "A".to_string(),
+            None,
+            vec![Point2D::new(0.0, 1.0)],
+        );
+        push_channel(
+            &mut channels,
+            "A".to_string(),
+            None,
+            vec![Point2D::new(0.0, 1.0)],
+        );
+
+        let names: Vec<&str> = channels
+            .iter()
+            .map(|channel| channel.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["A", "A (2)"]);
+    }
+}
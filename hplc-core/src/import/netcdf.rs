@@ -0,0 +1,464 @@
+const ABSENT: u32 = 0;
+const NC_DIMENSION: u32 = 0x0A;
+const NC_VARIABLE: u32 = 0x0B;
+const NC_ATTRIBUTE: u32 = 0x0C;
+
+/// Values of an attribute or variable, numbers of every width are widened to `f64`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Values {
+    Text(String),
+    Numbers(Vec<f64>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Attribute {
+    pub name: String,
+    pub values: Values,
+}
+
+#[derive(Clone, Debug)]
+struct Dimension {
+    // Zero for the record dimension
+    length: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Variable {
+    name: String,
+    dimensions: Vec<usize>,
+    kind: u32,
+    vsize: usize,
+    begin: usize,
+}
+
+/// The header of a netCDF classic or 64-bit offset file, variables are read from `bytes` on demand.
+///
+/// Only what AIA/ANDI files need is supported, there is no writing and no netCDF-4 (HDF5).
+pub(crate) struct NetCdf<'a> {
+    bytes: &'a [u8],
+    records: usize,
+    dimensions: Vec<Dimension>,
+    pub attributes: Vec<Attribute>,
+    variables: Vec<Variable>,
+}
+
+/// Whether the bytes start with the magic number of a classic or 64-bit offset netCDF file.
+pub(crate) fn is_netcdf(bytes: &[u8]) -> bool {
+    bytes.len() >= 4 && &bytes[..3] == b"CDF" && (bytes[3] == 1 || bytes[3] == 2)
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("Unexpected end of netCDF header")?;
+
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        let bytes = self.take(8)?;
+        let mut array = [0; 8];
+        array.copy_from_slice(bytes);
+        Ok(u64::from_be_bytes(array))
+    }
+
+    fn count(&mut self) -> Result<usize, String> {
+        Ok(self.u32()? as usize)
+    }
+
+    /// Skips the padding to the next multiple of 4 bytes.
+    fn align(&mut self) -> Result<(), String> {
+        let padding = (4 - self.position % 4) % 4;
+        self.take(padding).map(|_| ())
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let length = self.count()?;
+        let name = String::from_utf8_lossy(self.take(length)?).to_string();
+        self.align()?;
+        Ok(name)
+    }
+
+    /// A list is either absent or tagged with the expected kind.
+    fn list(&mut self, tag: u32) -> Result<usize, String> {
+        let found = self.u32()?;
+        let count = self.count()?;
+        match found {
+            ABSENT if count == 0 => Ok(0),
+            found if found == tag => Ok(count),
+            _ => Err(format!("Malformed netCDF header, expected list {:#x}", tag)),
+        }
+    }
+
+    fn attributes(&mut self) -> Result<Vec<Attribute>, String> {
+        let count = self.list(NC_ATTRIBUTE)?;
+        let mut attributes = vec![];
+        for _ in 0..count {
+            let name = self.name()?;
+            let kind = self.u32()?;
+            let length = self.count()?;
+            let size = type_size(kind)?;
+            let bytes = self.take(length.checked_mul(size).ok_or("Attribute too large")?)?;
+            self.align()?;
+
+            attributes.push(Attribute {
+                name,
+                values: decode(kind, bytes)?,
+            });
+        }
+
+        Ok(attributes)
+    }
+}
+
+fn type_size(kind: u32) -> Result<usize, String> {
+    match kind {
+        1 | 2 => Ok(1),
+        3 => Ok(2),
+        4 | 5 => Ok(4),
+        6 => Ok(8),
+        kind => Err(format!("Unsupported netCDF type {}", kind)),
+    }
+}
+
+/// Big-endian values of the given type, text is cut at the first NUL.
+fn decode(kind: u32, bytes: &[u8]) -> Result<Values, String> {
+    let values = match kind {
+        1 => Values::Numbers(bytes.iter().map(|&byte| byte as i8 as f64).collect()),
+        2 => {
+            let end = bytes
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(bytes.len());
+            Values::Text(String::from_utf8_lossy(&bytes[..end]).trim().to_string())
+        }
+        3 => Values::Numbers(
+            bytes
+                .chunks_exact(2)
+                .map(|chunk| i16::from_be_bytes([chunk[0], chunk[1]]) as f64)
+                .collect(),
+        ),
+        4 => Values::Numbers(
+            bytes
+                .chunks_exact(4)
+                .map(|chunk| i32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as f64)
+                .collect(),
+        ),
+        5 => Values::Numbers(
+            bytes
+                .chunks_exact(4)
+                .map(|chunk| f32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as f64)
+                .collect(),
+        ),
+        6 => Values::Numbers(
+            bytes
+                .chunks_exact(8)
+                .map(|chunk| {
+                    let mut array = [0; 8];
+                    array.copy_from_slice(chunk);
+                    f64::from_be_bytes(array)
+                })
+                .collect(),
+        ),
+        kind => return Err(format!("Unsupported netCDF type {}", kind)),
+    };
+
+    Ok(values)
+}
+
+impl<'a> NetCdf<'a> {
+    /// Parses the header, fails if the bytes are not a classic or 64-bit offset netCDF file.
+    pub(crate) fn parse(bytes: &'a [u8]) -> Result<Self, String> {
+        if !is_netcdf(bytes) {
+            return Err("Not a netCDF classic file".to_string());
+        }
+
+        let wide_offsets = bytes[3] == 2;
+        let mut cursor = Cursor { bytes, position: 4 };
+
+        // Streaming files (all ones) count their records from the file size instead
+        let records = cursor.u32()?;
+
+        let mut dimensions = vec![];
+        for _ in 0..cursor.list(NC_DIMENSION)? {
+            let _name = cursor.name()?;
+            let length = cursor.count()?;
+            dimensions.push(Dimension { length });
+        }
+
+        let attributes = cursor.attributes()?;
+
+        let mut variables = vec![];
+        for _ in 0..cursor.list(NC_VARIABLE)? {
+            let name = cursor.name()?;
+            let count = cursor.count()?;
+            let mut ids = vec![];
+            for _ in 0..count {
+                let id = cursor.count()?;
+                if id >= dimensions.len() {
+                    return Err(format!("Variable {} has an unknown dimension", name));
+                }
+                ids.push(id);
+            }
+
+            let _attributes = cursor.attributes()?;
+            let kind = cursor.u32()?;
+            let vsize = cursor.count()?;
+            let begin = if wide_offsets {
+                cursor.u64()? as usize
+            } else {
+                cursor.count()?
+            };
+
+            variables.push(Variable {
+                name,
+                dimensions: ids,
+                kind,
+                vsize,
+                begin,
+            });
+        }
+
+        let mut netcdf = NetCdf {
+            bytes,
+            records: records as usize,
+            dimensions,
+            attributes,
+            variables,
+        };
+
+        if records == u32::MAX {
+            let record_size = netcdf.record_size()?;
+            let start = netcdf
+                .variables
+                .iter()
+                .filter(|variable| netcdf.is_record(variable))
+                .map(|variable| variable.begin)
+                .min()
+                .unwrap_or(bytes.len());
+            netcdf.records = bytes.len().saturating_sub(start) / record_size.max(1);
+        }
+
+        Ok(netcdf)
+    }
+
+    /// Global attribute with the given name.
+    pub(crate) fn attribute(&self, name: &str) -> Option<&Values> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name == name)
+            .map(|attribute| &attribute.values)
+    }
+
+    /// Global text attribute with the given name, `None` if absent, empty or not text.
+    pub(crate) fn text(&self, name: &str) -> Option<String> {
+        match self.attribute(name)? {
+            Values::Text(text) if !text.is_empty() => Some(text.clone()),
+            _ => None,
+        }
+    }
+
+    /// Every value of the variable in storage order, `None` if it is absent.
+    pub(crate) fn variable(&self, name: &str) -> Option<Result<Values, String>> {
+        let variable = self
+            .variables
+            .iter()
+            .find(|variable| variable.name == name)?;
+        Some(self.read(variable))
+    }
+
+    /// First value of a numeric variable, for scalars.
+    pub(crate) fn scalar(&self, name: &str) -> Option<f64> {
+        match self.variable(name)? {
+            Ok(Values::Numbers(numbers)) => numbers.first().copied(),
+            _ => None,
+        }
+    }
+
+    fn is_record(&self, variable: &Variable) -> bool {
+        variable
+            .dimensions
+            .first()
+            .is_some_and(|&id| self.dimensions[id].length == 0)
+    }
+
+    /// Bytes of one value of the variable per record (or of the whole variable).
+    fn slab_size(&self, variable: &Variable) -> Result<usize, String> {
+        let skip = usize::from(self.is_record(variable));
+        variable.dimensions[skip..]
+            .iter()
+            .map(|&id| self.dimensions[id].length)
+            .try_fold(type_size(variable.kind)?, usize::checked_mul)
+            .ok_or("Variable too large".to_string())
+    }
+
+    /// Bytes of one record of every record variable, which are interleaved.
+    fn record_size(&self) -> Result<usize, String> {
+        let record_variables: Vec<&Variable> = self
+            .variables
+            .iter()
+            .filter(|variable| self.is_record(variable))
+            .collect();
+
+        // A single record variable is packed without padding
+        if let [variable] = record_variables.as_slice() {
+            return self.slab_size(variable);
+        }
+
+        Ok(record_variables.iter().map(|variable| variable.vsize).sum())
+    }
+
+    fn read(&self, variable: &Variable) -> Result<Values, String> {
+        let slab = self.slab_size(variable)?;
+        let slice = |start: usize, length: usize| {
+            start
+                .checked_add(length)
+                .and_then(|end| self.bytes.get(start..end))
+                .ok_or(format!("Variable {} is truncated", variable.name))
+        };
+
+        if !self.is_record(variable) {
+            return decode(variable.kind, slice(variable.begin, slab)?);
+        }
+
+        // Counts come from the file, a total larger than the file can only be truncated
+        let truncated = || format!("Variable {} is truncated", variable.name);
+        let total = slab
+            .checked_mul(self.records)
+            .filter(|total| *total <= self.bytes.len())
+            .ok_or_else(truncated)?;
+
+        let record_size = self.record_size()?;
+        let mut bytes = Vec::with_capacity(total);
+        for record in 0..self.records {
+            let start = record
+                .checked_mul(record_size)
+                .and_then(|offset| offset.checked_add(variable.begin))
+                .ok_or_else(truncated)?;
+            bytes.extend_from_slice(slice(start, slab)?);
+        }
+
+        decode(variable.kind, &bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Vec<u8> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test/samples/ANDI/chromatogram_46739.cdf");
+        std::fs::read(path).unwrap()
+    }
+
+    fn name(bytes: &mut Vec<u8>, name: &str) {
+        bytes.extend((name.len() as u32).to_be_bytes());
+        bytes.extend(name.as_bytes());
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+    }
+
+    /// A file of one float record variable `v` claiming `records` records.
+    fn record_file(records: u32, values: &[f32]) -> Vec<u8> {
+        let mut bytes = b"CDF\x01".to_vec();
+        bytes.extend(records.to_be_bytes());
+        bytes.extend([NC_DIMENSION, 1].map(u32::to_be_bytes).concat());
+        name(&mut bytes, "n");
+        bytes.extend(0u32.to_be_bytes());
+        bytes.extend([ABSENT, 0].map(u32::to_be_bytes).concat());
+        bytes.extend([NC_VARIABLE, 1].map(u32::to_be_bytes).concat());
+        name(&mut bytes, "v");
+        bytes.extend([1, 0, ABSENT, 0, 5, 4].map(u32::to_be_bytes).concat());
+        let begin = bytes.len() as u32 + 4;
+        bytes.extend(begin.to_be_bytes());
+        for value in values {
+            bytes.extend(value.to_be_bytes());
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn magic_number() {
+        assert!(is_netcdf(b"CDF\x01rest"));
+        assert!(is_netcdf(b"CDF\x02"));
+        assert!(!is_netcdf(b"CDF\x03"));
+        assert!(!is_netcdf(b"CDF"));
+        assert!(!is_netcdf(b"\x89HDF\r\n"));
+    }
+
+    #[test]
+    fn reads_fixture() {
+        let bytes = fixture();
+        let netcdf = NetCdf::parse(&bytes).unwrap();
+
+        assert_eq!(netcdf.text("detector_unit").as_deref(), Some("LU"));
+        assert_eq!(netcdf.text("missing"), None);
+        assert_eq!(
+            netcdf.attribute("sample_injection_volume"),
+            Some(&Values::Numbers(vec![50.0]))
+        );
+        assert!((netcdf.scalar("actual_sampling_interval").unwrap() - 1.0).abs() < 1e-9);
+
+        match netcdf.variable("ordinate_values") {
+            Some(Ok(Values::Numbers(heights))) => assert_eq!(heights.len(), 3301),
+            other => panic!("unexpected ordinate_values {:?}", other),
+        }
+        assert!(netcdf.variable("missing").is_none());
+    }
+
+    #[test]
+    fn truncated_header() {
+        let bytes = fixture();
+        for length in [4, 8, 20, 100] {
+            let result = NetCdf::parse(&bytes[..length]);
+            assert_eq!(
+                result.err().as_deref(),
+                Some("Unexpected end of netCDF header")
+            );
+        }
+    }
+
+    #[test]
+    fn record_variables() {
+        let bytes = record_file(3, &[1.0, 2.0, 3.0]);
+        let netcdf = NetCdf::parse(&bytes).unwrap();
+        assert_eq!(
+            netcdf.variable("v"),
+            Some(Ok(Values::Numbers(vec![1.0, 2.0, 3.0])))
+        );
+
+        // Streaming files count the records that fit in the file
+        let bytes = record_file(u32::MAX, &[1.0, 2.0]);
+        let netcdf = NetCdf::parse(&bytes).unwrap();
+        assert_eq!(
+            netcdf.variable("v"),
+            Some(Ok(Values::Numbers(vec![1.0, 2.0])))
+        );
+    }
+
+    #[test]
+    fn record_count_beyond_file() {
+        let bytes = record_file(u32::MAX - 1, &[1.0]);
+        let netcdf = NetCdf::parse(&bytes).unwrap();
+        assert_eq!(
+            netcdf.variable("v"),
+            Some(Err("Variable v is truncated".to_string()))
+        );
+    }
+}
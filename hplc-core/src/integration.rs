@@ -0,0 +1,780 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::component::Peak;
+use crate::vector::{Point2D, Vector2};
+
+/// How the area of a peak is separated from the baseline and from its neighbours.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IntegrationMode {
+    /// Area down to the baseline, overlapping peaks are split by a vertical line at the valley.
+    #[default]
+    DropLine,
+    /// Area above a straight line between the start and end of every peak.
+    ValleyToValley,
+    /// Small peaks on the flank of a larger peak are cut off by a straight line between their valleys,
+    /// the area below the line belongs to the larger peak.
+    TangentSkim,
+    /// Same as tangent skim but tail riders are cut off by the exponential decay of the larger peak.
+    ExponentialSkim,
+}
+
+impl IntegrationMode {
+    pub const ALL: [IntegrationMode; 4] = [
+        IntegrationMode::DropLine,
+        IntegrationMode::ValleyToValley,
+        IntegrationMode::TangentSkim,
+        IntegrationMode::ExponentialSkim,
+    ];
+}
+
+impl Display for IntegrationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IntegrationMode::DropLine => "Drop Line",
+            IntegrationMode::ValleyToValley => "Valley to Valley",
+            IntegrationMode::TangentSkim => "Tangent Skim",
+            IntegrationMode::ExponentialSkim => "Exponential Skim",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for IntegrationMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "drop-line" | "drop" => Ok(IntegrationMode::DropLine),
+            "valley-to-valley" | "valley" => Ok(IntegrationMode::ValleyToValley),
+            "tangent-skim" | "tangent" => Ok(IntegrationMode::TangentSkim),
+            "exponential-skim" | "exponential" => Ok(IntegrationMode::ExponentialSkim),
+            other => Err(format!("Unknown integration mode {}", other)),
+        }
+    }
+}
+
+/// Integration mode used for peaks with a retention time within `start..end`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegrationWindow {
+    pub start: f64,
+    pub end: f64,
+    pub mode: IntegrationMode,
+}
+
+/// What a timed integration event does within its time range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EventKind {
+    /// No peaks are reported with a retention time in the range.
+    #[default]
+    InhibitIntegration,
+    /// The whole range is reported as a single peak, trimming any peak overlapping it.
+    ForcePeak,
+    /// Replaces the height requirement within the range.
+    HeightRequirement,
+    /// Replaces the inflection requirement within the range.
+    InflectionRequirement,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 4] = [
+        EventKind::InhibitIntegration,
+        EventKind::ForcePeak,
+        EventKind::HeightRequirement,
+        EventKind::InflectionRequirement,
+    ];
+
+    /// Whether the event carries a value.
+    pub fn has_value(&self) -> bool {
+        matches!(
+            self,
+            EventKind::HeightRequirement | EventKind::InflectionRequirement
+        )
+    }
+}
+
+impl Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EventKind::InhibitIntegration => "Inhibit Integration",
+            EventKind::ForcePeak => "Force Peak",
+            EventKind::HeightRequirement => "Height Requirement",
+            EventKind::InflectionRequirement => "Inflection Requirement",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "inhibit-integration" | "inhibit" => Ok(EventKind::InhibitIntegration),
+            "force-peak" | "force" => Ok(EventKind::ForcePeak),
+            "height-requirement" | "height" => Ok(EventKind::HeightRequirement),
+            "inflection-requirement" | "inflection" => Ok(EventKind::InflectionRequirement),
+            other => Err(format!("Unknown integration event {}", other)),
+        }
+    }
+}
+
+/// An integration event active from `start` to `end`, or to the end of the data if there is no end.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegrationEvent {
+    pub kind: EventKind,
+    pub start: f64,
+    pub end: Option<f64>,
+    /// Requirement used by height and inflection events, ignored by the others.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub value: f64,
+}
+
+impl IntegrationEvent {
+    pub fn contains(&self, time: f64) -> bool {
+        time >= self.start && self.end.is_none_or(|end| time <= end)
+    }
+}
+
+/// Integration configuration of a sample.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntegrationSettings {
+    /// Mode outside of every window.
+    pub mode: IntegrationMode,
+    /// Windows overriding the mode, the first matching window is used.
+    pub windows: Vec<IntegrationWindow>,
+    /// A peak is only skimmed if its neighbour is at least this many times higher.
+    pub skim_ratio: f64,
+    /// Timed events, later events take precedence over earlier ones.
+    pub events: Vec<IntegrationEvent>,
+}
+
+impl Default for IntegrationSettings {
+    fn default() -> Self {
+        Self {
+            mode: IntegrationMode::DropLine,
+            windows: vec![],
+            skim_ratio: 10.0,
+            events: vec![],
+        }
+    }
+}
+
+impl IntegrationSettings {
+    /// Mode of a peak with its retention point at `time`.
+    pub fn mode_at(&self, time: f64) -> IntegrationMode {
+        self.windows
+            .iter()
+            .find(|window| (window.start..window.end).contains(&time))
+            .map_or(self.mode, |window| window.mode)
+    }
+
+    /// Value of the last event of `kind` active at `time`.
+    pub fn event_value(&self, kind: EventKind, time: f64) -> Option<f64> {
+        self.events
+            .iter()
+            .rev()
+            .find(|event| event.kind == kind && event.contains(time))
+            .map(|event| event.value)
+    }
+}
+
+/// Applies inhibit and force peak events to the detected peaks.
+pub(crate) fn apply_events(
+    peaks: &mut Vec<Peak>,
+    data: &[Point2D],
+    baseline: &[Point2D],
+    events: &[IntegrationEvent],
+) {
+    if data.is_empty() {
+        return;
+    }
+
+    for event in events {
+        match event.kind {
+            EventKind::InhibitIntegration => {
+                peaks.retain(|peak| !event.contains(peak.retention_point.x()));
+            }
+            EventKind::ForcePeak => force_peak(peaks, data, baseline, event.start, event.end),
+            EventKind::HeightRequirement | EventKind::InflectionRequirement => {}
+        }
+    }
+}
+
+/// Replaces every peak from `from` to `to` (or the end of the data) with a single peak spanning the range.
+pub(crate) fn force_peak(
+    peaks: &mut Vec<Peak>,
+    data: &[Point2D],
+    baseline: &[Point2D],
+    from: f64,
+    to: Option<f64>,
+) {
+    let index = |x: f64| {
+        data.partition_point(|point| point.x() < x)
+            .min(data.len() - 1)
+    };
+
+    let start = index(from);
+    let end = to.map_or(data.len() - 1, index);
+    if end <= start {
+        return;
+    }
+
+    let (from, to) = (data[start].x(), data[end].x());
+    peaks.retain(|peak| !(from..=to).contains(&peak.retention_point.x()));
+
+    // Trim the neighbours back to the forced peak
+    for peak in peaks.iter_mut() {
+        let (peak_start, peak_end) = span(data, peak);
+        if peak.retention_point.x() < from && peak_end > start {
+            peak.end = data[start];
+            peak.area = area(data, baseline, peak_start, start);
+        } else if peak.retention_point.x() > to && peak_start < end {
+            peak.start = data[end];
+            peak.area = area(data, baseline, end, peak_end);
+        }
+    }
+
+    let position = peaks.partition_point(|peak| peak.start.x() < from);
+    peaks.insert(position, peak_between(data, baseline, start, end));
+}
+
+/// Peak from index `start` to `end` with its retention point at the highest point above the baseline.
+pub(crate) fn peak_between(
+    data: &[Point2D],
+    baseline: &[Point2D],
+    start: usize,
+    end: usize,
+) -> Peak {
+    let apex = (start..=end)
+        .max_by(|lhs, rhs| {
+            let lhs = data[*lhs].y() - baseline[*lhs].y();
+            let rhs = data[*rhs].y() - baseline[*rhs].y();
+            lhs.total_cmp(&rhs)
+        })
+        .unwrap_or(start);
+
+    Peak {
+        start: data[start],
+        retention_point: data[apex],
+        end: data[end],
+        height: data[apex].y() - baseline[apex].y(),
+        area: area(data, baseline, start, end),
+        ..Default::default()
+    }
+}
+
+/// Trapezium area between the data and the baseline from `start` to `end`.
+fn area(data: &[Point2D], baseline: &[Point2D], start: usize, end: usize) -> f64 {
+    let mut area = 0.0;
+    for i in start + 1..=end {
+        let width = data[i].x() - data[i - 1].x();
+        let a = data[i - 1].y() - baseline[i - 1].y();
+        let b = data[i].y() - baseline[i].y();
+        area += 0.5 * width * (a + b);
+    }
+
+    area
+}
+
+/// Applies the integration settings to peaks found with drop lines.
+///
+/// Every peak receives the baseline it is integrated against, areas and heights are recalculated
+/// for peaks which are not integrated with drop lines.
+pub(crate) fn integrate(
+    peaks: &mut [Peak],
+    data: &[Point2D],
+    baseline: &[Point2D],
+    settings: &IntegrationSettings,
+) {
+    let spans: Vec<(usize, usize)> = peaks.iter().map(|peak| span(data, peak)).collect();
+    for (peak, (start, end)) in peaks.iter_mut().zip(&spans) {
+        peak.baseline = baseline[*start..=*end].to_vec();
+    }
+
+    // Riders are compared against the heights above the common baseline
+    let heights: Vec<f64> = peaks.iter().map(|peak| peak.height).collect();
+    let mut parents: Vec<Option<usize>> = vec![None; peaks.len()];
+
+    for i in 0..peaks.len() {
+        let (start, end) = spans[i];
+        let mode = settings.mode_at(peaks[i].retention_point.x());
+
+        match mode {
+            IntegrationMode::DropLine => {}
+            IntegrationMode::ValleyToValley => {
+                let line = straight_line(data, start, end);
+                set_peak_baseline(&mut peaks[i], data, start, line);
+            }
+            IntegrationMode::TangentSkim | IntegrationMode::ExponentialSkim => {
+                let adjacent = |lhs: usize, rhs: usize| peaks[lhs].end.x() == peaks[rhs].start.x();
+                let is_rider = |parent: usize| heights[i] * settings.skim_ratio <= heights[parent];
+
+                // Tail rider of the previous peak, or of the peak the previous rider sits on
+                let tail_parent = if i > 0 && adjacent(i - 1, i) {
+                    [Some(i - 1), parents[i - 1]]
+                        .into_iter()
+                        .flatten()
+                        .find(|parent| *parent < i && is_rider(*parent))
+                } else {
+                    None
+                };
+
+                let front_parent = if i + 1 < peaks.len() && adjacent(i, i + 1) && is_rider(i + 1) {
+                    Some(i + 1)
+                } else {
+                    None
+                };
+
+                let (parent, skim) = match (tail_parent, front_parent) {
+                    (Some(parent), _) => {
+                        let exponential = match mode {
+                            IntegrationMode::ExponentialSkim => exponential_skim(
+                                data,
+                                baseline,
+                                spans[parent].0,
+                                start,
+                                end,
+                                &peaks[parent],
+                            ),
+                            _ => None,
+                        };
+
+                        let skim = exponential.unwrap_or_else(|| straight_line(data, start, end));
+                        (parent, skim)
+                    }
+                    (None, Some(parent)) => (parent, straight_line(data, start, end)),
+                    (None, None) => continue,
+                };
+
+                parents[i] = Some(parent);
+                let skimmed = set_peak_baseline(&mut peaks[i], data, start, skim);
+                peaks[parent].area += skimmed;
+            }
+        }
+    }
+}
+
+/// Indices of the start and end of a peak within the data.
+pub(crate) fn span(data: &[Point2D], peak: &Peak) -> (usize, usize) {
+    let index = |x: f64| {
+        data.partition_point(|point| point.x() < x)
+            .min(data.len() - 1)
+    };
+
+    let start = index(peak.start.x());
+    let end = index(peak.end.x()).max(start);
+    (start, end)
+}
+
+fn straight_line(data: &[Point2D], start: usize, end: usize) -> Vec<Point2D> {
+    let origin = data[start];
+    let gradient = if end > start {
+        origin.gradient(&data[end])
+    } else {
+        0.0
+    };
+
+    data[start..=end]
+        .iter()
+        .map(|point| Point2D::new(point.x(), origin.y() + gradient * (point.x() - origin.x())))
+        .collect()
+}
+
+/// Decay of the parent's tail, fitted to the log of its height between its apex and the start of the rider.
+fn exponential_skim(
+    data: &[Point2D],
+    baseline: &[Point2D],
+    parent_start: usize,
+    start: usize,
+    end: usize,
+    parent: &Peak,
+) -> Option<Vec<Point2D>> {
+    let apex = data[parent_start..=start]
+        .iter()
+        .position(|point| point.x() >= parent.retention_point.x())?
+        + parent_start;
+
+    let samples: Vec<(f64, f64)> = (apex..=start)
+        .map(|index| (data[index].x(), data[index].y() - baseline[index].y()))
+        .filter(|(_, height)| *height > 0.0)
+        .map(|(time, height)| (time, height.ln()))
+        .collect();
+
+    if samples.len() < 3 {
+        return None;
+    }
+
+    // Least squares line through (time, ln height)
+    let count = samples.len() as f64;
+    let mean_x = samples.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = samples.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let covariance: f64 = samples
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = samples.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let rate = covariance / variance;
+
+    if !rate.is_finite() || rate >= 0.0 {
+        return None;
+    }
+
+    let origin = data[start];
+    let origin_height = origin.y() - baseline[start].y();
+
+    let skim = (start..=end)
+        .map(|index| {
+            let time = data[index].x();
+            let decay = origin_height * (rate * (time - origin.x())).exp();
+            Point2D::new(time, baseline[index].y() + decay)
+        })
+        .collect();
+
+    Some(skim)
+}
+
+/// Integrates the peak against its own baseline, which is clipped to never lie above the data.
+///
+/// Returns the area the peak lost.
+fn set_peak_baseline(
+    peak: &mut Peak,
+    data: &[Point2D],
+    start: usize,
+    mut line: Vec<Point2D>,
+) -> f64 {
+    for (offset, point) in line.iter_mut().enumerate() {
+        *point = Point2D::new(point.x(), point.y().min(data[start + offset].y()));
+    }
+
+    let mut area = 0.0;
+    for offset in 1..line.len() {
+        let prev = data[start + offset - 1];
+        let next = data[start + offset];
+
+        let width = next.x() - prev.x();
+        let a = prev.y() - line[offset - 1].y();
+        let b = next.y() - line[offset].y();
+        area += 0.5 * width * (a + b);
+    }
+
+    let retention = line
+        .iter()
+        .position(|point| point.x() >= peak.retention_point.x())
+        .unwrap_or(line.len() - 1);
+
+    let lost = peak.area - area;
+    peak.area = area;
+    peak.height = peak.retention_point.y() - line[retention].y();
+    peak.baseline = line;
+
+    lost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::interpolate;
+
+    /// Samples every 0.1 minutes of the lines through `knots`, so trapezium areas are exact.
+    fn trace(knots: &[(f64, f64)]) -> Vec<Point2D> {
+        let knots: Vec<Point2D> = knots.iter().map(|(x, y)| Point2D::new(*x, *y)).collect();
+        let last = (knots[knots.len() - 1].x() * 10.0).round() as usize;
+
+        (0..=last)
+            .map(|i| {
+                let time = i as f64 / 10.0;
+                Point2D::new(time, interpolate(&knots, time).unwrap())
+            })
+            .collect()
+    }
+
+    fn flat(data: &[Point2D]) -> Vec<Point2D> {
+        data.iter()
+            .map(|point| Point2D::new(point.x(), 0.0))
+            .collect()
+    }
+
+    fn index(data: &[Point2D], time: f64) -> usize {
+        data.partition_point(|point| point.x() < time)
+    }
+
+    /// A parent peak from 0 to 3 minutes with a rider on its tail from 3 to 4 minutes.
+    fn rider() -> (Vec<Point2D>, Vec<Peak>) {
+        let data = trace(&[
+            (0.0, 0.0),
+            (2.0, 100.0),
+            (3.0, 50.0),
+            (3.5, 60.0),
+            (4.0, 40.0),
+            (6.0, 0.0),
+        ]);
+        let baseline = flat(&data);
+        let peaks = vec![
+            peak_between(&data, &baseline, 0, index(&data, 3.0)),
+            peak_between(&data, &baseline, index(&data, 3.0), index(&data, 4.0)),
+        ];
+
+        (data, peaks)
+    }
+
+    fn settings(mode: IntegrationMode) -> IntegrationSettings {
+        IntegrationSettings {
+            mode,
+            skim_ratio: 1.5,
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(value: f64, expected: f64) {
+        assert!((value - expected).abs() < 1e-9, "{} != {}", value, expected);
+    }
+
+    #[test]
+    fn drop_line() {
+        let (data, mut peaks) = rider();
+        integrate(
+            &mut peaks,
+            &data,
+            &flat(&data),
+            &settings(IntegrationMode::DropLine),
+        );
+
+        assert_close(peaks[0].area, 175.0);
+        assert_close(peaks[1].area, 52.5);
+        assert_eq!(peaks[0].baseline.len(), 31);
+        assert!(peaks[1].baseline.iter().all(|point| point.y() == 0.0));
+    }
+
+    #[test]
+    fn valley_to_valley() {
+        let (data, mut peaks) = rider();
+        integrate(
+            &mut peaks,
+            &data,
+            &flat(&data),
+            &settings(IntegrationMode::ValleyToValley),
+        );
+
+        // Line from (0, 0) to (3, 50) below the parent, from (3, 50) to (4, 40) below the rider
+        assert_close(peaks[0].area, 175.0 - 75.0);
+        assert_close(peaks[0].height, 100.0 - 100.0 / 3.0);
+        assert_close(peaks[1].area, 7.5);
+        assert_close(peaks[1].height, 15.0);
+        assert_close(peaks[0].baseline[30].y(), 50.0);
+    }
+
+    #[test]
+    fn tangent_skim() {
+        let (data, mut peaks) = rider();
+        integrate(
+            &mut peaks,
+            &data,
+            &flat(&data),
+            &settings(IntegrationMode::TangentSkim),
+        );
+
+        // The area below the skim moves to the parent, which keeps the common baseline
+        assert_close(peaks[1].area, 7.5);
+        assert_close(peaks[1].height, 15.0);
+        assert_close(peaks[0].area, 175.0 + 45.0);
+        assert!(peaks[0].baseline.iter().all(|point| point.y() == 0.0));
+    }
+
+    #[test]
+    fn tangent_skim_front_rider() {
+        let data = trace(&[
+            (0.0, 0.0),
+            (0.5, 30.0),
+            (1.0, 20.0),
+            (3.0, 100.0),
+            (5.0, 0.0),
+        ]);
+        let baseline = flat(&data);
+        let mut peaks = vec![
+            peak_between(&data, &baseline, 0, index(&data, 1.0)),
+            peak_between(&data, &baseline, index(&data, 1.0), index(&data, 5.0)),
+        ];
+        integrate(
+            &mut peaks,
+            &data,
+            &baseline,
+            &settings(IntegrationMode::TangentSkim),
+        );
+
+        // Line from (0, 0) to (1, 20) below the rider
+        assert_close(peaks[0].area, 10.0);
+        assert_close(peaks[1].area, 220.0 + 10.0);
+    }
+
+    #[test]
+    fn skim_needs_ratio() {
+        let (data, mut peaks) = rider();
+        let mut settings = settings(IntegrationMode::TangentSkim);
+        settings.skim_ratio = 2.0;
+        integrate(&mut peaks, &data, &flat(&data), &settings);
+
+        assert_close(peaks[0].area, 175.0);
+        assert_close(peaks[1].area, 52.5);
+    }
+
+    #[test]
+    fn exponential_skim() {
+        // Exponential tail of the parent with a triangle of height 20 riding on it
+        let data: Vec<Point2D> = (0..=60)
+            .map(|i| {
+                let time = i as f64 / 10.0;
+                let parent = if time < 2.0 {
+                    50.0 * time
+                } else {
+                    100.0 * (2.0 - time).exp()
+                };
+                let rider = (20.0 - 40.0 * (time - 3.5).abs()).max(0.0);
+                Point2D::new(time, parent + rider)
+            })
+            .collect();
+        let baseline = flat(&data);
+        let mut peaks = vec![
+            peak_between(&data, &baseline, 0, 30),
+            peak_between(&data, &baseline, 30, 40),
+        ];
+        let (parent, dropped) = (peaks[0].area, peaks[1].area);
+
+        integrate(
+            &mut peaks,
+            &data,
+            &baseline,
+            &settings(IntegrationMode::ExponentialSkim),
+        );
+
+        assert_close(peaks[1].area, 10.0);
+        assert_close(peaks[1].height, 20.0);
+        assert!((peaks[1].baseline[5].y() - 100.0 * (-1.5f64).exp()).abs() < 1e-9);
+        assert_close(peaks[0].area, parent + dropped - 10.0);
+
+        // A straight skim lies above the convex decay and cuts into the rider
+        let mut tangent = vec![
+            peak_between(&data, &baseline, 0, 30),
+            peak_between(&data, &baseline, 30, 40),
+        ];
+        integrate(
+            &mut tangent,
+            &data,
+            &baseline,
+            &settings(IntegrationMode::TangentSkim),
+        );
+        assert!(tangent[1].area < peaks[1].area);
+    }
+
+    #[test]
+    fn windows() {
+        let (data, mut peaks) = rider();
+        let mut settings = settings(IntegrationMode::DropLine);
+        settings.windows = vec![
+            IntegrationWindow {
+                start: 3.0,
+                end: 4.0,
+                mode: IntegrationMode::TangentSkim,
+            },
+            IntegrationWindow {
+                start: 1.0,
+                end: 5.0,
+                mode: IntegrationMode::ValleyToValley,
+            },
+        ];
+
+        assert_eq!(settings.mode_at(0.5), IntegrationMode::DropLine);
+        assert_eq!(settings.mode_at(2.0), IntegrationMode::ValleyToValley);
+        assert_eq!(settings.mode_at(3.5), IntegrationMode::TangentSkim);
+        assert_eq!(settings.mode_at(5.0), IntegrationMode::DropLine);
+
+        integrate(&mut peaks, &data, &flat(&data), &settings);
+        assert_close(peaks[0].area, 100.0 + 45.0);
+        assert_close(peaks[1].area, 7.5);
+    }
+
+    #[test]
+    fn event_values() {
+        let event = |kind, start, end, value| IntegrationEvent {
+            kind,
+            start,
+            end,
+            value,
+        };
+        let settings = IntegrationSettings {
+            events: vec![
+                event(EventKind::HeightRequirement, 1.0, None, 5.0),
+                event(EventKind::HeightRequirement, 2.0, Some(3.0), 8.0),
+                event(EventKind::InflectionRequirement, 0.0, Some(1.0), 2.0),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            settings.event_value(EventKind::HeightRequirement, 0.5),
+            None
+        );
+        assert_eq!(
+            settings.event_value(EventKind::HeightRequirement, 1.5),
+            Some(5.0)
+        );
+        assert_eq!(
+            settings.event_value(EventKind::HeightRequirement, 2.5),
+            Some(8.0)
+        );
+        assert_eq!(
+            settings.event_value(EventKind::HeightRequirement, 30.0),
+            Some(5.0)
+        );
+        assert_eq!(
+            settings.event_value(EventKind::InflectionRequirement, 1.0),
+            Some(2.0)
+        );
+        assert_eq!(
+            settings.event_value(EventKind::InflectionRequirement, 1.1),
+            None
+        );
+    }
+
+    #[test]
+    fn inhibit_integration() {
+        let (data, mut peaks) = rider();
+        let events = [IntegrationEvent {
+            kind: EventKind::InhibitIntegration,
+            start: 3.2,
+            end: None,
+            value: 0.0,
+        }];
+        apply_events(&mut peaks, &data, &flat(&data), &events);
+
+        assert_eq!(peaks.len(), 1);
+        assert_eq!(peaks[0].retention_point.x(), 2.0);
+    }
+
+    #[test]
+    fn force_peak_trims_neighbours() {
+        let (data, mut peaks) = rider();
+        let baseline = flat(&data);
+        let events = [IntegrationEvent {
+            kind: EventKind::ForcePeak,
+            start: 2.5,
+            end: Some(5.0),
+            value: 0.0,
+        }];
+        apply_events(&mut peaks, &data, &baseline, &events);
+
+        // The rider is replaced and the parent ends where the forced peak starts
+        assert_eq!(peaks.len(), 2);
+        assert_eq!(peaks[0].end.x(), 2.5);
+        assert_close(peaks[0].area, 100.0 + 0.5 * 0.5 * (100.0 + 75.0));
+        assert_eq!((peaks[1].start.x(), peaks[1].end.x()), (2.5, 5.0));
+        assert_eq!(peaks[1].retention_point.x(), 2.5);
+        assert_close(peaks[1].area, 31.25 + 52.5 + 30.0);
+    }
+}
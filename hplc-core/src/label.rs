@@ -0,0 +1,28 @@
+use crate::component::Peak;
+use crate::vector::Vector2;
+
+/// A label chosen by hand for the peak at `retention_time`.
+///
+/// Peaks are matched by the time they contain rather than by index,
+/// so the label survives parameter changes which find the peaks again.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LabelOverride {
+    pub retention_time: f64,
+    /// Name of the reference the peak is, `None` marks the peak as unknown.
+    pub label: Option<String>,
+}
+
+/// Index of the peak whose start and end contain `time`, the closest retention point wins if several do.
+pub(crate) fn peak_at(peaks: &[Peak], time: f64) -> Option<usize> {
+    peaks
+        .iter()
+        .enumerate()
+        .filter(|(_, peak)| peak.start.x() <= time && time <= peak.end.x())
+        .min_by(|(_, lhs), (_, rhs)| {
+            let lhs = (lhs.retention_point.x() - time).abs();
+            let rhs = (rhs.retention_point.x() - time).abs();
+            lhs.total_cmp(&rhs)
+        })
+        .map(|(index, _)| index)
+}
@@ -0,0 +1,76 @@
+//! Analysis engine of HPLC-RS.
+//!
+//! This crate contains everything needed to turn a raw HPLC trace into a table of labelled
+//! components without depending on any GUI toolkit.
+//!
+//! * [`Chromatography`] parses a sample and owns the whole pipeline
+//!   (smoothing, baseline, peak detection and identification) of one of its [`Channel`]s
+//! * [`Smoothing`] selects one of the [`Smoother`] filters applied before analysis
+//! * [`Derivatives`] selects how the derivatives used for peak detection are calculated
+//! * [`BaselineSettings`] selects one of the [`Baseline`] algorithms
+//! * [`IntegrationSettings`] selects how overlapping peaks are separated, globally or per [`IntegrationWindow`],
+//!   and holds the timed [`IntegrationEvent`]s
+//! * [`PeakEdit`] records a peak added, deleted, split, merged or resized by hand
+//! * [`NoiseSettings`] selects how the noise used for S/N is estimated
+//! * [`PeakMetrics`] holds the widths, tailing, plates, resolution and S/N of every peak
+//! * [`PeakModel`] selects the shape fitted to overlapping peaks, results are stored as a [`PeakFit`]
+//! * [`Reference`] parses the lipid reference file used to label peaks,
+//!   every labelled peak carries a [`MatchConfidence`] and labels can be chosen by hand with a [`LabelOverride`]
+//! * [`DataFormat`] is the vendor format a raw data file was recognised as,
+//!   other delimited text is read as described by an [`ImportProfile`]
+//! * [`SampleMetadata`] holds the header of the raw data file, with acquisition dates as a [`Timestamp`]
+//! * [`Spline`] converts retention times to glucose units once a dex ladder is known
+//!
+//! ```no_run
+//! use std::rc::Rc;
+//!
+//! use hplc_core::{Chromatography, ComponentFilter, Reference};
+//!
+//! let references = Reference::parse_file(&"PC12_reference.csv").unwrap();
+//!
+//! let mut sample = Chromatography::from_file(&"chromatogram_timeseries_46739.arw").unwrap();
+//! sample.set_data_range(&(8.5..36.5));
+//! sample.set_lipid_references(Rc::from(references.as_slice()));
+//!
+//! for component in sample.get_components(&ComponentFilter::EXPECTED_ONLY) {
+//!     println!("{:?} {:?}", component.get_expected_rt(), component.get_area());
+//! }
+//! ```
+
+mod assignment;
+pub mod baseline;
+pub mod chromatography;
+pub mod component;
+pub mod deconvolution;
+pub mod derivative;
+pub mod editing;
+pub mod import;
+pub mod integration;
+pub mod label;
+mod linear;
+pub mod metadata;
+pub mod metrics;
+pub mod noise;
+pub mod reference;
+pub mod smoothing;
+pub mod spline;
+pub mod vector;
+
+pub use baseline::{Baseline, BaselineMethod, BaselineSettings};
+pub use chromatography::{Channel, Chromatography, ComponentFilter, SampleType};
+pub use component::{Component, MatchConfidence, Peak};
+pub use deconvolution::{PeakFit, PeakModel};
+pub use derivative::{DerivativeMethod, Derivatives};
+pub use editing::PeakEdit;
+pub use import::{DataFormat, ImportProfile};
+pub use integration::{
+    EventKind, IntegrationEvent, IntegrationMode, IntegrationSettings, IntegrationWindow,
+};
+pub use label::LabelOverride;
+pub use metadata::{SampleMetadata, Timestamp};
+pub use metrics::PeakMetrics;
+pub use noise::{NoiseMethod, NoiseSettings};
+pub use reference::Reference;
+pub use smoothing::{Smoother, Smoothing, SmoothingMethod};
+pub use spline::Spline;
+pub use vector::{Point2D, Vector2};
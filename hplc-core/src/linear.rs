@@ -0,0 +1,144 @@
+/// Solves `matrix * x = values` by gaussian elimination with partial pivoting.
+///
+/// Both arguments are used as scratch space, returns `None` for (nearly) singular matrices.
+pub(crate) fn solve_matrix(matrix: &mut [Vec<f64>], values: &mut [f64]) -> Option<Vec<f64>> {
+    let order = matrix.len();
+
+    for i in 0..order {
+        // Partial pivoting
+        let mut max_row = i;
+        for k in (i + 1)..order {
+            if matrix[k][i].abs() > matrix[max_row][i].abs() {
+                max_row = k;
+            }
+        }
+
+        // Swap rows in matrix and vector
+        matrix.swap(i, max_row);
+        values.swap(i, max_row);
+
+        // Check for singular matrix
+        if matrix[i][i].abs() < 1e-12 {
+            return None; // Singular or nearly singular matrix
+        }
+
+        // Eliminate entries below pivot
+        let (upper, lower) = matrix.split_at_mut(i + 1);
+        let pivot = &upper[i];
+        for (k, row) in (i + 1..order).zip(lower.iter_mut()) {
+            let factor = row[i] / pivot[i];
+            for (entry, above) in row[i..order].iter_mut().zip(&pivot[i..order]) {
+                *entry -= factor * above;
+            }
+            values[k] -= factor * values[i];
+        }
+    }
+
+    // Back substitution
+    let mut x = vec![0.0; order];
+    for i in (0..order).rev() {
+        let mut sum = values[i];
+        for j in (i + 1)..order {
+            sum -= matrix[i][j] * x[j];
+        }
+        x[i] = sum / matrix[i][i];
+    }
+
+    Some(x)
+}
+
+/// Solves a symmetric positive definite pentadiagonal system by banded Cholesky decomposition.
+///
+/// `diagonal` holds the main diagonal, `upper1[i]` and `upper2[i]` the entries at `(i, i + 1)` and `(i, i + 2)`.
+/// Returns `None` if the matrix is not positive definite.
+pub(crate) fn solve_pentadiagonal(
+    diagonal: &[f64],
+    upper1: &[f64],
+    upper2: &[f64],
+    values: &[f64],
+) -> Option<Vec<f64>> {
+    let order = diagonal.len();
+    let at = |band: &[f64], i: usize| band.get(i).copied().unwrap_or(0.0);
+
+    // L[i][i], L[i + 1][i] and L[i + 2][i]
+    let mut l0 = vec![0.0; order];
+    let mut l1 = vec![0.0; order];
+    let mut l2 = vec![0.0; order];
+
+    for i in 0..order {
+        let mut pivot = diagonal[i];
+        if i >= 1 {
+            pivot -= l1[i - 1] * l1[i - 1];
+        }
+        if i >= 2 {
+            pivot -= l2[i - 2] * l2[i - 2];
+        }
+
+        if pivot <= 0.0 {
+            return None;
+        }
+        l0[i] = pivot.sqrt();
+
+        let mut below = at(upper1, i);
+        if i >= 1 {
+            below -= l2[i - 1] * l1[i - 1];
+        }
+        l1[i] = below / l0[i];
+        l2[i] = at(upper2, i) / l0[i];
+    }
+
+    // Forward substitution
+    let mut y = vec![0.0; order];
+    for i in 0..order {
+        let mut sum = values[i];
+        if i >= 1 {
+            sum -= l1[i - 1] * y[i - 1];
+        }
+        if i >= 2 {
+            sum -= l2[i - 2] * y[i - 2];
+        }
+        y[i] = sum / l0[i];
+    }
+
+    // Back substitution
+    let mut x = vec![0.0; order];
+    for i in (0..order).rev() {
+        let mut sum = y[i];
+        if i + 1 < order {
+            sum -= l1[i] * x[i + 1];
+        }
+        if i + 2 < order {
+            sum -= l2[i] * x[i + 2];
+        }
+        x[i] = sum / l0[i];
+    }
+
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_with_pivoting() {
+        // The first pivot is zero, so rows have to be swapped
+        let mut matrix = vec![
+            vec![0.0, 2.0, 1.0],
+            vec![1.0, 1.0, 1.0],
+            vec![2.0, 1.0, 3.0],
+        ];
+        let mut values = vec![7.0, 6.0, 13.0];
+
+        let x = solve_matrix(&mut matrix, &mut values).unwrap();
+        for (x, expected) in x.iter().zip([1.0, 2.0, 3.0]) {
+            assert!((x - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn singular() {
+        let mut matrix = vec![vec![1.0, 2.0], vec![2.0, 4.0]];
+        assert_eq!(solve_matrix(&mut matrix, &mut [1.0, 2.0]), None);
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt::Display;
+
+/// A date and time as written by the instrument, the time zone is kept as text.
+///
+/// Timestamps are ordered by date then time, so samples can be sorted by acquisition.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub zone: Option<String>,
+}
+
+impl Timestamp {
+    /// Parses `M/D/YYYY h:mm:ss AM/PM [zone]` or `DD/MM/YYYY HH:mm:ss [zone]` as written by Empower,
+    /// or `YYYY-MM-DD HH:MM:SS [zone]`.
+    ///
+    /// Empower writes the date in the order of the Windows locale, which also sets the clock: numeric
+    /// dates are month first with a 12 hour clock and day first with a 24 hour clock, unless one of
+    /// the numbers is above 12.
+    ///
+    /// Dates of other vendors are accepted as well: `YYYY/MM/DD`, `DD.MM.YYYY` and month names
+    /// such as `15-May-25` or `15/May/2025`, two digit years being in the 2000s.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut words = value.split_whitespace();
+        let date = words.next()?.trim_end_matches(',');
+        let time = words.next().unwrap_or("0:00:00");
+
+        let parts: Vec<&str> = date.split(['/', '-', '.']).collect();
+        let [first, second, third] = parts[..] else {
+            return None;
+        };
+
+        let mut rest: Vec<&str> = words.collect();
+        let clock = rest.first().map(|word| word.to_ascii_uppercase());
+        let meridiem = match clock.as_deref() {
+            Some("AM") => Some(false),
+            Some("PM") => Some(true),
+            _ => None,
+        };
+        if meridiem.is_some() {
+            rest.remove(0);
+        }
+
+        let (year, month, day) = if first.len() == 4 {
+            (first, month_number(second)?, third)
+        } else if second.starts_with(|c: char| c.is_ascii_alphabetic()) || date.contains('.') {
+            (third, month_number(second)?, first)
+        } else {
+            let (lhs, rhs) = (month_number(first)?, month_number(second)?);
+            if lhs > 12 || (rhs <= 12 && meridiem.is_none()) {
+                (third, rhs, first)
+            } else {
+                (third, lhs, second)
+            }
+        };
+
+        let mut year: u16 = year.parse().ok()?;
+        if year < 100 {
+            year += 2000;
+        }
+        let day = day.parse().ok()?;
+
+        let mut parts = time.split(':');
+        let mut hour: u8 = parts.next()?.parse().ok()?;
+        let minute = parts.next()?.parse().ok()?;
+        let second = parts.next().map_or(Some(0), |second| second.parse().ok())?;
+
+        match meridiem {
+            Some(false) => hour %= 12,
+            Some(true) => hour = hour % 12 + 12,
+            None => {}
+        }
+
+        let valid = (1..=12).contains(&month)
+            && (1..=31).contains(&day)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+        if !valid {
+            return None;
+        }
+
+        Some(Timestamp {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            zone: (!rest.is_empty()).then(|| rest.join(" ")),
+        })
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+
+        match &self.zone {
+            Some(zone) => write!(f, " {}", zone),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Number of a month written as digits or as an English name (`May`, `Sep.`, `September`).
+fn month_number(month: &str) -> Option<u8> {
+    const NAMES: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+
+    if let Ok(number) = month.parse() {
+        return Some(number);
+    }
+
+    let prefix = month.get(..3)?.to_ascii_lowercase();
+    let index = NAMES.iter().position(|name| *name == prefix)?;
+    Some(index as u8 + 1)
+}
+
+/// The header of a raw data file, identifying the instrument run a sample came from.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SampleMetadata {
+    pub sample_name: Option<String>,
+    pub system_name: Option<String>,
+    pub sample_set_name: Option<String>,
+    pub date_acquired: Option<Timestamp>,
+    pub sample_set_start_date: Option<Timestamp>,
+    /// Every header in the order of the file, including the ones above.
+    pub headers: Vec<(String, String)>,
+}
+
+impl SampleMetadata {
+    /// Builds the metadata from the key/value pairs of the header, quotes already removed.
+    ///
+    /// Keys are those of Empower, the sample name may also be given as `Sample Name`.
+    pub fn from_headers(headers: Vec<(String, String)>) -> Self {
+        let find = |key: &str| {
+            headers
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+        };
+
+        SampleMetadata {
+            sample_name: find("SampleName").or_else(|| find("Sample Name")),
+            system_name: find("System Name"),
+            sample_set_name: find("Sample Set Name"),
+            date_acquired: find("Date Acquired").and_then(|value| Timestamp::parse(&value)),
+            sample_set_start_date: find("Sample Set Start Date")
+                .and_then(|value| Timestamp::parse(&value)),
+            headers,
+        }
+    }
+
+    /// Value of the header named `key`, as written in the file.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Value of the header named `key` for display, dates are written as `YYYY-MM-DD HH:MM:SS`.
+    pub fn format(&self, key: &str) -> Option<String> {
+        let timestamp = match key {
+            "Date Acquired" => self.date_acquired.as_ref(),
+            "Sample Set Start Date" => self.sample_set_start_date.as_ref(),
+            _ => None,
+        };
+
+        match timestamp {
+            Some(timestamp) => Some(timestamp.to_string()),
+            None => self.get(key).map(str::to_string),
+        }
+    }
+}
+
+/// Splits a `"Key"<tab>"Value"` header line, `None` for lines of data.
+///
+/// Headers with one value per channel are joined with `, `.
+pub(crate) fn parse_header(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.split_once('\t')?;
+    let key = key.trim();
+    if !key.starts_with('"') {
+        return None;
+    }
+
+    let unquote = |text: &str| text.trim().trim_matches('"').to_string();
+    let values: Vec<String> = value.split('\t').map(unquote).collect();
+    Some((unquote(key), values.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> String {
+        Timestamp::parse(value).map_or(String::from("None"), |timestamp| timestamp.to_string())
+    }
+
+    #[test]
+    fn empower_month_first() {
+        assert_eq!(
+            parse("5/15/2025 10:18:57 PM BST"),
+            "2025-05-15 22:18:57 BST"
+        );
+        assert_eq!(parse("5/6/2025 12:35:41 AM"), "2025-05-06 00:35:41");
+        assert_eq!(parse("5/6/2025 12:35:41 PM"), "2025-05-06 12:35:41");
+    }
+
+    #[test]
+    fn empower_day_first() {
+        assert_eq!(parse("15/05/2025 22:18:57 BST"), "2025-05-15 22:18:57 BST");
+        assert_eq!(parse("06/05/2025 08:47:36"), "2025-05-06 08:47:36");
+        assert_eq!(parse("15/05/2025 10:18:57 PM"), "2025-05-15 22:18:57");
+    }
+
+    #[test]
+    fn other_vendors() {
+        assert_eq!(
+            parse("2025-05-15 22:18:57 +0100"),
+            "2025-05-15 22:18:57 +0100"
+        );
+        assert_eq!(parse("2025/05/15 22:18:57"), "2025-05-15 22:18:57");
+        assert_eq!(parse("15.05.2025 22:18"), "2025-05-15 22:18:00");
+        assert_eq!(parse("15-May-25 22:18:57"), "2025-05-15 22:18:57");
+        assert_eq!(parse("15/September/2025, 22:18:57"), "2025-09-15 22:18:57");
+        assert_eq!(parse("2025-05-15"), "2025-05-15 00:00:00");
+    }
+
+    #[test]
+    fn invalid() {
+        for value in [
+            "",
+            "yesterday",
+            "5/15 10:00",
+            "13/13/2025 10:00",
+            "5/15/2025 25:00",
+            "2025-05-15 x",
+        ] {
+            assert_eq!(parse(value), "None", "{}", value);
+        }
+    }
+
+    #[test]
+    fn sorts_by_acquisition() {
+        let mut dates: Vec<Timestamp> = [
+            "12/05/2025 09:00:00",
+            "06/12/2025 08:00:00",
+            "06/05/2025 23:00:00",
+        ]
+        .iter()
+        .filter_map(|value| Timestamp::parse(value))
+        .collect();
+        dates.sort();
+
+        let sorted: Vec<String> = dates.iter().map(Timestamp::to_string).collect();
+        assert_eq!(
+            sorted,
+            [
+                "2025-05-06 23:00:00",
+                "2025-05-12 09:00:00",
+                "2025-12-06 08:00:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn headers() {
+        let metadata = SampleMetadata::from_headers(vec![
+            (String::from("Sample Name"), String::from("PC12-9")),
+            (
+                String::from("Date Acquired"),
+                String::from("16/05/2025 13:32:36 BST"),
+            ),
+        ]);
+
+        assert_eq!(metadata.sample_name.as_deref(), Some("PC12-9"));
+        assert_eq!(
+            metadata.format("Date Acquired").as_deref(),
+            Some("2025-05-16 13:32:36 BST")
+        );
+        assert_eq!(
+            metadata.get("Date Acquired"),
+            Some("16/05/2025 13:32:36 BST")
+        );
+        assert_eq!(
+            parse_header("\"Channel\"\t\"FLR\"\t\"UV\""),
+            Some((String::from("Channel"), String::from("FLR, UV")))
+        );
+        assert_eq!(parse_header("0.0\t1.0"), None);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::component::Peak;
+use crate::vector::{Point2D, Vector2};
+
+/// Column performance figures of a peak, `None` where the peak does not reach the required height.
+///
+/// Widths are measured on the peak above its own baseline, or on the fitted peak if it was deconvolved.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PeakMetrics {
+    /// Width at half height.
+    pub width_half_height: Option<f64>,
+    /// Distance between the points where the tangents at the inflections cross the baseline.
+    pub width_base: Option<f64>,
+    /// USP tailing factor, width at 5% height over twice the front half width at 5% height.
+    pub tailing: Option<f64>,
+    /// Back half width over front half width at 10% height.
+    pub asymmetry: Option<f64>,
+    /// Theoretical plates from the width at half height, `5.54 * (rt / w0.5)^2`.
+    pub plates: Option<f64>,
+    /// Resolution to the previous peak from the widths at half height, `1.18 * (rt2 - rt1) / (w1 + w2)`.
+    pub resolution: Option<f64>,
+    /// Height over the standard deviation of the noise.
+    pub signal_to_noise: Option<f64>,
+}
+
+/// Calculates the metrics of every peak, `noise` is the standard deviation of the detector noise.
+pub(crate) fn calculate_metrics(
+    peaks: &mut [Peak],
+    data: &[Point2D],
+    baseline: &[Point2D],
+    noise: f64,
+) {
+    let mut previous: Option<(f64, f64)> = None;
+
+    for peak in peaks.iter_mut() {
+        let profile = profile(peak, data, baseline);
+        let Some(apex) =
+            (0..profile.len()).max_by(|lhs, rhs| profile[*lhs].y().total_cmp(&profile[*rhs].y()))
+        else {
+            continue;
+        };
+
+        let retention_time = profile[apex].x();
+        let height = profile[apex].y();
+
+        let mut metrics = PeakMetrics::default();
+        if height > 0.0 {
+            let half = widths(&profile, apex, 0.5);
+            let tenth = widths(&profile, apex, 0.1);
+            let twentieth = widths(&profile, apex, 0.05);
+
+            metrics.width_half_height = half.map(|(front, back)| front + back);
+            metrics.width_base = base_width(&profile, apex);
+            metrics.tailing = twentieth.map(|(front, back)| (front + back) / (2.0 * front));
+            metrics.asymmetry = tenth.map(|(front, back)| back / front);
+            metrics.plates = metrics
+                .width_half_height
+                .map(|width| 5.54 * (retention_time / width).powi(2));
+
+            if noise > 0.0 {
+                metrics.signal_to_noise = Some(height / noise);
+            }
+        }
+
+        if let (Some((previous_time, previous_width)), Some(width)) =
+            (previous, metrics.width_half_height)
+        {
+            metrics.resolution =
+                Some(1.18 * (retention_time - previous_time) / (previous_width + width));
+        }
+
+        previous = metrics
+            .width_half_height
+            .map(|width| (retention_time, width));
+        peak.metrics = metrics;
+    }
+}
+
+/// Height of the peak above its baseline at every point.
+fn profile(peak: &Peak, data: &[Point2D], baseline: &[Point2D]) -> Vec<Point2D> {
+    let index = |x: f64| {
+        data.partition_point(|point| point.x() < x)
+            .min(data.len().saturating_sub(1))
+    };
+
+    match &peak.fit {
+        Some(fit) => {
+            let start = index(fit.curve.first().map_or(0.0, |point| point.x()));
+            fit.curve
+                .iter()
+                .zip(&baseline[start..])
+                .map(|(point, base)| Point2D::new(point.x(), point.y() - base.y()))
+                .collect()
+        }
+        None => {
+            let start = index(peak.start.x());
+            data[start..]
+                .iter()
+                .zip(&peak.baseline)
+                .map(|(point, base)| Point2D::new(point.x(), point.y() - base.y()))
+                .collect()
+        }
+    }
+}
+
+/// Front and back half widths where the peak falls to `fraction` of its height.
+fn widths(profile: &[Point2D], apex: usize, fraction: f64) -> Option<(f64, f64)> {
+    let level = profile[apex].y() * fraction;
+    let crossing = |lhs: &Point2D, rhs: &Point2D| {
+        lhs.x() + (level - lhs.y()) * (rhs.x() - lhs.x()) / (rhs.y() - lhs.y())
+    };
+
+    let front = (1..=apex)
+        .rev()
+        .find(|i| profile[i - 1].y() <= level)
+        .map(|i| crossing(&profile[i - 1], &profile[i]))?;
+    let back = (apex + 1..profile.len())
+        .find(|i| profile[*i].y() <= level)
+        .map(|i| crossing(&profile[i - 1], &profile[i]))?;
+
+    let time = profile[apex].x();
+    Some((time - front, back - time))
+}
+
+/// Width between the baseline intercepts of the tangents at the steepest points either side of the apex.
+fn base_width(profile: &[Point2D], apex: usize) -> Option<f64> {
+    let slope = |i: usize| profile[i].gradient(&profile[i + 1]);
+    let intercept = |i: usize| {
+        let midpoint = Point2D::new(
+            0.5 * (profile[i].x() + profile[i + 1].x()),
+            0.5 * (profile[i].y() + profile[i + 1].y()),
+        );
+        midpoint.x() - midpoint.y() / slope(i)
+    };
+
+    let front = (0..apex).max_by(|lhs, rhs| slope(*lhs).total_cmp(&slope(*rhs)))?;
+    let back = (apex..profile.len() - 1).min_by(|lhs, rhs| slope(*lhs).total_cmp(&slope(*rhs)))?;
+
+    if slope(front) <= 0.0 || slope(back) >= 0.0 {
+        return None;
+    }
+
+    Some(intercept(back) - intercept(front))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::peak_between;
+
+    // Width at half height of a Gaussian in standard deviations, 2 sqrt(2 ln 2)
+    const HALF_WIDTH: f64 = 2.354820045;
+
+    fn gaussian(time: f64, centre: f64, sigma: f64) -> f64 {
+        100.0 * (-0.5 * ((time - centre) / sigma).powi(2)).exp()
+    }
+
+    /// Data sampled every millisecond from 3 to 9 minutes with a flat baseline of 10.
+    fn measure<F: Fn(f64) -> f64>(signal: F, bounds: &[(usize, usize)], noise: f64) -> Vec<Peak> {
+        let data: Vec<Point2D> = (3000..=9000)
+            .map(|i| {
+                let time = i as f64 / 1000.0;
+                Point2D::new(time, 10.0 + signal(time))
+            })
+            .collect();
+        let baseline: Vec<Point2D> = data
+            .iter()
+            .map(|point| Point2D::new(point.x(), 10.0))
+            .collect();
+
+        let mut peaks: Vec<Peak> = bounds
+            .iter()
+            .map(|(start, end)| {
+                let mut peak = peak_between(&data, &baseline, *start, *end);
+                peak.baseline = baseline[*start..=*end].to_vec();
+                peak
+            })
+            .collect();
+
+        calculate_metrics(&mut peaks, &data, &baseline, noise);
+        peaks
+    }
+
+    fn assert_close(value: Option<f64>, expected: f64, tolerance: f64) {
+        let value = value.expect("metric should be measured");
+        assert!(
+            (value - expected).abs() < tolerance,
+            "{} != {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn gaussian_peak() {
+        let peaks = measure(|time| gaussian(time, 5.0, 0.1), &[(0, 4000)], 0.5);
+        let metrics = peaks[0].metrics;
+
+        assert_close(metrics.width_half_height, HALF_WIDTH * 0.1, 1e-5);
+        assert_close(metrics.width_base, 4.0 * 0.1, 1e-3);
+        assert_close(metrics.tailing, 1.0, 1e-4);
+        assert_close(metrics.asymmetry, 1.0, 1e-4);
+        assert_close(
+            metrics.plates,
+            5.54 * (5.0 / (HALF_WIDTH * 0.1)).powi(2),
+            0.5,
+        );
+        assert_close(metrics.signal_to_noise, 200.0, 1e-9);
+        assert_eq!(metrics.resolution, None);
+    }
+
+    #[test]
+    fn resolution() {
+        let signal = |time| gaussian(time, 5.0, 0.1) + gaussian(time, 6.0, 0.1);
+        let peaks = measure(signal, &[(0, 2500), (2500, 6000)], 0.0);
+
+        // Baseline resolution of two Gaussians is their distance over 4 sigma
+        assert_close(
+            peaks[1].metrics.resolution,
+            1.18 / (2.0 * HALF_WIDTH * 0.1),
+            1e-4,
+        );
+        assert_close(peaks[1].metrics.resolution, 1.0 / (4.0 * 0.1), 0.01);
+        assert_eq!(peaks[0].metrics.resolution, None);
+        assert_eq!(peaks[0].metrics.signal_to_noise, None);
+    }
+
+    #[test]
+    fn tailing_peak() {
+        // Back half twice as wide as the front half
+        let signal = |time: f64| {
+            let sigma = if time < 5.0 { 0.1 } else { 0.2 };
+            gaussian(time, 5.0, sigma)
+        };
+        let metrics = measure(signal, &[(0, 6000)], 0.0)[0].metrics;
+
+        assert_close(metrics.tailing, 1.5, 1e-3);
+        assert_close(metrics.asymmetry, 2.0, 1e-3);
+        assert_close(metrics.width_half_height, 1.5 * HALF_WIDTH * 0.1, 1e-5);
+    }
+
+    #[test]
+    fn peak_cut_short() {
+        // The peak ends before falling to 5% of its height
+        let metrics = measure(|time| gaussian(time, 5.0, 0.1), &[(0, 2200)], 0.0)[0].metrics;
+
+        assert!(metrics.width_half_height.is_some());
+        assert_eq!(metrics.tailing, None);
+        assert_eq!(metrics.asymmetry, None);
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt::{self, Display};
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::vector::{Point2D, Vector2};
+
+/// How the standard deviation of the detector noise is estimated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoiseMethod {
+    /// Median absolute deviation of the second differences of the whole data range.
+    #[default]
+    Mad,
+    /// Standard deviation around a straight line through a region without peaks.
+    QuietRegion,
+}
+
+impl NoiseMethod {
+    pub const ALL: [NoiseMethod; 2] = [NoiseMethod::Mad, NoiseMethod::QuietRegion];
+}
+
+impl Display for NoiseMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            NoiseMethod::Mad => "MAD",
+            NoiseMethod::QuietRegion => "Quiet Region",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for NoiseMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "mad" => Ok(NoiseMethod::Mad),
+            "quiet-region" | "quiet" => Ok(NoiseMethod::QuietRegion),
+            other => Err(format!("Unknown noise method {}", other)),
+        }
+    }
+}
+
+/// Noise configuration of a sample, `start` and `end` (in minutes) bound the quiet region.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NoiseSettings {
+    pub method: NoiseMethod,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Default for NoiseSettings {
+    fn default() -> Self {
+        Self {
+            method: NoiseMethod::Mad,
+            start: 0.0,
+            end: 1.0,
+        }
+    }
+}
+
+impl NoiseSettings {
+    /// Standard deviation of the noise of unsmoothed data.
+    ///
+    /// The MAD only considers points within `range`, the quiet region may lie outside of it.
+    /// A quiet region with fewer than 3 points falls back to the MAD.
+    pub fn estimate(&self, data: &[Point2D], range: Option<&Range<f64>>) -> f64 {
+        if self.method == NoiseMethod::QuietRegion {
+            let region: Vec<Point2D> = data
+                .iter()
+                .filter(|point| (self.start..=self.end).contains(&point.x()))
+                .copied()
+                .collect();
+
+            if region.len() >= 3 {
+                return detrended_deviation(&region);
+            }
+        }
+
+        match range {
+            Some(range) => {
+                let within: Vec<Point2D> = data
+                    .iter()
+                    .filter(|point| range.contains(&point.x()))
+                    .copied()
+                    .collect();
+                mad_noise(&within)
+            }
+            None => mad_noise(data),
+        }
+    }
+}
+
+/// Standard deviation of the residuals of a least squares line through the data.
+fn detrended_deviation(data: &[Point2D]) -> f64 {
+    let count = data.len() as f64;
+    let mean_x = data.iter().map(|point| point.x()).sum::<f64>() / count;
+    let mean_y = data.iter().map(|point| point.y()).sum::<f64>() / count;
+
+    let covariance: f64 = data
+        .iter()
+        .map(|point| (point.x() - mean_x) * (point.y() - mean_y))
+        .sum();
+    let variance: f64 = data.iter().map(|point| (point.x() - mean_x).powi(2)).sum();
+    let gradient = if variance > 0.0 {
+        covariance / variance
+    } else {
+        0.0
+    };
+
+    let squares: f64 = data
+        .iter()
+        .map(|point| (point.y() - mean_y - gradient * (point.x() - mean_x)).powi(2))
+        .sum();
+
+    // Two degrees of freedom are used by the line
+    (squares / (count - 2.0)).sqrt()
+}
+
+/// Standard deviation of the detector noise, estimated from the median absolute deviation
+/// of the second differences of the data.
+///
+/// Second differences remove the baseline drift and most of the peaks, which vary slowly compared to the noise,
+/// and the median ignores what is left of the peaks.
+/// Returns 0 for fewer than 3 points.
+pub fn mad_noise(data: &[Point2D]) -> f64 {
+    if data.len() < 3 {
+        return 0.0;
+    }
+
+    let mut differences: Vec<f64> = data
+        .windows(3)
+        .map(|window| window[1].y() - 0.5 * (window[0].y() + window[2].y()))
+        .collect();
+
+    let centre = median(&mut differences);
+    let mut deviations: Vec<f64> = differences
+        .iter()
+        .map(|difference| (difference - centre).abs())
+        .collect();
+
+    // 1.4826 scales the MAD of a normal distribution to its standard deviation,
+    // a second difference of white noise has 1.5 times its variance
+    1.4826 * median(&mut deviations) / 1.5f64.sqrt()
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        0.5 * (values[middle - 1] + values[middle])
+    } else {
+        values[middle]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gaussian white noise from a xorshift generator and the Box-Muller transform.
+    fn white_noise(count: usize, sigma: f64) -> Vec<f64> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut uniform = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            ((state >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+        };
+
+        (0..count)
+            .map(|_| {
+                let (u, v) = (uniform(), uniform());
+                sigma * (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+            })
+            .collect()
+    }
+
+    /// Noise of standard deviation 0.5 on a drifting baseline with a broad peak at 30 minutes,
+    /// sampled every 0.01 minutes for 60 minutes.
+    fn signal() -> Vec<Point2D> {
+        white_noise(6000, 0.5)
+            .into_iter()
+            .enumerate()
+            .map(|(i, noise)| {
+                let time = i as f64 / 100.0;
+                let peak = 1000.0 * (-0.5 * ((time - 30.0) / 0.5).powi(2)).exp();
+                Point2D::new(time, 5.0 + 0.2 * time + peak + noise)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mad_recovers_sigma() {
+        let noise = mad_noise(&signal());
+        assert!((noise - 0.5).abs() < 0.025, "{}", noise);
+    }
+
+    #[test]
+    fn mad_within_range() {
+        let settings = NoiseSettings::default();
+        let data: Vec<Point2D> = signal()
+            .into_iter()
+            .map(|point| {
+                // Ten times the noise after 40 minutes
+                let scale = if point.x() >= 40.0 { 10.0 } else { 1.0 };
+                Point2D::new(point.x(), point.y() * scale)
+            })
+            .collect();
+
+        let noise = settings.estimate(&data, Some(&(0.0..40.0)));
+        assert!((noise - 0.5).abs() < 0.03, "{}", noise);
+        assert!(settings.estimate(&data, None) > noise);
+    }
+
+    #[test]
+    fn quiet_region() {
+        let settings = NoiseSettings {
+            method: NoiseMethod::QuietRegion,
+            start: 5.0,
+            end: 25.0,
+        };
+
+        // The drift is removed with the line through the region
+        let noise = settings.estimate(&signal(), None);
+        assert!((noise - 0.5).abs() < 0.025, "{}", noise);
+    }
+
+    #[test]
+    fn short_quiet_region_falls_back() {
+        let settings = NoiseSettings {
+            method: NoiseMethod::QuietRegion,
+            start: 5.0,
+            end: 5.015,
+        };
+        let data = signal();
+
+        assert_eq!(settings.estimate(&data, None), mad_noise(&data));
+    }
+
+    #[test]
+    fn too_few_points() {
+        assert_eq!(mad_noise(&[]), 0.0);
+        assert_eq!(
+            mad_noise(&[Point2D::new(0.0, 1.0), Point2D::new(1.0, 2.0)]),
+            0.0
+        );
+        assert_eq!(median(&mut [3.0, 1.0, 2.0, 4.0]), 2.5);
+    }
+}
@@ -0,0 +1,227 @@
+use std::fs;
+use std::io::{self, BufRead};
+use std::path;
+
+use crate::spline::Spline;
+
+#[derive(Clone, Debug, PartialEq)]
+enum ExpectedLocation {
+    RetentionTime(f64),
+    GlucoseUnit(f64),
+    Complete(f64, f64),
+}
+
+/// An entry of the lipid reference file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reference {
+    pub name: Option<String>,
+    expected_location: ExpectedLocation,
+}
+
+impl Reference {
+    /// Parses a csv/tsv reference file with a header containing `Name`, `RT` and/or `GU`.
+    ///
+    /// Entries without either RT or GU are skipped.
+    pub fn parse_file<P: AsRef<path::Path>>(path: &P) -> Result<Vec<Self>, String> {
+        let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let mut lines = content.lines();
+        let header = lines.next().ok_or("Empty reference file")?;
+
+        let entries = if header.contains("\t") {
+            header.split("\t")
+        } else {
+            header.split(",")
+        };
+
+        type ReferenceFn = &'static dyn Fn(&mut ReferenceBuilder, &str);
+
+        let funcs: Vec<ReferenceFn> = entries
+            .map(|entry| {
+                if entry == "Name" {
+                    &ReferenceBuilder::parse_name
+                } else if entry == "RT" {
+                    &ReferenceBuilder::parse_retention_time
+                } else if entry == "GU" {
+                    &ReferenceBuilder::parse_glucose_units
+                } else {
+                    let func: ReferenceFn = &ReferenceBuilder::parse_none;
+                    func
+                }
+            })
+            .collect();
+
+        let references = lines
+            .filter_map(|line| {
+                let mut reference_builder = ReferenceBuilder::default();
+
+                let entries = if line.contains("\t") {
+                    line.split("\t")
+                } else {
+                    line.split(",")
+                };
+
+                for (entry, func) in entries.zip(&funcs) {
+                    func(&mut reference_builder, entry);
+                }
+
+                match reference_builder.location {
+                    None => {
+                        match &reference_builder.name {
+                            Some(name) => eprintln!(
+                                "Lipid {} needs at least one of retention time or GU",
+                                name
+                            ),
+                            None => eprintln!(
+                                "Unnamed lipid needs at least one of retention time or GU"
+                            ),
+                        }
+
+                        None
+                    }
+                    Some(location) => {
+                        let reference = Reference {
+                            name: reference_builder.name,
+                            expected_location: location,
+                        };
+
+                        Some(reference)
+                    }
+                }
+            })
+            .collect();
+
+        Ok(references)
+    }
+
+    /// Whether the file has the header of a reference file, a `Name` column and an `RT` or `GU` column.
+    ///
+    /// Only the first line is read, so data files can be told apart from references cheaply.
+    pub fn is_reference_file<P: AsRef<path::Path>>(path: &P) -> bool {
+        let Ok(file) = fs::File::open(path) else {
+            return false;
+        };
+
+        let mut header = String::new();
+        if io::BufReader::new(file).read_line(&mut header).is_err() {
+            return false;
+        }
+
+        let header = header.trim_end_matches(['\r', '\n']);
+        let entries: Vec<&str> = if header.contains("\t") {
+            header.split("\t").collect()
+        } else {
+            header.split(",").collect()
+        };
+
+        entries.contains(&"Name") && (entries.contains(&"RT") || entries.contains(&"GU"))
+    }
+
+    /// Expected location of the lipid, in GU if a spline is given otherwise in RT.
+    pub fn get_expected_location(&self, spline: Option<&Spline>) -> Option<f64> {
+        match (&self.expected_location, spline) {
+            (ExpectedLocation::RetentionTime(rt), None) => Some(*rt),
+            (ExpectedLocation::RetentionTime(rt), Some(spline)) => spline.evaluate(*rt),
+            (ExpectedLocation::GlucoseUnit(_), None) => None,
+            (ExpectedLocation::GlucoseUnit(gu), Some(_)) => Some(*gu),
+            (ExpectedLocation::Complete(rt, _), None) => Some(*rt),
+            (ExpectedLocation::Complete(_, gu), Some(_)) => Some(*gu),
+        }
+    }
+
+    /// Expected retention time of the lipid.
+    pub fn get_expected_rt(&self) -> Option<f64> {
+        match &self.expected_location {
+            ExpectedLocation::RetentionTime(rt) => Some(*rt),
+            ExpectedLocation::GlucoseUnit(_) => None,
+            ExpectedLocation::Complete(rt, _) => Some(*rt),
+        }
+    }
+
+    /// Expected GU of the lipid, calculated from RT if a spline is given.
+    pub fn get_expected_gu(&self, spline: Option<&Spline>) -> Option<f64> {
+        match (&self.expected_location, spline) {
+            (ExpectedLocation::RetentionTime(_), None) => None,
+            (ExpectedLocation::RetentionTime(rt), Some(spline)) => spline.evaluate(*rt),
+            (ExpectedLocation::GlucoseUnit(gu), _) => Some(*gu),
+            (ExpectedLocation::Complete(_, gu), _) => Some(*gu),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ReferenceBuilder {
+    pub name: Option<String>,
+    pub location: Option<ExpectedLocation>,
+}
+
+impl ReferenceBuilder {
+    fn parse_none(&mut self, _: &str) {}
+
+    fn parse_name(&mut self, name: &str) {
+        self.name = Some(name.to_string());
+    }
+
+    fn parse_retention_time(&mut self, rt: &str) {
+        if let Ok(rt) = rt.parse::<f64>() {
+            match self.location {
+                None => {
+                    self.location = Some(ExpectedLocation::RetentionTime(rt));
+                }
+                Some(ExpectedLocation::GlucoseUnit(gu)) => {
+                    self.location = Some(ExpectedLocation::Complete(rt, gu));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn parse_glucose_units(&mut self, gu: &str) {
+        if let Ok(gu) = gu.parse::<f64>() {
+            match self.location {
+                None => {
+                    self.location = Some(ExpectedLocation::GlucoseUnit(gu));
+                }
+                Some(ExpectedLocation::RetentionTime(rt)) => {
+                    self.location = Some(ExpectedLocation::Complete(rt, gu));
+                }
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> path::PathBuf {
+        path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../test/samples/PC12/PC12_reference.csv")
+    }
+
+    #[test]
+    fn reads_fixture() {
+        let references = Reference::parse_file(&fixture()).unwrap();
+
+        assert_eq!(references.len(), 30);
+        assert_eq!(references[29].name.as_deref(), Some("GQ1b"));
+        assert_eq!(references[0].name.as_deref(), Some("Lac"));
+        assert_eq!(references[0].get_expected_rt(), Some(9.2));
+        assert_eq!(references[0].get_expected_gu(None), Some(2.02));
+        assert!(Reference::is_reference_file(&fixture()));
+    }
+
+    #[test]
+    fn unreadable_files() {
+        let missing = fixture().with_file_name("missing.csv");
+        assert!(Reference::parse_file(&missing).is_err());
+        assert!(!Reference::is_reference_file(&missing));
+
+        let empty = std::env::temp_dir().join("hplc_core_empty_reference.csv");
+        fs::write(&empty, "").unwrap();
+        assert_eq!(
+            Reference::parse_file(&empty),
+            Err(String::from("Empty reference file"))
+        );
+        fs::remove_file(&empty).unwrap();
+    }
+}
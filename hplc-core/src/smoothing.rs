@@ -0,0 +1,367 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::linear::solve_matrix;
+use crate::vector::{Point2D, Vector2};
+
+/// A noise reduction filter applied to the raw data before any analysis.
+///
+/// Windowed filters only produce points which have a full window around them,
+/// so `radius` points are dropped from either end of the data.
+pub trait Smoother {
+    fn smooth(&self, data: &[Point2D]) -> Vec<Point2D>;
+}
+
+/// Leaves the data untouched.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoSmoothing;
+
+/// Mean of the `radius` points on either side (boxcar).
+#[derive(Clone, Copy, Debug)]
+pub struct MeanFilter {
+    pub radius: usize,
+}
+
+/// Gaussian weighted mean with a standard deviation of half the radius.
+#[derive(Clone, Copy, Debug)]
+pub struct GaussianFilter {
+    pub radius: usize,
+}
+
+/// Median of the `radius` points on either side, removes spikes without broadening peaks.
+#[derive(Clone, Copy, Debug)]
+pub struct MedianFilter {
+    pub radius: usize,
+}
+
+/// Least squares fit of a polynomial of `order` over the window, evaluated at its centre.
+///
+/// Preserves the height and width of peaks far better than a mean of the same window.
+#[derive(Clone, Copy, Debug)]
+pub struct SavitzkyGolay {
+    pub radius: usize,
+    pub order: usize,
+}
+
+impl Smoother for NoSmoothing {
+    fn smooth(&self, data: &[Point2D]) -> Vec<Point2D> {
+        data.to_vec()
+    }
+}
+
+impl Smoother for MeanFilter {
+    fn smooth(&self, data: &[Point2D]) -> Vec<Point2D> {
+        let radius = self.radius;
+        let mut smoothed = Vec::with_capacity(data.len());
+
+        for i in radius..data.len().saturating_sub(radius) {
+            let mut total = 0.0;
+            for point in &data[i - radius..=i + radius] {
+                total += point.y();
+            }
+
+            let point = Point2D::new(data[i].x(), total / (2 * radius + 1) as f64);
+            smoothed.push(point);
+        }
+
+        smoothed
+    }
+}
+
+impl Smoother for GaussianFilter {
+    fn smooth(&self, data: &[Point2D]) -> Vec<Point2D> {
+        if self.radius == 0 {
+            return data.to_vec();
+        }
+
+        convolve(data, &self.weights())
+    }
+}
+
+impl GaussianFilter {
+    /// Normalised weights of the window, they sum to 1 so the filter keeps the level of the data.
+    fn weights(&self) -> Vec<f64> {
+        let sigma = self.radius as f64 / 2.0;
+        let mut weights: Vec<f64> = (0..=2 * self.radius)
+            .map(|i| {
+                let offset = i as f64 - self.radius as f64;
+                (-0.5 * (offset / sigma).powi(2)).exp()
+            })
+            .collect();
+
+        let total: f64 = weights.iter().sum();
+        for weight in weights.iter_mut() {
+            *weight /= total;
+        }
+
+        weights
+    }
+}
+
+impl Smoother for MedianFilter {
+    fn smooth(&self, data: &[Point2D]) -> Vec<Point2D> {
+        let radius = self.radius;
+        let mut smoothed = Vec::with_capacity(data.len());
+        let mut window = Vec::with_capacity(2 * radius + 1);
+
+        for i in radius..data.len().saturating_sub(radius) {
+            window.clear();
+            window.extend(data[i - radius..=i + radius].iter().map(|point| point.y()));
+            window.sort_by(f64::total_cmp);
+
+            smoothed.push(Point2D::new(data[i].x(), window[radius]));
+        }
+
+        smoothed
+    }
+}
+
+impl SavitzkyGolay {
+    /// Convolution weights which evaluate the `derivative` of the fitted polynomial
+    /// at the centre of the window, assuming a spacing of 1 between points.
+    ///
+    /// Returns `None` if the window is too small for the order or the derivative is above the order.
+    pub fn weights(&self, derivative: usize) -> Option<Vec<f64>> {
+        self.weights_at(derivative, self.radius as f64)
+    }
+
+    /// Same as [`SavitzkyGolay::weights`] but evaluated at `position` points from the start of the window,
+    /// used for the ends of the data where there is no full window around the point.
+    pub fn weights_at(&self, derivative: usize, position: f64) -> Option<Vec<f64>> {
+        let size = 2 * self.radius + 1;
+        if self.order >= size || derivative > self.order {
+            return None;
+        }
+
+        let offsets: Vec<f64> = (0..size).map(|i| i as f64 - self.radius as f64).collect();
+        let position = position - self.radius as f64;
+
+        // Normal equations of the least squares fit (A^T A) c = A^T y,
+        // the weights are the derivative of the fit at position expressed in terms of y.
+        let terms = self.order + 1;
+        let mut matrix = vec![vec![0.0; terms]; terms];
+        for (row, equation) in matrix.iter_mut().enumerate() {
+            for (column, value) in equation.iter_mut().enumerate() {
+                *value = offsets.iter().map(|x| x.powi((row + column) as i32)).sum();
+            }
+        }
+
+        let mut evaluation = vec![0.0; terms];
+        for (power, value) in evaluation.iter_mut().enumerate().skip(derivative) {
+            let falling_factorial: f64 =
+                (power - derivative + 1..=power).map(|n| n as f64).product();
+            *value = falling_factorial * position.powi((power - derivative) as i32);
+        }
+
+        let solution = solve_matrix(&mut matrix, &mut evaluation)?;
+
+        let weights = offsets
+            .iter()
+            .map(|x| {
+                solution
+                    .iter()
+                    .enumerate()
+                    .map(|(power, coefficient)| coefficient * x.powi(power as i32))
+                    .sum()
+            })
+            .collect();
+
+        Some(weights)
+    }
+}
+
+impl Smoother for SavitzkyGolay {
+    fn smooth(&self, data: &[Point2D]) -> Vec<Point2D> {
+        match self.weights(0) {
+            Some(weights) => convolve(data, &weights),
+            None => data.to_vec(),
+        }
+    }
+}
+
+/// Weighted sum of the window around every point, the window is as long as the weights.
+fn convolve(data: &[Point2D], weights: &[f64]) -> Vec<Point2D> {
+    let radius = weights.len() / 2;
+    let mut smoothed = Vec::with_capacity(data.len());
+
+    for i in radius..data.len().saturating_sub(radius) {
+        let mut total = 0.0;
+        for (offset, weight) in weights.iter().enumerate() {
+            total += data[i + offset - radius].y() * weight;
+        }
+
+        smoothed.push(Point2D::new(data[i].x(), total));
+    }
+
+    smoothed
+}
+
+/// The filters selectable by the user.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SmoothingMethod {
+    None,
+    #[default]
+    Mean,
+    SavitzkyGolay,
+    Gaussian,
+    Median,
+}
+
+impl SmoothingMethod {
+    pub const ALL: [SmoothingMethod; 5] = [
+        SmoothingMethod::None,
+        SmoothingMethod::Mean,
+        SmoothingMethod::SavitzkyGolay,
+        SmoothingMethod::Gaussian,
+        SmoothingMethod::Median,
+    ];
+}
+
+impl Display for SmoothingMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SmoothingMethod::None => "None",
+            SmoothingMethod::Mean => "Mean",
+            SmoothingMethod::SavitzkyGolay => "Savitzky-Golay",
+            SmoothingMethod::Gaussian => "Gaussian",
+            SmoothingMethod::Median => "Median",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for SmoothingMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Ok(SmoothingMethod::None),
+            "mean" => Ok(SmoothingMethod::Mean),
+            "savitzky-golay" | "sg" => Ok(SmoothingMethod::SavitzkyGolay),
+            "gaussian" => Ok(SmoothingMethod::Gaussian),
+            "median" => Ok(SmoothingMethod::Median),
+            other => Err(format!("Unknown smoothing method {}", other)),
+        }
+    }
+}
+
+/// Smoothing configuration of a sample, a method and the number of points on either side.
+///
+/// Savitzky–Golay smoothing always fits a quadratic,
+/// the order is only configurable for derivatives where it has to exceed the derivative taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Smoothing {
+    pub method: SmoothingMethod,
+    pub radius: usize,
+}
+
+/// Polynomial order of Savitzky–Golay smoothing, valid for any radius above 0.
+const SMOOTHING_ORDER: usize = 2;
+
+impl Default for Smoothing {
+    fn default() -> Self {
+        Self {
+            method: SmoothingMethod::Mean,
+            radius: 5,
+        }
+    }
+}
+
+impl Smoother for Smoothing {
+    fn smooth(&self, data: &[Point2D]) -> Vec<Point2D> {
+        let radius = self.radius;
+        match self.method {
+            SmoothingMethod::None => NoSmoothing.smooth(data),
+            SmoothingMethod::Mean => MeanFilter { radius }.smooth(data),
+            SmoothingMethod::SavitzkyGolay => SavitzkyGolay {
+                radius,
+                order: SMOOTHING_ORDER,
+            }
+            .smooth(data),
+            SmoothingMethod::Gaussian => GaussianFilter { radius }.smooth(data),
+            SmoothingMethod::Median => MedianFilter { radius }.smooth(data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(f: impl Fn(f64) -> f64) -> Vec<Point2D> {
+        (0..=100)
+            .map(|i| {
+                let x = i as f64 / 10.0;
+                Point2D::new(x, f(x))
+            })
+            .collect()
+    }
+
+    fn filters(radius: usize) -> Vec<Smoothing> {
+        SmoothingMethod::ALL
+            .into_iter()
+            .map(|method| Smoothing { method, radius })
+            .collect()
+    }
+
+    #[test]
+    fn constant_unchanged() {
+        let data = series(|_| 4.0);
+        for filter in filters(3) {
+            for point in filter.smooth(&data) {
+                assert!((point.y() - 4.0).abs() < 1e-9, "{}", filter.method);
+            }
+        }
+    }
+
+    #[test]
+    fn radius_dropped_from_ends() {
+        let data = series(|x| x.sin());
+        for filter in filters(3) {
+            let smoothed = filter.smooth(&data);
+            if filter.method == SmoothingMethod::None {
+                assert_eq!(smoothed, data);
+                continue;
+            }
+
+            assert_eq!(smoothed.len(), data.len() - 6, "{}", filter.method);
+            assert_eq!(smoothed[0].x(), data[3].x());
+            assert_eq!(smoothed[smoothed.len() - 1].x(), data[data.len() - 4].x());
+        }
+    }
+
+    #[test]
+    fn median_removes_spike() {
+        let mut data = series(|_| 1.0);
+        data[50] = Point2D::new(data[50].x(), 100.0);
+
+        let smoothed = MedianFilter { radius: 2 }.smooth(&data);
+        assert!(smoothed.iter().all(|point| point.y() == 1.0));
+    }
+
+    #[test]
+    fn savitzky_golay_quadratic() {
+        let quadratic = |x: f64| 2.0 * x * x - 3.0 * x + 1.0;
+        let data = series(quadratic);
+
+        let smoothed = Smoothing {
+            method: SmoothingMethod::SavitzkyGolay,
+            radius: 4,
+        }
+        .smooth(&data);
+        for point in smoothed {
+            assert!((point.y() - quadratic(point.x())).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn gaussian_weights_normalised() {
+        for radius in 1..10 {
+            let total: f64 = GaussianFilter { radius }.weights().iter().sum();
+            assert!((total - 1.0).abs() < 1e-12);
+        }
+    }
+}
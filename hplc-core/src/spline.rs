@@ -1,13 +1,16 @@
 use std::{fmt::Debug, ops::RangeInclusive};
 
+use crate::linear::solve_matrix;
 use crate::vector::{Point2D, Vector2};
 
+/// Natural cubic spline through a set of points.
 #[derive(Clone, Debug)]
 pub struct Spline {
     cubics: Vec<(RangeInclusive<f64>, Cubic)>,
 }
 
 impl Spline {
+    /// Returns `None` if the points do not produce a solvable system.
     pub fn new(points: &[Point2D]) -> Option<Self> {
         let order = (points.len() - 1) * 4;
         let mut matrix: Vec<Vec<f64>> = Vec::with_capacity(order);
@@ -103,7 +106,7 @@ impl Spline {
             result.push(0.0);
         }
 
-        let maybe_splines = Self::solve_matrix(&mut matrix, &mut result);
+        let maybe_splines = solve_matrix(&mut matrix, &mut result);
         if let Some(splines) = maybe_splines {
             let (iter, _) = splines.as_chunks::<4>();
             let mut cubics = Vec::with_capacity(iter.len());
@@ -126,6 +129,7 @@ impl Spline {
         None
     }
 
+    /// Returns `None` outside the range of the points the spline was built from.
     pub fn evaluate(&self, value: f64) -> Option<f64> {
         for (range, cubic) in &self.cubics {
             if range.contains(&value) {
@@ -133,51 +137,7 @@ impl Spline {
             }
         }
 
-        return None;
-    }
-
-    fn solve_matrix(matrix: &mut [Vec<f64>], values: &mut [f64]) -> Option<Vec<f64>> {
-        let order = matrix.len();
-
-        for i in 0..order {
-            // Partial pivoting
-            let mut max_row = i;
-            for k in (i + 1)..order {
-                if matrix[k][i].abs() > matrix[max_row][i].abs() {
-                    max_row = k;
-                }
-            }
-
-            // Swap rows in matrix and vector
-            matrix.swap(i, max_row);
-            values.swap(i, max_row);
-
-            // Check for singular matrix
-            if matrix[i][i].abs() < 1e-12 {
-                return None; // Singular or nearly singular matrix
-            }
-
-            // Eliminate entries below pivot
-            for k in (i + 1)..order {
-                let factor = matrix[k][i] / matrix[i][i];
-                for j in i..order {
-                    matrix[k][j] -= factor * matrix[i][j];
-                }
-                values[k] -= factor * values[i];
-            }
-        }
-
-        // Back substitution
-        let mut x = vec![0.0; order];
-        for i in (0..order).rev() {
-            let mut sum = values[i];
-            for j in (i + 1)..order {
-                sum -= matrix[i][j] * x[j];
-            }
-            x[i] = sum / matrix[i][i];
-        }
-
-        Some(x)
+        None
     }
 }
 
@@ -0,0 +1,41 @@
+/// A (time, height) pair.
+pub type Point2D = (f64, f64);
+
+/// Accessors for 2D points.
+pub trait Vector2 {
+    fn new(x: f64, y: f64) -> Self;
+
+    fn x(&self) -> f64;
+    fn y(&self) -> f64;
+
+    fn gradient(&self, rhs: &Self) -> f64 {
+        let delta_x = rhs.x() - self.x();
+        let delta_y = rhs.y() - self.y();
+        delta_y / delta_x
+    }
+}
+
+impl Vector2 for Point2D {
+    fn new(x: f64, y: f64) -> Self {
+        (x, y)
+    }
+
+    fn x(&self) -> f64 {
+        self.0
+    }
+    fn y(&self) -> f64 {
+        self.1
+    }
+}
+
+/// Linear interpolation of the height of data sorted by time, `None` outside of the data.
+pub fn interpolate(data: &[Point2D], x: f64) -> Option<f64> {
+    let index = data.partition_point(|point| point.x() < x);
+    let next = data.get(index)?;
+    if next.x() == x {
+        return Some(next.y());
+    }
+
+    let prev = data.get(index.checked_sub(1)?)?;
+    Some(prev.y() + prev.gradient(next) * (x - prev.x()))
+}
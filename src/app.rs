@@ -1,20 +1,42 @@
+use std::fmt::{self, Display};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use iced::{
     Element, Length, Point, Subscription, Task,
     alignment::Horizontal,
-    widget::{button, column, radio, row, scrollable, text, text_input, toggler},
+    keyboard,
+    widget::{button, column, pick_list, radio, row, scrollable, text, text_input, toggler},
     window::{self, Settings, events},
 };
 use plotters_iced::ChartWidget;
 use rfd::FileHandle;
 
-use crate::{
+use hplc_core::{
+    baseline::{BaselineMethod, BaselineSettings},
     chromatography::{Chromatography, SampleType},
-    expandable_slider::{ExpandableSlider, Message as SliderMessage},
-    exporter::{self, Exporter},
+    component::Component,
+    deconvolution::PeakModel,
+    derivative::{DerivativeMethod, Derivatives},
+    editing::PeakEdit,
+    import::ImportProfile,
+    integration::{EventKind, IntegrationEvent, IntegrationMode, IntegrationWindow},
+    noise::{NoiseMethod, NoiseSettings},
     reference::Reference,
+    smoothing::{Smoothing, SmoothingMethod},
     spline::Spline,
+    vector::Point2D,
+};
+
+use crate::{
+    chromatogram::{self, Chromatogram, EditMode, PeakTool},
+    config::Config,
+    expandable_slider::{ExpandableSlider, Message as SliderMessage, SliderState},
+    exporter::{self, Exporter},
+    history::{History, Snapshot},
+    importer::{self, Importer},
+    project::{self, FileRecord, Parameters, Project, SampleRecord},
+    table,
 };
 
 //TODO implement docking
@@ -22,6 +44,7 @@ use crate::{
 pub struct App {
     main_window: window::Id,
     lipid_reference: Rc<[Reference]>,
+    reference_path: Option<PathBuf>,
     samples: Vec<Chromatography>,
     sample_handle: Option<usize>,
     blank_handle: Option<usize>,
@@ -35,6 +58,29 @@ pub struct App {
     sample_dilution_str: String,
     chart_start: ExpandableSlider,
     chart_end: ExpandableSlider,
+    smoothing_method: SmoothingMethod,
+    smoothing_radius: ExpandableSlider,
+    derivative_method: DerivativeMethod,
+    derivative_radius: ExpandableSlider,
+    derivative_order: ExpandableSlider,
+    baseline_method: BaselineMethod,
+    baseline_smoothness: ExpandableSlider,
+    baseline_asymmetry: ExpandableSlider,
+    baseline_iterations: ExpandableSlider,
+    baseline_radius: ExpandableSlider,
+    integration_mode: IntegrationMode,
+    integration_windows: Vec<IntegrationWindow>,
+    integration_window_strs: Vec<(String, String)>,
+    integration_events: Vec<IntegrationEvent>,
+    integration_event_strs: Vec<(String, String, String)>,
+    peak_model: PeakModel,
+    noise_method: NoiseMethod,
+    noise_start: f64,
+    noise_start_str: String,
+    noise_end: f64,
+    noise_end_str: String,
+    height_signal_to_noise: bool,
+    skim_ratio: ExpandableSlider,
     height_requirement: ExpandableSlider,
     inflection_requirement: ExpandableSlider,
     retention_time_tolerance: ExpandableSlider,
@@ -42,7 +88,20 @@ pub struct App {
     zoom_x: ExpandableSlider,
     zoom_y: ExpandableSlider,
     include_unknowns: bool,
+    subtract_blank: bool,
+    edit_mode: EditMode,
+    selected_peak: Option<f64>,
+    peak_tool: PeakTool,
+    sample_order: SampleOrder,
+    overlay_channels: bool,
     exporter: Exporter,
+    importer: Importer,
+    history: History,
+    hovered_samples: usize,
+    hovered_references: usize,
+    project_warnings: Vec<String>,
+    // Outcome of the last save, shown below the warnings
+    status: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -51,10 +110,47 @@ pub enum Message {
     CloseWindow(window::Id),
     RequestSamplePaths,
     LoadSampleFiles(Vec<FileHandle>),
+    FileHovered(PathBuf),
+    FilesHoveredLeft,
+    FileDropped(PathBuf),
     RequestReferencePath,
     LoadRefereceFile(FileHandle),
+    RequestProjectPath,
+    OpenProject(FileHandle),
+    RequestProjectTarget,
+    SaveProject(FileHandle),
+    SaveConfig,
     ChartStart(SliderMessage),
     ChartEnd(SliderMessage),
+    SmoothingMethodSelect(SmoothingMethod),
+    SmoothingRadius(SliderMessage),
+    DerivativeMethodSelect(DerivativeMethod),
+    DerivativeRadius(SliderMessage),
+    DerivativeOrder(SliderMessage),
+    BaselineMethodSelect(BaselineMethod),
+    BaselineSmoothness(SliderMessage),
+    BaselineAsymmetry(SliderMessage),
+    BaselineIterations(SliderMessage),
+    BaselineRadius(SliderMessage),
+    IntegrationSelect(IntegrationMode),
+    IntegrationApplyAll,
+    SkimRatio(SliderMessage),
+    AddIntegrationWindow,
+    RemoveIntegrationWindow(usize),
+    IntegrationWindowStart(usize, String),
+    IntegrationWindowEnd(usize, String),
+    IntegrationWindowMode(usize, IntegrationMode),
+    PeakModelSelect(PeakModel),
+    NoiseMethodSelect(NoiseMethod),
+    NoiseStart(String),
+    NoiseEnd(String),
+    HeightSignalToNoise(bool),
+    AddIntegrationEvent,
+    RemoveIntegrationEvent(usize),
+    IntegrationEventKind(usize, EventKind),
+    IntegrationEventStart(usize, String),
+    IntegrationEventEnd(usize, String),
+    IntegrationEventValue(usize, String),
     HeightRequirement(SliderMessage),
     InflectionRequirement(SliderMessage),
     RetentionTimeTolerance(SliderMessage),
@@ -62,11 +158,29 @@ pub enum Message {
     ZoomX(SliderMessage),
     ZoomY(SliderMessage),
     ShowUnknowns(bool),
+    SubtractBlank(bool),
     SampleTypeSelect(SampleType),
     InjectedVolume(String),
     SampleDilution(String),
     TabSwitch(usize),
+    EditModeSelect(EditMode),
+    ClearAnchors,
+    PeakToolSelect(PeakTool),
+    SampleOrderSelect(SampleOrder),
+    ChannelSelect(String),
+    ChannelApplyAll,
+    OverlayChannels(bool),
+    ClearPeakEdits,
+    SelectPeak(f64),
+    AssignLabel(String),
+    MarkUnknown,
+    LockLabel,
+    ClearLabel,
+    ChartMessage(chromatogram::Message),
     ExporterMessage(exporter::Message),
+    ImporterMessage(importer::Message),
+    Undo,
+    Redo,
 }
 
 impl From<()> for Message {
@@ -75,32 +189,94 @@ impl From<()> for Message {
     }
 }
 
+/// Order of the sample tabs, samples keep their index whatever the order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SampleOrder {
+    #[default]
+    Loaded,
+    Name,
+    Acquired,
+    SampleSet,
+    System,
+}
+
+impl SampleOrder {
+    pub const ALL: [SampleOrder; 5] = [
+        SampleOrder::Loaded,
+        SampleOrder::Name,
+        SampleOrder::Acquired,
+        SampleOrder::SampleSet,
+        SampleOrder::System,
+    ];
+}
+
+impl Display for SampleOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SampleOrder::Loaded => "Loaded",
+            SampleOrder::Name => "Sample Name",
+            SampleOrder::Acquired => "Date Acquired",
+            SampleOrder::SampleSet => "Sample Set",
+            SampleOrder::System => "System",
+        };
+
+        f.write_str(name)
+    }
+}
+
 impl App {
     pub fn new() -> (Self, Task<Message>) {
         let settings = Settings::default();
         let (id, task) = window::open(settings);
 
-        // TODO load configuration from settings
-        let chart_start = ExpandableSlider::new(8.5, 0.0, 60.0, 0.5, "Chart Start");
-        let chart_end = ExpandableSlider::new(36.5, 0.0, 60.0, 0.5, "Chart End");
-        let height_requirement = ExpandableSlider::new(0.3, 0.0, 1.0, 0.01, "Height Requirement");
+        let config = Config::load();
+        let parameters = &config.parameters;
+
+        let slider = |state: &SliderState, label| {
+            ExpandableSlider::new(state.value, state.start, state.end, state.step, label)
+        };
+
+        let chart_start = slider(&parameters.chart_start, "Chart Start");
+        let chart_end = slider(&parameters.chart_end, "Chart End");
+        let smoothing_radius = slider(&parameters.smoothing_radius, "Smoothing Radius");
+        let derivative_radius = slider(&parameters.derivative_radius, "Derivative Radius");
+        let derivative_order = slider(&parameters.derivative_order, "Derivative Order");
+        let baseline_smoothness = slider(
+            &parameters.baseline_smoothness,
+            "Baseline Smoothness (log10 λ)",
+        );
+        let baseline_asymmetry = slider(&parameters.baseline_asymmetry, "Baseline Asymmetry");
+        let baseline_iterations = slider(&parameters.baseline_iterations, "Baseline Iterations");
+        let baseline_radius = slider(&parameters.baseline_radius, "Baseline Radius");
+        let skim_ratio = slider(&parameters.skim_ratio, "Skim Ratio");
+        let height_requirement = slider(&parameters.height_requirement, "Height Requirement");
         let inflection_requirement =
-            ExpandableSlider::new(10.0, 0.0, 10.0, 1.0, "Inflection Requirement");
-        let retention_time_tolerance =
-            ExpandableSlider::new(0.2, 0.0, 1.0, 0.01, "Retention Time Tolerance");
+            slider(&parameters.inflection_requirement, "Inflection Requirement");
+        let retention_time_tolerance = slider(
+            &parameters.retention_time_tolerance,
+            "Retention Time Tolerance",
+        );
 
         let glucose_unit_tolerance =
-            ExpandableSlider::new(0.02, 0.0, 1.0, 0.01, "Glucose Unit Tolerance");
+            slider(&parameters.glucose_unit_tolerance, "Glucose Unit Tolerance");
 
-        let mut zoom_x = ExpandableSlider::new(0.0, 0.0, 100.0, 1.1, "Horizontal Zoom");
-        let mut zoom_y = ExpandableSlider::new(0.0, 0.0, 100.0, 1.1, "Vertical Zoom");
+        let mut zoom_x = slider(&parameters.zoom_x, "Horizontal Zoom");
+        let mut zoom_y = slider(&parameters.zoom_y, "Vertical Zoom");
 
         zoom_x.set_exponential(true);
         zoom_y.set_exponential(true);
 
+        let mut exporter = Exporter::default();
+        exporter.set_settings(config.export.clone());
+        exporter.set_global_zoom(Point::new(zoom_x.get_value(), zoom_y.get_value()));
+
+        let mut importer = Importer::default();
+        importer.set_profiles(config.import_profiles.clone());
+
         let app = Self {
             main_window: id,
             lipid_reference: Rc::default(),
+            reference_path: None,
             samples: Vec::default(),
             sample_handle: None,
             blank_handle: None,
@@ -108,20 +284,55 @@ impl App {
             standard_handle: None,
             glucose_transformer: None,
             concentration_multiplier: None,
-            injected_volume: 50.0,
-            injected_volume_str: "50.0".to_string(),
-            sample_dilution: 40.0,
-            sample_dilution_str: "40.0".to_string(),
+            injected_volume: parameters.injected_volume,
+            injected_volume_str: parameters.injected_volume.to_string(),
+            sample_dilution: parameters.sample_dilution,
+            sample_dilution_str: parameters.sample_dilution.to_string(),
             chart_start,
             chart_end,
+            smoothing_method: parameters.smoothing,
+            smoothing_radius,
+            derivative_method: parameters.derivatives,
+            derivative_radius,
+            derivative_order,
+            baseline_method: parameters.baseline,
+            baseline_smoothness,
+            baseline_asymmetry,
+            baseline_iterations,
+            baseline_radius,
+            integration_mode: parameters.integration,
+            integration_windows: parameters.integration_windows.clone(),
+            integration_window_strs: window_strs(&parameters.integration_windows),
+            integration_events: parameters.integration_events.clone(),
+            integration_event_strs: event_strs(&parameters.integration_events),
+            peak_model: parameters.peak_model,
+            noise_method: parameters.noise,
+            noise_start: parameters.noise_start,
+            noise_start_str: parameters.noise_start.to_string(),
+            noise_end: parameters.noise_end,
+            noise_end_str: parameters.noise_end.to_string(),
+            height_signal_to_noise: parameters.height_signal_to_noise,
+            skim_ratio,
             height_requirement,
             inflection_requirement,
             retention_time_tolerance,
             glucose_unit_tolerance,
             zoom_x,
             zoom_y,
-            include_unknowns: false,
-            exporter: Exporter::default(),
+            include_unknowns: parameters.include_unknowns,
+            subtract_blank: parameters.subtract_blank,
+            edit_mode: EditMode::View,
+            selected_peak: None,
+            peak_tool: PeakTool::Adjust,
+            sample_order: SampleOrder::Loaded,
+            overlay_channels: false,
+            exporter,
+            importer,
+            history: History::default(),
+            hovered_samples: 0,
+            hovered_references: 0,
+            project_warnings: vec![],
+            status: None,
         };
 
         (app, task.map(|_| Message::None))
@@ -140,11 +351,23 @@ impl App {
                 .map(Message::ExporterMessage);
         }
 
+        if self.importer.owns_window(window_id) {
+            return self.importer.view().map(Message::ImporterMessage);
+        }
+
         let load_data_file = button("Load Raw Data File").on_press(Message::RequestSamplePaths);
 
+        let import_text_file = self.importer.external_view().map(Message::ImporterMessage);
+
         let load_reference_file =
             button("Load Lipid Reference File").on_press(Message::RequestReferencePath);
 
+        let open_project = button("Open Project").on_press(Message::RequestProjectPath);
+
+        let save_project = button("Save Project").on_press(Message::RequestProjectTarget);
+
+        let save_config = button("Save as Defaults").on_press(Message::SaveConfig);
+
         let export_file = self
             .exporter
             .external_csv_view()
@@ -159,6 +382,83 @@ impl App {
 
         let chart_end = self.chart_end.view().map(Message::ChartEnd);
 
+        let smoothing_radius = self.smoothing_radius.view().map(Message::SmoothingRadius);
+
+        let derivative_radius = self.derivative_radius.view().map(Message::DerivativeRadius);
+
+        let derivative_order = self.derivative_order.view().map(Message::DerivativeOrder);
+
+        // Only the parameters used by the selected baseline
+        let baseline_options = match self.baseline_method {
+            BaselineMethod::ConvexHull => column![],
+            BaselineMethod::AsymmetricLeastSquares => column![
+                self.baseline_smoothness
+                    .view()
+                    .map(Message::BaselineSmoothness),
+                self.baseline_asymmetry
+                    .view()
+                    .map(Message::BaselineAsymmetry),
+                self.baseline_iterations
+                    .view()
+                    .map(Message::BaselineIterations),
+            ],
+            BaselineMethod::Arpls => column![
+                self.baseline_smoothness
+                    .view()
+                    .map(Message::BaselineSmoothness),
+                self.baseline_iterations
+                    .view()
+                    .map(Message::BaselineIterations),
+            ],
+            BaselineMethod::RollingBall | BaselineMethod::Snip => {
+                column![self.baseline_radius.view().map(Message::BaselineRadius)]
+            }
+        };
+
+        let skim_ratio = self.skim_ratio.view().map(Message::SkimRatio);
+
+        let integration_windows = {
+            let mut windows = column![text("Integration Windows (minutes)")];
+            for (i, window) in self.integration_windows.iter().enumerate() {
+                let (start, end) = &self.integration_window_strs[i];
+                let start = text_input("start", start)
+                    .on_input(move |input| Message::IntegrationWindowStart(i, input));
+                let end = text_input("end", end)
+                    .on_input(move |input| Message::IntegrationWindowEnd(i, input));
+                let mode = pick_list(IntegrationMode::ALL, Some(window.mode), move |mode| {
+                    Message::IntegrationWindowMode(i, mode)
+                });
+                let remove = button("Remove").on_press(Message::RemoveIntegrationWindow(i));
+                windows = windows.push(row![start, end, mode, remove]);
+            }
+
+            windows.push(button("Add Window").on_press(Message::AddIntegrationWindow))
+        };
+
+        let integration_events = {
+            let mut events = column![text("Integration Events (minutes, empty end for no end)")];
+            for (i, event) in self.integration_events.iter().enumerate() {
+                let (start, end, value) = &self.integration_event_strs[i];
+                let kind = pick_list(EventKind::ALL, Some(event.kind), move |kind| {
+                    Message::IntegrationEventKind(i, kind)
+                });
+                let start = text_input("start", start)
+                    .on_input(move |input| Message::IntegrationEventStart(i, input));
+                let end = text_input("end", end)
+                    .on_input(move |input| Message::IntegrationEventEnd(i, input));
+                let value = if event.kind.has_value() {
+                    text_input("value", value)
+                        .on_input(move |input| Message::IntegrationEventValue(i, input))
+                } else {
+                    text_input("", "")
+                };
+                let remove = button("Remove").on_press(Message::RemoveIntegrationEvent(i));
+                events = events.push(row![kind, start, end, value, remove]);
+            }
+
+            events.push(button("Add Event").on_press(Message::AddIntegrationEvent))
+        };
+
         let height_requirement = self
             .height_requirement
             .view()
@@ -184,12 +484,20 @@ impl App {
         let zoom_y = self.zoom_y.view().map(Message::ZoomY);
 
         let options = column![
-            load_data_file,
+            row![load_data_file, import_text_file],
             load_reference_file,
+            row![open_project, save_project, save_config],
             export_file,
             export_profiles,
             chart_start,
             chart_end,
+            smoothing_radius,
+            derivative_radius,
+            derivative_order,
+            baseline_options,
+            skim_ratio,
+            integration_windows,
+            integration_events,
             height_requirement,
             inflection_requirement,
             retention_time_tolerance,
@@ -205,6 +513,167 @@ impl App {
             row![toggle, label]
         };
 
+        let subtract_blank = {
+            let toggle = toggler(self.subtract_blank).on_toggle(Message::SubtractBlank);
+            let label = text("Subtract Blank").align_x(Horizontal::Center);
+            row![toggle, label]
+        };
+
+        let smoothing_method = {
+            let label = text("Smoothing: ");
+            let list = pick_list(
+                SmoothingMethod::ALL,
+                Some(self.smoothing_method),
+                Message::SmoothingMethodSelect,
+            );
+            row![label, list]
+        };
+
+        let derivative_method = {
+            let label = text("Derivatives: ");
+            let list = pick_list(
+                DerivativeMethod::ALL,
+                Some(self.derivative_method),
+                Message::DerivativeMethodSelect,
+            );
+            row![label, list]
+        };
+
+        let baseline_method = {
+            let label = text("Baseline: ");
+            let list = pick_list(
+                BaselineMethod::ALL,
+                Some(self.baseline_method),
+                Message::BaselineMethodSelect,
+            );
+            row![label, list]
+        };
+
+        let integration_mode = {
+            let label = text("Integration: ");
+            let selected = self
+                .sample_handle
+                .map(|handle| self.samples[handle].get_integration_mode())
+                .unwrap_or(self.integration_mode);
+            let list = pick_list(
+                IntegrationMode::ALL,
+                Some(selected),
+                Message::IntegrationSelect,
+            );
+            let apply = button("Apply to All").on_press(Message::IntegrationApplyAll);
+            row![label, list, apply]
+        };
+
+        let peak_model = {
+            let label = text("Deconvolution: ");
+            let list = pick_list(
+                PeakModel::ALL,
+                Some(self.peak_model),
+                Message::PeakModelSelect,
+            );
+            row![label, list]
+        };
+
+        let noise = {
+            let label = text("Noise: ");
+            let list = pick_list(
+                NoiseMethod::ALL,
+                Some(self.noise_method),
+                Message::NoiseMethodSelect,
+            );
+
+            // Only the quiet region needs bounds
+            match self.noise_method {
+                NoiseMethod::Mad => row![label, list],
+                NoiseMethod::QuietRegion => {
+                    let start =
+                        text_input("start", &self.noise_start_str).on_input(Message::NoiseStart);
+                    let end = text_input("end", &self.noise_end_str).on_input(Message::NoiseEnd);
+                    row![label, list, start, end]
+                }
+            }
+        };
+
+        let height_signal_to_noise = {
+            let toggle =
+                toggler(self.height_signal_to_noise).on_toggle(Message::HeightSignalToNoise);
+            let label = text("Height Requirement as S/N").align_x(Horizontal::Center);
+            row![toggle, label]
+        };
+
+        let edit_mode = {
+            let label = text("Chart Editing: ");
+            let list = pick_list(EditMode::ALL, Some(self.edit_mode), Message::EditModeSelect);
+            let clear = button("Clear Anchors").on_press(Message::ClearAnchors);
+
+            if self.edit_mode == EditMode::Peaks {
+                let tool = pick_list(PeakTool::ALL, Some(self.peak_tool), Message::PeakToolSelect);
+                let clear_edits = button("Clear Peak Edits").on_press(Message::ClearPeakEdits);
+                row![label, list, clear, tool, clear_edits]
+            } else {
+                row![label, list, clear]
+            }
+        };
+
+        let labels = {
+            let selected = self
+                .sample_handle
+                .zip(self.selected_peak)
+                .and_then(|(handle, time)| self.samples[handle].get_component_at(time));
+
+            match selected {
+                Some(component) => {
+                    let current = match component {
+                        Component::Located(_, reference) => reference.name.clone(),
+                        _ => None,
+                    };
+                    let names: Vec<String> = self
+                        .lipid_reference
+                        .iter()
+                        .filter_map(|reference| reference.name.clone())
+                        .collect();
+
+                    let label = text(format!(
+                        "Peak {:.3}: ",
+                        component.get_experimental_rt().unwrap_or_default()
+                    ));
+                    let list = pick_list(names, current.clone(), Message::AssignLabel);
+                    let unknown = button("Unknown").on_press(Message::MarkUnknown);
+                    let lock = button("Lock").on_press_maybe(
+                        (current.is_some() && !component.is_manual()).then_some(Message::LockLabel),
+                    );
+                    let clear = button("Clear")
+                        .on_press_maybe(component.is_manual().then_some(Message::ClearLabel));
+                    row![label, list, unknown, lock, clear]
+                }
+                None if self.edit_mode == EditMode::Labels => {
+                    row![text("Select a peak on the chart or in the table")]
+                }
+                None => row![],
+            }
+        };
+
+        // Only files with several signal columns have a choice of channel
+        let channel = match self.sample_handle.map(|handle| &self.samples[handle]) {
+            Some(sample) if sample.get_channels().len() > 1 => {
+                let names: Vec<String> = sample
+                    .get_channels()
+                    .iter()
+                    .map(|channel| channel.name.clone())
+                    .collect();
+                let current = names.get(sample.get_channel()).cloned();
+
+                let label = text("Channel: ");
+                let list = pick_list(names, current, Message::ChannelSelect);
+                let apply_all = button("Apply to All").on_press(Message::ChannelApplyAll);
+                let overlay = toggler(self.overlay_channels)
+                    .label("Overlay")
+                    .on_toggle(Message::OverlayChannels);
+                row![label, list, apply_all, overlay]
+            }
+            _ => row![],
+        };
+
         let sample_type = {
             let selected = self
                 .sample_handle
@@ -246,10 +715,16 @@ impl App {
             if self.standard_handle.is_none() {
                 content += "Standard not set. Cannot calculate concentration.";
             }
+            for warning in &self.project_warnings {
+                content += "\n";
+                content += warning;
+            }
 
             text(content).color(iced::color!(0xff0000))
         };
 
+        let status = self.status.as_ref().map(|status| text(status.clone()));
+
         let injected_volume = {
             let label = text("Vinjection (µl): ");
             let input =
@@ -266,18 +741,72 @@ impl App {
 
         let options2 = column![
             unknown_lipid,
+            subtract_blank,
+            smoothing_method,
+            derivative_method,
+            baseline_method,
+            integration_mode,
+            peak_model,
+            noise,
+            height_signal_to_noise,
+            edit_mode,
+            labels,
+            channel,
             sample_type,
             warnings,
-            injected_volume,
-            sample_dilution
         ]
+        .push_maybe(status)
+        .push(injected_volume)
+        .push(sample_dilution)
         .width(250);
 
+        let history = {
+            let undo =
+                button("Undo").on_press_maybe(self.history.can_undo().then_some(Message::Undo));
+            let redo =
+                button("Redo").on_press_maybe(self.history.can_redo().then_some(Message::Redo));
+
+            let mut actions = column![];
+            for action in self.history.done() {
+                actions = actions.push(text(action.to_string()));
+            }
+            for action in self.history.undone() {
+                actions = actions.push(text(action.to_string()).color(iced::color!(0x808080)));
+            }
+
+            column![
+                text("History"),
+                row![undo, redo],
+                scrollable(actions).height(300)
+            ]
+            .width(250)
+        };
+
         let ui = if let Some(handle) = self.sample_handle {
-            let header = text(format!("Sample {}", handle));
+            let header = text(format!(
+                "Sample {} (noise {:.3e})",
+                handle,
+                self.samples[handle].get_noise()
+            ));
             let tabs = {
+                let order = pick_list(
+                    SampleOrder::ALL,
+                    Some(self.sample_order),
+                    Message::SampleOrderSelect,
+                );
+
                 let mut buttons = column![];
-                for (i, sample) in self.samples.iter().enumerate() {
+                let mut last_group = None;
+                for i in self.sorted_samples() {
+                    let sample = &self.samples[i];
+
+                    if let Some(group) = self.sample_group(sample)
+                        && last_group.as_ref() != Some(&group)
+                    {
+                        buttons = buttons.push(text(group.clone()));
+                        last_group = Some(group);
+                    }
+
                     let content = format!("{}", i);
                     let label = text(content);
                     let button =
@@ -295,34 +824,363 @@ impl App {
                             });
                     buttons = buttons.push(button);
                 }
-                scrollable(buttons)
+                column![order, scrollable(buttons)]
             };
 
             let sample = &self.samples[handle];
-            let table = sample
-                .into_table_element(self.concentration_multiplier.unwrap_or(0.0))
-                .map(Message::from);
+            let table = table::sample_table(sample, self.concentration_multiplier.unwrap_or(0.0))
+                .map(Message::SelectPeak);
+
+            let info = {
+                let metadata = sample.get_metadata();
+                let mut lines = column![
+                    text("Sample Info"),
+                    text(format!("File: {}", sample.file_path.display())),
+                    text(format!("Format: {}", sample.get_format()))
+                ];
+                if let Some(channel) = sample.get_channels().get(sample.get_channel()) {
+                    let unit = match &channel.unit {
+                        Some(unit) => format!(" ({})", unit),
+                        None => String::new(),
+                    };
+                    lines = lines.push(text(format!("Channel: {}{}", channel.name, unit)));
+                }
+                for (key, _) in metadata.headers.iter() {
+                    let value = metadata.format(key).unwrap_or_default();
+                    lines = lines.push(text(format!("{}: {}", key, value)));
+                }
 
-            let footer = row![options, options2];
-            let chart: Element<()> = ChartWidget::new(sample.clone()).width(Length::Fill).into();
+                lines.width(300)
+            };
 
-            let body = row![tabs, chart.map(Message::from)];
+            let footer = row![options, options2, info, history];
+            let zoom = Point::new(self.zoom_x.get_value(), self.zoom_y.get_value());
+            let chart: Element<chromatogram::Message> = ChartWidget::new(
+                Chromatogram::new(sample, zoom)
+                    .with_edit_mode(self.edit_mode)
+                    .with_peak_tool(self.peak_tool)
+                    .with_selected_peak(self.selected_peak)
+                    .with_overlay(self.overlay_channels),
+            )
+            .width(Length::Fill)
+            .into();
+
+            let body = row![tabs, chart.map(Message::ChartMessage)];
             column![header, body, footer, table]
         } else {
-            let footer = row![options, options2];
+            let footer = row![options, options2, history];
             let scroll_footer = scrollable(footer).direction(scrollable::Direction::Horizontal(
                 scrollable::Scrollbar::default(),
             ));
             column![scroll_footer]
         };
 
-        ui.into()
+        let drop_hint = (self.hovered_samples + self.hovered_references > 0).then(|| {
+            let mut content = String::from("Drop to load");
+            if self.hovered_samples > 0 {
+                content += &format!(" {} samples", self.hovered_samples);
+            }
+            if self.hovered_samples > 0 && self.hovered_references > 0 {
+                content += " and";
+            }
+            if self.hovered_references > 0 {
+                content += " the reference";
+            }
+
+            text(content).size(20).color(iced::color!(0x0000ff))
+        });
+
+        column![].push_maybe(drop_hint).push(ui).into()
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
+            Message::Undo => {
+                if let Some(snapshot) = self.history.undo(self.snapshot()) {
+                    self.restore(snapshot);
+                }
+
+                Task::none()
+            }
+            Message::Redo => {
+                if let Some(snapshot) = self.history.redo(self.snapshot()) {
+                    self.restore(snapshot);
+                }
+
+                Task::none()
+            }
+            message => match self.action_name(&message) {
+                Some(action) => {
+                    // Further steps of a drag or typing keep the state from before it started
+                    let continuous = is_continuous(&message);
+                    if !self.history.would_merge(&action, continuous) {
+                        let before = self.snapshot();
+                        self.history.record(action, before, continuous);
+                    }
+
+                    self.apply(message)
+                }
+                None => {
+                    // Releasing a slider or anything else in between ends a drag or typing
+                    if !matches!(message, Message::None) {
+                        self.history.end_interaction();
+                    }
+
+                    self.apply(message)
+                }
+            },
+        }
+    }
+
+    /// Name shown in the history of messages which change the analysis, `None` for everything else.
+    ///
+    /// Messages which will be rejected (e.g. letters typed into a number) do not change anything either.
+    fn action_name(&self, message: &Message) -> Option<String> {
+        let selected = self.sample_handle.zip(self.selected_peak);
+
+        let name = match message {
+            Message::LoadSampleFiles(handles) => format!("Load {} Samples", handles.len()),
+            Message::FileDropped(path) => format!("Drop {}", path.file_name()?.to_string_lossy()),
+            Message::ImporterMessage(importer::Message::Import) => {
+                let (path, _) = self.importer.get_import()?;
+                format!("Import {}", path.file_name()?.to_string_lossy())
+            }
+            Message::LoadRefereceFile(handle) => format!("Load Reference {}", handle.file_name()),
+            Message::OpenProject(handle) => format!("Open Project {}", handle.file_name()),
+            Message::ChartStart(slider) if changes(slider) => "Chart Start".to_string(),
+            Message::ChartEnd(slider) if changes(slider) => "Chart End".to_string(),
+            Message::SmoothingMethodSelect(method) => format!("Smoothing: {}", method),
+            Message::SmoothingRadius(slider) if changes(slider) => "Smoothing Radius".to_string(),
+            Message::DerivativeMethodSelect(method) => format!("Derivatives: {}", method),
+            Message::DerivativeRadius(slider) if changes(slider) => "Derivative Radius".to_string(),
+            Message::DerivativeOrder(slider) if changes(slider) => "Derivative Order".to_string(),
+            Message::BaselineMethodSelect(method) => format!("Baseline: {}", method),
+            Message::BaselineSmoothness(slider) if changes(slider) => {
+                "Baseline Smoothness".to_string()
+            }
+            Message::BaselineAsymmetry(slider) if changes(slider) => {
+                "Baseline Asymmetry".to_string()
+            }
+            Message::BaselineIterations(slider) if changes(slider) => {
+                "Baseline Iterations".to_string()
+            }
+            Message::BaselineRadius(slider) if changes(slider) => "Baseline Radius".to_string(),
+            Message::IntegrationSelect(mode) => format!("Integration: {}", mode),
+            Message::IntegrationApplyAll => "Apply Integration to All".to_string(),
+            Message::SkimRatio(slider) if changes(slider) => "Skim Ratio".to_string(),
+            Message::AddIntegrationWindow => "Add Integration Window".to_string(),
+            Message::RemoveIntegrationWindow(index) if *index < self.integration_windows.len() => {
+                format!("Remove Integration Window {}", index)
+            }
+            Message::IntegrationWindowStart(index, input)
+            | Message::IntegrationWindowEnd(index, input)
+                if *index < self.integration_windows.len() && is_decimal(input) =>
+            {
+                format!("Integration Window {}", index)
+            }
+            Message::IntegrationWindowMode(index, _) if *index < self.integration_windows.len() => {
+                format!("Integration Window {}", index)
+            }
+            Message::PeakModelSelect(model) => format!("Deconvolution: {}", model),
+            Message::NoiseMethodSelect(method) => format!("Noise: {}", method),
+            Message::NoiseStart(input) | Message::NoiseEnd(input) if is_decimal(input) => {
+                "Noise Region".to_string()
+            }
+            Message::HeightSignalToNoise(_) => "Height Requirement as S/N".to_string(),
+            Message::AddIntegrationEvent => "Add Integration Event".to_string(),
+            Message::RemoveIntegrationEvent(index) if *index < self.integration_events.len() => {
+                format!("Remove Integration Event {}", index)
+            }
+            Message::IntegrationEventStart(index, input)
+            | Message::IntegrationEventEnd(index, input)
+            | Message::IntegrationEventValue(index, input)
+                if *index < self.integration_events.len() && is_decimal(input) =>
+            {
+                format!("Integration Event {}", index)
+            }
+            Message::IntegrationEventKind(index, _) if *index < self.integration_events.len() => {
+                format!("Integration Event {}", index)
+            }
+            Message::HeightRequirement(slider) if changes(slider) => {
+                "Height Requirement".to_string()
+            }
+            Message::InflectionRequirement(slider) if changes(slider) => {
+                "Inflection Requirement".to_string()
+            }
+            Message::RetentionTimeTolerance(slider) if changes(slider) => {
+                "RT Tolerance".to_string()
+            }
+            Message::GlucoseUnitTolerance(slider) if changes(slider) => "GU Tolerance".to_string(),
+            Message::ShowUnknowns(_) => "Show Unknowns".to_string(),
+            Message::SubtractBlank(_) => "Subtract Blank".to_string(),
+            Message::SampleTypeSelect(sample_type) => {
+                let handle = self.sample_handle?;
+                format!("Sample {} as {:?}", handle, sample_type)
+            }
+            Message::ChannelSelect(name) => {
+                let handle = self.sample_handle?;
+                format!("Sample {} Channel {}", handle, name)
+            }
+            Message::ChannelApplyAll => "Apply Channel to All".to_string(),
+            Message::InjectedVolume(input) if is_decimal(input) => "Vinjection".to_string(),
+            Message::SampleDilution(input) if is_decimal(input) => "Dilution".to_string(),
+            Message::ClearAnchors => "Clear Anchors".to_string(),
+            Message::ClearPeakEdits => "Clear Peak Edits".to_string(),
+            Message::AssignLabel(name) => {
+                let (_, time) = selected?;
+                format!("Label {:.3} as {}", time, name)
+            }
+            Message::MarkUnknown => format!("Label {:.3} as Unknown", selected?.1),
+            Message::LockLabel => format!("Lock Label {:.3}", selected?.1),
+            Message::ClearLabel => format!("Clear Label {:.3}", selected?.1),
+            Message::ChartMessage(message) => match message {
+                chromatogram::Message::AddAnchor(point) => format!("Add Anchor {:.3}", point.0),
+                chromatogram::Message::MoveAnchor(index, _) => format!("Move Anchor {}", index),
+                chromatogram::Message::RemoveAnchor(index) => format!("Remove Anchor {}", index),
+                chromatogram::Message::ReleaseAnchor | chromatogram::Message::SelectPeak(_) => {
+                    return None;
+                }
+                chromatogram::Message::EditPeak(edit) => match edit {
+                    PeakEdit::Add { start, end } => format!("Add Peak {:.3}-{:.3}", start, end),
+                    PeakEdit::Delete { time } => format!("Delete Peak {:.3}", time),
+                    PeakEdit::Split { time } => format!("Split Peak at {:.3}", time),
+                    PeakEdit::Merge { first, second } => {
+                        format!("Merge Peaks {:.3}-{:.3}", first, second)
+                    }
+                    PeakEdit::Bounds { start, end, .. } => {
+                        format!("Move Peak to {:.3}-{:.3}", start, end)
+                    }
+                },
+            },
+            _ => return None,
+        };
+
+        Some(name)
+    }
+
+    /// Recalculates what depends on the type of a sample (blank, GU spline, concentration) after its data changed.
+    fn refresh_sample_type(&mut self, handle: usize) {
+        let sample_type = self.samples[handle].get_sample_type();
+        if sample_type != SampleType::Data {
+            let selected = self.sample_handle.replace(handle);
+            let _ = self.apply(Message::SampleTypeSelect(sample_type));
+            self.sample_handle = selected;
+        }
+    }
+
+    /// Indices of the samples in the selected order, ties keep the order they were loaded in.
+    fn sorted_samples(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.samples.len()).collect();
+        let metadata = |i: usize| self.samples[i].get_metadata();
+
+        match self.sample_order {
+            SampleOrder::Loaded => {}
+            SampleOrder::Name => indices.sort_by(|&lhs, &rhs| {
+                let name = |i: usize| {
+                    metadata(i)
+                        .sample_name
+                        .as_deref()
+                        .unwrap_or(&self.samples[i].title)
+                };
+                name(lhs).cmp(name(rhs))
+            }),
+            SampleOrder::Acquired => indices.sort_by_key(|&i| metadata(i).date_acquired.clone()),
+            SampleOrder::SampleSet => indices.sort_by_key(|&i| {
+                let metadata = metadata(i);
+                (
+                    metadata.sample_set_name.clone(),
+                    metadata.date_acquired.clone(),
+                )
+            }),
+            SampleOrder::System => indices.sort_by_key(|&i| {
+                let metadata = metadata(i);
+                (metadata.system_name.clone(), metadata.date_acquired.clone())
+            }),
+        }
+
+        indices
+    }
+
+    /// Heading the sample is grouped under in the tabs, `None` when the order has no groups.
+    fn sample_group(&self, sample: &Chromatography) -> Option<String> {
+        let metadata = sample.get_metadata();
+        match self.sample_order {
+            SampleOrder::SampleSet => Some(
+                metadata
+                    .sample_set_name
+                    .clone()
+                    .unwrap_or("No Sample Set".to_string()),
+            ),
+            SampleOrder::System => Some(
+                metadata
+                    .system_name
+                    .clone()
+                    .unwrap_or("No System".to_string()),
+            ),
+            _ => None,
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            parameters: self.get_parameters(),
+            lipid_reference: Rc::clone(&self.lipid_reference),
+            reference_path: self.reference_path.clone(),
+            samples: self.samples.clone(),
+            sample_handle: self.sample_handle,
+            blank_handle: self.blank_handle,
+            dex_handle: self.dex_handle,
+            standard_handle: self.standard_handle,
+            glucose_transformer: self.glucose_transformer.clone(),
+            concentration_multiplier: self.concentration_multiplier,
+        }
+    }
+
+    /// Returns to a snapshot, the samples are restored as they were rather than recalculated.
+    fn restore(&mut self, snapshot: Snapshot) {
+        // Zoom only changes the view and is not part of the history
+        let mut parameters = snapshot.parameters;
+        parameters.zoom_x = self.zoom_x.get_state();
+        parameters.zoom_y = self.zoom_y.get_state();
+        self.apply_parameters(&parameters);
+
+        self.lipid_reference = snapshot.lipid_reference;
+        self.reference_path = snapshot.reference_path;
+        self.exporter
+            .set_lipid_references(Rc::clone(&self.lipid_reference));
+
+        self.samples = snapshot.samples;
+        self.sample_handle = snapshot.sample_handle;
+        self.blank_handle = snapshot.blank_handle;
+        self.dex_handle = snapshot.dex_handle;
+        self.standard_handle = snapshot.standard_handle;
+        self.selected_peak = None;
+
+        self.glucose_transformer = snapshot.glucose_transformer;
+        self.exporter
+            .set_glucose_spline(self.glucose_transformer.as_ref());
+
+        self.concentration_multiplier = snapshot.concentration_multiplier;
+        self.exporter
+            .set_concentration_multiplier(self.standard_area(), self.concentration_multiplier);
+    }
+
+    /// Area of the first peak of the standard, `None` without a standard or if it has no peaks.
+    fn standard_area(&self) -> Option<f64> {
+        self.standard_handle.and_then(|handle| {
+            self.samples[handle]
+                .get_unqualified_components()
+                .first()
+                .map(|peak| peak.area)
+        })
+    }
+
+    fn apply(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::Undo | Message::Redo => self.update(message),
             Message::None => Task::none(),
             Message::CloseWindow(id) => {
+                self.importer.close(id);
                 if id == self.main_window {
                     iced::exit().map(|_: ()| Message::None)
                 } else {
@@ -332,7 +1190,10 @@ impl App {
             Message::RequestSamplePaths => {
                 let task = rfd::AsyncFileDialog::new()
                     .add_filter("any", &["*"])
-                    .add_filter("text", &["arw", "csv", "tsv", "txt"])
+                    .add_filter("text", &["arw", "csv", "tsv", "txt", "asc"])
+                    .add_filter("AIA/ANDI", &["cdf"])
+                    .add_filter("ChemStation", &["csv"])
+                    .add_filter("LabSolutions / Chromeleon", &["txt", "asc"])
                     .pick_files();
 
                 Task::perform(task, |maybe_handles| match maybe_handles {
@@ -342,27 +1203,43 @@ impl App {
             }
             Message::LoadSampleFiles(handles) => {
                 for handle in handles {
-                    let path = handle.path();
-                    let mut sample = match Chromatography::from_file(&path) {
-                        Some(value) => value,
-                        None => continue,
-                    };
+                    self.load_sample(handle.path(), None);
+                }
+
+                if !self.samples.is_empty() {
+                    self.sample_handle = Some(self.samples.len() - 1);
+                }
+                Task::none()
+            }
+            Message::FileHovered(path) => {
+                if Reference::is_reference_file(&path) {
+                    self.hovered_references += 1;
+                } else if is_data_file(&path) {
+                    self.hovered_samples += 1;
+                }
 
-                    let range = self.chart_start.get_value()..self.chart_end.get_value();
-                    sample.set_data_range(&range);
-                    sample.set_lipid_references(Rc::clone(&self.lipid_reference));
-                    sample.set_include_unknowns(&self.include_unknowns);
-                    sample.set_height_requirement(&self.height_requirement.get_value());
-                    sample.set_inflection_requirement(&self.inflection_requirement.get_value());
-                    sample.set_retention_time_tolerance(&self.retention_time_tolerance.get_value());
-                    sample.set_glucose_unit_tolerance(&self.glucose_unit_tolerance.get_value());
-                    sample.set_glucose_transformer(&self.glucose_transformer);
-                    let zoom = Point::new(self.zoom_x.get_value(), self.zoom_y.get_value());
-                    sample.set_global_zoom(&zoom);
-                    self.samples.push(sample);
+                Task::none()
+            }
+            Message::FilesHoveredLeft => {
+                self.hovered_samples = 0;
+                self.hovered_references = 0;
+
+                Task::none()
+            }
+            Message::FileDropped(path) => {
+                // Every dropped file is its own event, the hover has ended after the first
+                self.hovered_samples = 0;
+                self.hovered_references = 0;
+
+                if Reference::is_reference_file(&path) {
+                    self.load_reference(&path);
+                } else if !is_data_file(&path) {
+                    self.project_warnings
+                        .push(format!("Could not load {}", path.display()));
+                } else if self.load_sample(&path, None) {
+                    self.sample_handle = Some(self.samples.len() - 1);
                 }
 
-                self.sample_handle = Some(self.samples.len() - 1);
                 Task::none()
             }
             Message::RequestReferencePath => {
@@ -377,14 +1254,72 @@ impl App {
                 })
             }
             Message::LoadRefereceFile(handle) => {
+                self.load_reference(handle.path());
+
+                Task::none()
+            }
+            Message::RequestProjectPath => {
+                let task = rfd::AsyncFileDialog::new()
+                    .add_filter("project", &[project::EXTENSION])
+                    .add_filter("any", &["*"])
+                    .pick_file();
+
+                Task::perform(task, |maybe_handle| match maybe_handle {
+                    Some(handle) => Message::OpenProject(handle),
+                    None => Message::None,
+                })
+            }
+            Message::OpenProject(handle) => {
                 let path = handle.path();
-                let reference = Reference::parse_file(&path);
-                self.lipid_reference = Rc::from(reference.as_slice());
-                self.exporter
-                    .set_lipid_references(Rc::clone(&self.lipid_reference));
+                match Project::open(&path) {
+                    Ok(project) => {
+                        let root = path.parent().unwrap_or(Path::new(""));
+                        self.apply_project(&project, root);
+                    }
+                    Err(err) => {
+                        self.project_warnings = vec![err];
+                    }
+                }
 
-                for sample in self.samples.iter_mut() {
-                    sample.set_lipid_references(Rc::clone(&self.lipid_reference));
+                Task::none()
+            }
+            Message::RequestProjectTarget => {
+                let task = rfd::AsyncFileDialog::new()
+                    .add_filter("project", &[project::EXTENSION])
+                    .set_file_name(format!("analysis.{}", project::EXTENSION))
+                    .save_file();
+
+                Task::perform(task, |maybe_handle| match maybe_handle {
+                    Some(handle) => Message::SaveProject(handle),
+                    None => Message::None,
+                })
+            }
+            Message::SaveProject(handle) => {
+                let path = handle.path();
+                let root = path.parent().unwrap_or(Path::new(""));
+                let result = self
+                    .to_project(root)
+                    .and_then(|project| project.save(&path));
+
+                if let Err(err) = result {
+                    self.project_warnings = vec![err];
+                }
+
+                Task::none()
+            }
+            Message::SaveConfig => {
+                let config = Config {
+                    parameters: self.get_parameters(),
+                    export: self.exporter.get_settings().clone(),
+                    import_profiles: self.importer.get_profiles().to_vec(),
+                };
+
+                match config.save() {
+                    Ok(path) => self.status = Some(format!("Saved defaults to {}", path.display())),
+                    Err(err) => {
+                        self.project_warnings = vec![err];
+                        self.status = None;
+                    }
                 }
 
                 Task::none()
@@ -415,81 +1350,392 @@ impl App {
 
                 Task::none()
             }
-            Message::HeightRequirement(message) => {
-                if let Some(value) = self.height_requirement.update(message) {
-                    for sample in self.samples.iter_mut() {
-                        sample.set_height_requirement(&value);
-                    }
+            Message::SmoothingMethodSelect(method) => {
+                self.smoothing_method = method;
+                let smoothing = self.get_smoothing();
+                for sample in self.samples.iter_mut() {
+                    sample.set_smoothing(&smoothing);
                 }
 
                 Task::none()
             }
-            Message::InflectionRequirement(message) => {
-                if let Some(value) = self.inflection_requirement.update(message) {
+            Message::SmoothingRadius(message) => {
+                if self.smoothing_radius.update(message).is_some() {
+                    let smoothing = self.get_smoothing();
                     for sample in self.samples.iter_mut() {
-                        sample.set_inflection_requirement(&value);
+                        sample.set_smoothing(&smoothing);
                     }
                 }
 
                 Task::none()
             }
-            Message::RetentionTimeTolerance(message) => {
-                if let Some(value) = self.retention_time_tolerance.update(message) {
+            Message::DerivativeMethodSelect(method) => {
+                self.derivative_method = method;
+                let derivatives = self.get_derivatives();
+                for sample in self.samples.iter_mut() {
+                    sample.set_derivatives(&derivatives);
+                }
+
+                Task::none()
+            }
+            Message::DerivativeRadius(message) => {
+                if self.derivative_radius.update(message).is_some() {
+                    let derivatives = self.get_derivatives();
                     for sample in self.samples.iter_mut() {
-                        sample.set_retention_time_tolerance(&value);
+                        sample.set_derivatives(&derivatives);
                     }
                 }
 
                 Task::none()
             }
-            Message::GlucoseUnitTolerance(message) => {
-                if let Some(value) = self.glucose_unit_tolerance.update(message) {
+            Message::DerivativeOrder(message) => {
+                if self.derivative_order.update(message).is_some() {
+                    let derivatives = self.get_derivatives();
                     for sample in self.samples.iter_mut() {
-                        sample.set_glucose_unit_tolerance(&value);
+                        sample.set_derivatives(&derivatives);
                     }
                 }
 
                 Task::none()
             }
-            Message::ShowUnknowns(show) => {
-                self.include_unknowns = show;
-                for sample in self.samples.iter_mut() {
-                    sample.set_include_unknowns(&show);
+            Message::BaselineMethodSelect(method) => {
+                self.baseline_method = method;
+                self.update_baseline_settings();
+
+                Task::none()
+            }
+            Message::BaselineSmoothness(message) => {
+                if self.baseline_smoothness.update(message).is_some() {
+                    self.update_baseline_settings();
                 }
 
                 Task::none()
             }
-            Message::ZoomX(zoom) => {
-                if let Some(value) = self.zoom_x.update(zoom) {
-                    let point = Point::new(value, self.zoom_y.get_value());
-                    for sample in self.samples.iter_mut() {
-                        sample.set_global_zoom(&point);
-                    }
+            Message::BaselineAsymmetry(message) => {
+                if self.baseline_asymmetry.update(message).is_some() {
+                    self.update_baseline_settings();
                 }
 
                 Task::none()
             }
-            Message::ZoomY(zoom) => {
-                if let Some(value) = self.zoom_y.update(zoom) {
-                    let point = Point::new(self.zoom_x.get_value(), value);
-                    for sample in self.samples.iter_mut() {
-                        sample.set_global_zoom(&point);
-                    }
+            Message::BaselineIterations(message) => {
+                if self.baseline_iterations.update(message).is_some() {
+                    self.update_baseline_settings();
                 }
 
                 Task::none()
             }
-            Message::SampleTypeSelect(sample_type) => {
-                if let Some(handle) = self.sample_handle {
-                    match self.samples[handle].get_sample_type() {
-                        SampleType::Data => (),
-                        SampleType::Blank => {
-                            self.blank_handle = None;
-                        }
-                        SampleType::Dex => {
-                            for sample in self.samples.iter_mut() {
-                                sample.set_glucose_transformer(&None);
-                            }
+            Message::BaselineRadius(message) => {
+                if self.baseline_radius.update(message).is_some() {
+                    self.update_baseline_settings();
+                }
+
+                Task::none()
+            }
+            Message::IntegrationSelect(mode) => {
+                // New samples start with the last selected mode
+                self.integration_mode = mode;
+                if let Some(handle) = self.sample_handle {
+                    self.samples[handle].set_integration_mode(&mode);
+                }
+
+                Task::none()
+            }
+            Message::IntegrationApplyAll => {
+                for sample in self.samples.iter_mut() {
+                    sample.set_integration_mode(&self.integration_mode);
+                }
+
+                Task::none()
+            }
+            Message::SkimRatio(message) => {
+                if let Some(value) = self.skim_ratio.update(message) {
+                    for sample in self.samples.iter_mut() {
+                        sample.set_skim_ratio(&value);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::AddIntegrationWindow => {
+                let window = IntegrationWindow {
+                    start: self.chart_start.get_value(),
+                    end: self.chart_end.get_value(),
+                    mode: self.integration_mode,
+                };
+
+                self.integration_windows.push(window);
+                self.integration_window_strs = window_strs(&self.integration_windows);
+                self.update_integration_windows();
+
+                Task::none()
+            }
+            Message::RemoveIntegrationWindow(index) => {
+                if index < self.integration_windows.len() {
+                    self.integration_windows.remove(index);
+                    self.integration_window_strs.remove(index);
+                    self.update_integration_windows();
+                }
+
+                Task::none()
+            }
+            Message::IntegrationWindowStart(index, input) => {
+                let (Some(value), Some(window), Some(strs)) = (
+                    parse_decimal_input(&input),
+                    self.integration_windows.get_mut(index),
+                    self.integration_window_strs.get_mut(index),
+                ) else {
+                    return Task::none();
+                };
+
+                strs.0 = input;
+                if let Some(value) = value {
+                    window.start = value;
+                    self.update_integration_windows();
+                }
+
+                Task::none()
+            }
+            Message::IntegrationWindowEnd(index, input) => {
+                let (Some(value), Some(window), Some(strs)) = (
+                    parse_decimal_input(&input),
+                    self.integration_windows.get_mut(index),
+                    self.integration_window_strs.get_mut(index),
+                ) else {
+                    return Task::none();
+                };
+
+                strs.1 = input;
+                if let Some(value) = value {
+                    window.end = value;
+                    self.update_integration_windows();
+                }
+
+                Task::none()
+            }
+            Message::IntegrationWindowMode(index, mode) => {
+                if let Some(window) = self.integration_windows.get_mut(index) {
+                    window.mode = mode;
+                    self.update_integration_windows();
+                }
+
+                Task::none()
+            }
+            Message::PeakModelSelect(model) => {
+                self.peak_model = model;
+                for sample in self.samples.iter_mut() {
+                    sample.set_peak_model(&model);
+                }
+
+                Task::none()
+            }
+            Message::NoiseMethodSelect(method) => {
+                self.noise_method = method;
+                self.update_noise_settings();
+
+                Task::none()
+            }
+            Message::NoiseStart(input) => {
+                let Some(value) = parse_decimal_input(&input) else {
+                    return Task::none();
+                };
+
+                if let Some(value) = value {
+                    self.noise_start = value;
+                    self.update_noise_settings();
+                }
+                self.noise_start_str = input;
+
+                Task::none()
+            }
+            Message::NoiseEnd(input) => {
+                let Some(value) = parse_decimal_input(&input) else {
+                    return Task::none();
+                };
+
+                if let Some(value) = value {
+                    self.noise_end = value;
+                    self.update_noise_settings();
+                }
+                self.noise_end_str = input;
+
+                Task::none()
+            }
+            Message::HeightSignalToNoise(enable) => {
+                self.height_signal_to_noise = enable;
+                for sample in self.samples.iter_mut() {
+                    sample.set_signal_to_noise_threshold(&enable);
+                }
+
+                Task::none()
+            }
+            Message::AddIntegrationEvent => {
+                let event = IntegrationEvent {
+                    kind: EventKind::InhibitIntegration,
+                    start: self.chart_start.get_value(),
+                    end: Some(self.chart_end.get_value()),
+                    value: 0.0,
+                };
+
+                self.integration_events.push(event);
+                self.integration_event_strs = event_strs(&self.integration_events);
+                self.update_integration_events();
+
+                Task::none()
+            }
+            Message::RemoveIntegrationEvent(index) => {
+                if index < self.integration_events.len() {
+                    self.integration_events.remove(index);
+                    self.integration_event_strs.remove(index);
+                    self.update_integration_events();
+                }
+
+                Task::none()
+            }
+            Message::IntegrationEventKind(index, kind) => {
+                if let Some(event) = self.integration_events.get_mut(index) {
+                    event.kind = kind;
+                    self.update_integration_events();
+                }
+
+                Task::none()
+            }
+            Message::IntegrationEventStart(index, input) => {
+                let (Some(value), Some(event), Some(strs)) = (
+                    parse_decimal_input(&input),
+                    self.integration_events.get_mut(index),
+                    self.integration_event_strs.get_mut(index),
+                ) else {
+                    return Task::none();
+                };
+
+                strs.0 = input;
+                if let Some(value) = value {
+                    event.start = value;
+                    self.update_integration_events();
+                }
+
+                Task::none()
+            }
+            Message::IntegrationEventEnd(index, input) => {
+                let (Some(value), Some(event), Some(strs)) = (
+                    parse_decimal_input(&input),
+                    self.integration_events.get_mut(index),
+                    self.integration_event_strs.get_mut(index),
+                ) else {
+                    return Task::none();
+                };
+
+                // An empty end lasts until the end of the data
+                let empty = input.is_empty();
+                strs.1 = input;
+                if empty {
+                    event.end = None;
+                    self.update_integration_events();
+                } else if let Some(value) = value {
+                    event.end = Some(value);
+                    self.update_integration_events();
+                }
+
+                Task::none()
+            }
+            Message::IntegrationEventValue(index, input) => {
+                let (Some(value), Some(event), Some(strs)) = (
+                    parse_decimal_input(&input),
+                    self.integration_events.get_mut(index),
+                    self.integration_event_strs.get_mut(index),
+                ) else {
+                    return Task::none();
+                };
+
+                strs.2 = input;
+                if let Some(value) = value {
+                    event.value = value;
+                    self.update_integration_events();
+                }
+
+                Task::none()
+            }
+            Message::HeightRequirement(message) => {
+                if let Some(value) = self.height_requirement.update(message) {
+                    for sample in self.samples.iter_mut() {
+                        sample.set_height_requirement(&value);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::InflectionRequirement(message) => {
+                if let Some(value) = self.inflection_requirement.update(message) {
+                    for sample in self.samples.iter_mut() {
+                        sample.set_inflection_requirement(&value);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::RetentionTimeTolerance(message) => {
+                if let Some(value) = self.retention_time_tolerance.update(message) {
+                    for sample in self.samples.iter_mut() {
+                        sample.set_retention_time_tolerance(&value);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::GlucoseUnitTolerance(message) => {
+                if let Some(value) = self.glucose_unit_tolerance.update(message) {
+                    for sample in self.samples.iter_mut() {
+                        sample.set_glucose_unit_tolerance(&value);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::ShowUnknowns(show) => {
+                self.include_unknowns = show;
+                for sample in self.samples.iter_mut() {
+                    sample.set_include_unknowns(&show);
+                }
+
+                Task::none()
+            }
+            Message::SubtractBlank(subtract) => {
+                self.subtract_blank = subtract;
+                for sample in self.samples.iter_mut() {
+                    sample.set_subtract_blank(&subtract);
+                }
+
+                Task::none()
+            }
+            Message::ZoomX(zoom) => {
+                if let Some(value) = self.zoom_x.update(zoom) {
+                    let point = Point::new(value, self.zoom_y.get_value());
+                    self.exporter.set_global_zoom(point);
+                }
+
+                Task::none()
+            }
+            Message::ZoomY(zoom) => {
+                if let Some(value) = self.zoom_y.update(zoom) {
+                    let point = Point::new(self.zoom_x.get_value(), value);
+                    self.exporter.set_global_zoom(point);
+                }
+
+                Task::none()
+            }
+            Message::SampleTypeSelect(sample_type) => {
+                if let Some(handle) = self.sample_handle {
+                    match self.samples[handle].get_sample_type() {
+                        SampleType::Data => (),
+                        SampleType::Blank => {
+                            self.blank_handle = None;
+                            self.update_blank();
+                        }
+                        SampleType::Dex => {
+                            for sample in self.samples.iter_mut() {
+                                sample.set_glucose_transformer(&None);
+                            }
 
                             self.dex_handle = None;
                         }
@@ -504,6 +1750,7 @@ impl App {
                         SampleType::Data => (),
                         SampleType::Blank => {
                             self.blank_handle = Some(handle);
+                            self.update_blank();
                         }
                         SampleType::Dex => {
                             self.dex_handle = Some(handle);
@@ -516,18 +1763,17 @@ impl App {
                             }
                         }
                         SampleType::Standard => {
-                            let area = self.samples[handle].get_unqualified_components()[0].area;
-                            self.standard_handle = Some(handle);
-                            self.concentration_multiplier = Some(
-                                1000.0
-                                    * (1.0 / self.injected_volume)
-                                    * self.sample_dilution
-                                    * 0.0025
-                                    * (1.0 / area),
-                            );
+                            // A standard without peaks, such as on another channel, gives no concentration
+                            let peaks = self.samples[handle].get_unqualified_components();
+                            self.standard_handle = (!peaks.is_empty()).then_some(handle);
+                            self.concentration_multiplier = self.samples[handle]
+                                .get_concentration_multiplier(
+                                    self.injected_volume,
+                                    self.sample_dilution,
+                                );
 
                             self.exporter.set_concentration_multiplier(
-                                Some(area),
+                                self.standard_area(),
                                 self.concentration_multiplier,
                             );
                         }
@@ -537,56 +1783,46 @@ impl App {
                 Task::none()
             }
             Message::InjectedVolume(input) => {
-                for character in input.chars() {
-                    if !character.is_ascii_digit() && character != '.' {
-                        return Task::none();
-                    }
-                }
+                let Some(value) = parse_decimal_input(&input) else {
+                    return Task::none();
+                };
 
-                if let Ok(value) = input.parse::<f64>() {
+                if let Some(value) = value {
                     self.injected_volume = value;
-                    self.concentration_multiplier = self.standard_handle.map(|handle| {
-                        1000.0
-                            * (1.0 / self.injected_volume)
-                            * self.sample_dilution
-                            * 0.0025
-                            * (1.0 / self.samples[handle].get_unqualified_components()[0].area)
+                    self.concentration_multiplier = self.standard_handle.and_then(|handle| {
+                        self.samples[handle].get_concentration_multiplier(
+                            self.injected_volume,
+                            self.sample_dilution,
+                        )
                     });
 
-                    let area = self
-                        .standard_handle
-                        .map(|handle| self.samples[handle].get_unqualified_components()[0].area);
-
-                    self.exporter
-                        .set_concentration_multiplier(area, self.concentration_multiplier);
+                    self.exporter.set_concentration_multiplier(
+                        self.standard_area(),
+                        self.concentration_multiplier,
+                    );
                 }
                 self.injected_volume_str = input;
 
                 Task::none()
             }
             Message::SampleDilution(input) => {
-                for character in input.chars() {
-                    if !character.is_ascii_digit() && character != '.' {
-                        return Task::none();
-                    }
-                }
+                let Some(value) = parse_decimal_input(&input) else {
+                    return Task::none();
+                };
 
-                if let Ok(value) = input.parse::<f64>() {
+                if let Some(value) = value {
                     self.sample_dilution = value;
-                    self.concentration_multiplier = self.standard_handle.map(|handle| {
-                        1000.0
-                            * (1.0 / self.injected_volume)
-                            * self.sample_dilution
-                            * 0.0025
-                            * (1.0 / self.samples[handle].get_unqualified_components()[0].area)
+                    self.concentration_multiplier = self.standard_handle.and_then(|handle| {
+                        self.samples[handle].get_concentration_multiplier(
+                            self.injected_volume,
+                            self.sample_dilution,
+                        )
                     });
 
-                    let area = self
-                        .standard_handle
-                        .map(|handle| self.samples[handle].get_unqualified_components()[0].area);
-
-                    self.exporter
-                        .set_concentration_multiplier(area, self.concentration_multiplier);
+                    self.exporter.set_concentration_multiplier(
+                        self.standard_area(),
+                        self.concentration_multiplier,
+                    );
                 }
                 self.sample_dilution_str = input;
 
@@ -594,6 +1830,165 @@ impl App {
             }
             Message::TabSwitch(tab) => {
                 self.sample_handle = Some(tab);
+                self.selected_peak = None;
+
+                Task::none()
+            }
+            Message::EditModeSelect(mode) => {
+                self.edit_mode = mode;
+
+                Task::none()
+            }
+            Message::ClearAnchors => {
+                if let Some(handle) = self.sample_handle {
+                    self.samples[handle].set_baseline_anchors(&[]);
+                }
+
+                Task::none()
+            }
+            Message::PeakToolSelect(tool) => {
+                self.peak_tool = tool;
+
+                Task::none()
+            }
+            Message::SampleOrderSelect(order) => {
+                self.sample_order = order;
+
+                Task::none()
+            }
+            Message::ChannelSelect(name) => {
+                if let Some(handle) = self.sample_handle {
+                    let sample = &mut self.samples[handle];
+                    let index = sample
+                        .get_channels()
+                        .iter()
+                        .position(|channel| channel.name == name);
+
+                    if let Some(index) = index {
+                        sample.set_channel(&index);
+                        self.selected_peak = None;
+                        self.refresh_sample_type(handle);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::ChannelApplyAll => {
+                if let Some(handle) = self.sample_handle {
+                    let index = self.samples[handle].get_channel();
+                    for sample in self.samples.iter_mut() {
+                        sample.set_channel(&index);
+                    }
+
+                    // Every blank, dex and standard, even a standard left without peaks before
+                    for handle in 0..self.samples.len() {
+                        self.refresh_sample_type(handle);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::OverlayChannels(overlay) => {
+                self.overlay_channels = overlay;
+
+                Task::none()
+            }
+            Message::ClearPeakEdits => {
+                if let Some(handle) = self.sample_handle {
+                    self.samples[handle].set_peak_edits(&[]);
+                }
+
+                Task::none()
+            }
+            Message::SelectPeak(time) => {
+                self.selected_peak = Some(time);
+
+                Task::none()
+            }
+            Message::AssignLabel(name) => {
+                if let (Some(handle), Some(time)) = (self.sample_handle, self.selected_peak) {
+                    self.samples[handle].set_manual_label(&time, Some(name));
+                }
+
+                Task::none()
+            }
+            Message::MarkUnknown => {
+                if let (Some(handle), Some(time)) = (self.sample_handle, self.selected_peak) {
+                    self.samples[handle].set_manual_label(&time, None);
+                }
+
+                Task::none()
+            }
+            Message::LockLabel => {
+                if let (Some(handle), Some(time)) = (self.sample_handle, self.selected_peak) {
+                    let sample = &mut self.samples[handle];
+                    let name = match sample.get_component_at(time) {
+                        Some(Component::Located(_, reference)) => reference.name.clone(),
+                        _ => None,
+                    };
+
+                    if name.is_some() {
+                        sample.set_manual_label(&time, name);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::ClearLabel => {
+                if let (Some(handle), Some(time)) = (self.sample_handle, self.selected_peak) {
+                    self.samples[handle].clear_manual_label(&time);
+                }
+
+                Task::none()
+            }
+            Message::ChartMessage(message) => {
+                let Some(handle) = self.sample_handle else {
+                    return Task::none();
+                };
+
+                let mut anchors = self.samples[handle].get_baseline_anchors().to_vec();
+                match message {
+                    chromatogram::Message::AddAnchor(point) => anchors.push(point),
+                    chromatogram::Message::MoveAnchor(index, point) => {
+                        if let Some(anchor) = anchors.get_mut(index) {
+                            *anchor = point;
+                        }
+                    }
+                    chromatogram::Message::RemoveAnchor(index) => {
+                        if index < anchors.len() {
+                            anchors.remove(index);
+                        }
+                    }
+                    chromatogram::Message::ReleaseAnchor => return Task::none(),
+                    chromatogram::Message::SelectPeak(time) => {
+                        return self.apply(Message::SelectPeak(time));
+                    }
+                    chromatogram::Message::EditPeak(edit) => {
+                        let sample = &mut self.samples[handle];
+
+                        // Edits which miss every peak would otherwise wait for one to appear
+                        let has_peak = |time: f64| sample.get_component_at(time).is_some();
+                        let applies = match edit {
+                            PeakEdit::Add { .. } => true,
+                            PeakEdit::Delete { time }
+                            | PeakEdit::Split { time }
+                            | PeakEdit::Bounds { time, .. } => has_peak(time),
+                            PeakEdit::Merge { first, second } => {
+                                has_peak(first) && has_peak(second)
+                            }
+                        };
+
+                        if applies {
+                            let mut edits = sample.get_peak_edits().to_vec();
+                            edits.push(edit);
+                            sample.set_peak_edits(&edits);
+                        }
+
+                        return Task::none();
+                    }
+                }
+
+                self.samples[handle].set_baseline_anchors(&anchors);
 
                 Task::none()
             }
@@ -601,21 +1996,446 @@ impl App {
                 .exporter
                 .update(msg, &self.samples)
                 .map(Message::ExporterMessage),
+            Message::ImporterMessage(msg) => {
+                if let importer::Message::Import = msg
+                    && let Some((path, profile)) = self.importer.get_import()
+                {
+                    let (path, profile) = (path.to_path_buf(), profile.clone());
+                    if self.load_sample(&path, Some(&profile)) {
+                        self.sample_handle = Some(self.samples.len() - 1);
+                    }
+                }
+
+                self.importer.update(msg).map(Message::ImporterMessage)
+            }
         }
     }
 
+    /// Loads a sample with the current settings, read with `profile` if given or else detected.
+    fn load_sample(&mut self, path: &Path, profile: Option<&ImportProfile>) -> bool {
+        let sample = match profile {
+            Some(profile) => Chromatography::from_delimited_file(&path, profile),
+            None => Chromatography::from_file(&path),
+        };
+
+        let mut sample = match sample {
+            Ok(value) => value,
+            Err(err) => {
+                self.project_warnings
+                    .push(format!("Could not load {}: {}", path.display(), err));
+                return false;
+            }
+        };
+
+        let range = self.chart_start.get_value()..self.chart_end.get_value();
+        sample.set_smoothing(&self.get_smoothing());
+        sample.set_derivatives(&self.get_derivatives());
+        sample.set_baseline_settings(&self.get_baseline_settings());
+        sample.set_integration_mode(&self.integration_mode);
+        sample.set_integration_windows(&self.integration_windows);
+        sample.set_skim_ratio(&self.skim_ratio.get_value());
+        sample.set_integration_events(&self.integration_events);
+        sample.set_data_range(&range);
+        sample.set_lipid_references(Rc::clone(&self.lipid_reference));
+        sample.set_include_unknowns(&self.include_unknowns);
+        sample.set_height_requirement(&self.height_requirement.get_value());
+        sample.set_inflection_requirement(&self.inflection_requirement.get_value());
+        sample.set_retention_time_tolerance(&self.retention_time_tolerance.get_value());
+        sample.set_glucose_unit_tolerance(&self.glucose_unit_tolerance.get_value());
+        sample.set_glucose_transformer(&self.glucose_transformer);
+        sample.set_subtract_blank(&self.subtract_blank);
+        sample.set_blank(self.get_blank());
+        sample.set_noise_settings(&self.get_noise_settings());
+        sample.set_signal_to_noise_threshold(&self.height_signal_to_noise);
+        sample.set_peak_model(&self.peak_model);
+        self.samples.push(sample);
+
+        true
+    }
+
+    fn load_reference(&mut self, path: &Path) {
+        let reference = match Reference::parse_file(&path) {
+            Ok(value) => value,
+            Err(err) => {
+                self.project_warnings
+                    .push(format!("Could not load {}: {}", path.display(), err));
+                return;
+            }
+        };
+
+        self.lipid_reference = Rc::from(reference.as_slice());
+        self.reference_path = Some(path.to_path_buf());
+        self.exporter
+            .set_lipid_references(Rc::clone(&self.lipid_reference));
+
+        for sample in self.samples.iter_mut() {
+            sample.set_lipid_references(Rc::clone(&self.lipid_reference));
+        }
+    }
+
+    fn get_smoothing(&self) -> Smoothing {
+        Smoothing {
+            method: self.smoothing_method,
+            radius: self.smoothing_radius.get_value().round().max(0.0) as usize,
+        }
+    }
+
+    fn get_derivatives(&self) -> Derivatives {
+        Derivatives {
+            method: self.derivative_method,
+            radius: self.derivative_radius.get_value().round().max(0.0) as usize,
+            order: self.derivative_order.get_value().round().max(0.0) as usize,
+        }
+    }
+
+    fn get_baseline_settings(&self) -> BaselineSettings {
+        BaselineSettings {
+            method: self.baseline_method,
+            lambda: 10f64.powf(self.baseline_smoothness.get_value()),
+            asymmetry: self.baseline_asymmetry.get_value(),
+            iterations: self.baseline_iterations.get_value().round().max(0.0) as usize,
+            radius: self.baseline_radius.get_value().round().max(0.0) as usize,
+        }
+    }
+
+    fn update_baseline_settings(&mut self) {
+        let settings = self.get_baseline_settings();
+        for sample in self.samples.iter_mut() {
+            sample.set_baseline_settings(&settings);
+        }
+    }
+
+    fn get_noise_settings(&self) -> NoiseSettings {
+        NoiseSettings {
+            method: self.noise_method,
+            start: self.noise_start,
+            end: self.noise_end,
+        }
+    }
+
+    fn update_noise_settings(&mut self) {
+        let settings = self.get_noise_settings();
+        for sample in self.samples.iter_mut() {
+            sample.set_noise_settings(&settings);
+        }
+    }
+
+    fn update_integration_windows(&mut self) {
+        for sample in self.samples.iter_mut() {
+            sample.set_integration_windows(&self.integration_windows);
+        }
+    }
+
+    fn update_integration_events(&mut self) {
+        for sample in self.samples.iter_mut() {
+            sample.set_integration_events(&self.integration_events);
+        }
+    }
+
+    fn get_blank(&self) -> Option<Rc<[Point2D]>> {
+        self.blank_handle
+            .map(|handle| Rc::from(self.samples[handle].get_raw_data()))
+    }
+
+    fn update_blank(&mut self) {
+        let blank = self.get_blank();
+        for (i, sample) in self.samples.iter_mut().enumerate() {
+            if Some(i) == self.blank_handle {
+                sample.set_blank(None);
+            } else {
+                sample.set_blank(blank.clone());
+            }
+        }
+    }
+
+    fn get_parameters(&self) -> Parameters {
+        Parameters {
+            include_unknowns: self.include_unknowns,
+            subtract_blank: self.subtract_blank,
+            injected_volume: self.injected_volume,
+            sample_dilution: self.sample_dilution,
+            chart_start: self.chart_start.get_state(),
+            chart_end: self.chart_end.get_state(),
+            smoothing: self.smoothing_method,
+            smoothing_radius: self.smoothing_radius.get_state(),
+            derivatives: self.derivative_method,
+            derivative_radius: self.derivative_radius.get_state(),
+            derivative_order: self.derivative_order.get_state(),
+            baseline: self.baseline_method,
+            baseline_smoothness: self.baseline_smoothness.get_state(),
+            baseline_asymmetry: self.baseline_asymmetry.get_state(),
+            baseline_iterations: self.baseline_iterations.get_state(),
+            baseline_radius: self.baseline_radius.get_state(),
+            integration: self.integration_mode,
+            integration_windows: self.integration_windows.clone(),
+            integration_events: self.integration_events.clone(),
+            peak_model: self.peak_model,
+            noise: self.noise_method,
+            noise_start: self.noise_start,
+            noise_end: self.noise_end,
+            height_signal_to_noise: self.height_signal_to_noise,
+            skim_ratio: self.skim_ratio.get_state(),
+            height_requirement: self.height_requirement.get_state(),
+            inflection_requirement: self.inflection_requirement.get_state(),
+            retention_time_tolerance: self.retention_time_tolerance.get_state(),
+            glucose_unit_tolerance: self.glucose_unit_tolerance.get_state(),
+            zoom_x: self.zoom_x.get_state(),
+            zoom_y: self.zoom_y.get_state(),
+        }
+    }
+
+    fn to_project(&self, root: &Path) -> Result<Project, String> {
+        let parameters = self.get_parameters();
+
+        let reference = match &self.reference_path {
+            Some(path) => Some(FileRecord::new(path, root)?),
+            None => None,
+        };
+
+        let mut samples = Vec::with_capacity(self.samples.len());
+        for sample in &self.samples {
+            samples.push(SampleRecord {
+                file: FileRecord::new(&sample.file_path, root)?,
+                sample_type: sample.get_sample_type(),
+                baseline_anchors: sample.get_baseline_anchors().to_vec(),
+                integration: sample.get_integration_mode(),
+                label_overrides: sample.get_label_overrides().to_vec(),
+                peak_edits: sample.get_peak_edits().to_vec(),
+                channel: sample.get_channel(),
+                import_profile: sample.get_import_profile().cloned(),
+            });
+        }
+
+        Ok(Project::new(
+            parameters,
+            self.exporter.get_settings().clone(),
+            reference,
+            samples,
+        ))
+    }
+
+    /// Sets every option and slider, samples are left untouched.
+    fn apply_parameters(&mut self, parameters: &Parameters) {
+        self.chart_start.set_state(&parameters.chart_start);
+        self.chart_end.set_state(&parameters.chart_end);
+        self.smoothing_method = parameters.smoothing;
+        self.smoothing_radius
+            .set_state(&parameters.smoothing_radius);
+        self.derivative_method = parameters.derivatives;
+        self.derivative_radius
+            .set_state(&parameters.derivative_radius);
+        self.derivative_order
+            .set_state(&parameters.derivative_order);
+        self.baseline_method = parameters.baseline;
+        self.baseline_smoothness
+            .set_state(&parameters.baseline_smoothness);
+        self.baseline_asymmetry
+            .set_state(&parameters.baseline_asymmetry);
+        self.baseline_iterations
+            .set_state(&parameters.baseline_iterations);
+        self.baseline_radius.set_state(&parameters.baseline_radius);
+        self.integration_mode = parameters.integration;
+        self.integration_windows = parameters.integration_windows.clone();
+        self.integration_window_strs = window_strs(&self.integration_windows);
+        self.integration_events = parameters.integration_events.clone();
+        self.integration_event_strs = event_strs(&self.integration_events);
+        self.peak_model = parameters.peak_model;
+        self.noise_method = parameters.noise;
+        self.noise_start = parameters.noise_start;
+        self.noise_start_str = parameters.noise_start.to_string();
+        self.noise_end = parameters.noise_end;
+        self.noise_end_str = parameters.noise_end.to_string();
+        self.height_signal_to_noise = parameters.height_signal_to_noise;
+        self.skim_ratio.set_state(&parameters.skim_ratio);
+        self.height_requirement
+            .set_state(&parameters.height_requirement);
+        self.inflection_requirement
+            .set_state(&parameters.inflection_requirement);
+        self.retention_time_tolerance
+            .set_state(&parameters.retention_time_tolerance);
+        self.glucose_unit_tolerance
+            .set_state(&parameters.glucose_unit_tolerance);
+        self.zoom_x.set_state(&parameters.zoom_x);
+        self.zoom_y.set_state(&parameters.zoom_y);
+        self.include_unknowns = parameters.include_unknowns;
+        self.subtract_blank = parameters.subtract_blank;
+        self.injected_volume = parameters.injected_volume;
+        self.injected_volume_str = parameters.injected_volume.to_string();
+        self.sample_dilution = parameters.sample_dilution;
+        self.sample_dilution_str = parameters.sample_dilution.to_string();
+    }
+
+    fn apply_project(&mut self, project: &Project, root: &Path) {
+        self.project_warnings.clear();
+
+        self.apply_parameters(&project.parameters);
+
+        self.exporter.set_settings(project.export.clone());
+        self.exporter
+            .set_global_zoom(Point::new(self.zoom_x.get_value(), self.zoom_y.get_value()));
+
+        // Start from an empty sample set
+        self.samples.clear();
+        self.sample_handle = None;
+        self.blank_handle = None;
+        self.dex_handle = None;
+        self.standard_handle = None;
+        self.glucose_transformer = None;
+        self.concentration_multiplier = None;
+        self.exporter.set_glucose_spline(None);
+        self.exporter.set_concentration_multiplier(None, None);
+
+        if let Some(record) = &project.reference {
+            let (path, warning) = record.resolve(root);
+            self.project_warnings.extend(warning);
+            self.load_reference(&path);
+        } else {
+            self.lipid_reference = Rc::default();
+            self.reference_path = None;
+            self.exporter
+                .set_lipid_references(Rc::clone(&self.lipid_reference));
+        }
+
+        let mut sample_types = vec![];
+        for record in &project.samples {
+            let (path, warning) = record.file.resolve(root);
+            self.project_warnings.extend(warning);
+
+            if self.load_sample(&path, record.import_profile.as_ref()) {
+                let handle = self.samples.len() - 1;
+                self.samples[handle].set_channel(&record.channel);
+                self.samples[handle].set_baseline_anchors(&record.baseline_anchors);
+                self.samples[handle].set_integration_mode(&record.integration);
+                self.samples[handle].set_peak_edits(&record.peak_edits);
+                self.samples[handle].set_label_overrides(&record.label_overrides);
+                sample_types.push((handle, record.sample_type));
+            }
+        }
+
+        for (handle, sample_type) in sample_types {
+            if sample_type != SampleType::Data {
+                self.sample_handle = Some(handle);
+                let _ = self.apply(Message::SampleTypeSelect(sample_type));
+            }
+        }
+
+        self.sample_handle = self.samples.len().checked_sub(1);
+    }
+
     pub fn subscription(&self) -> Subscription<Message> {
-        events().map(|(id, event)| {
+        let shortcuts = keyboard::on_key_press(|key, modifiers| match key.as_ref() {
+            keyboard::Key::Character(character)
+                if modifiers.command() && character.eq_ignore_ascii_case("z") =>
+            {
+                if modifiers.shift() {
+                    Some(Message::Redo)
+                } else {
+                    Some(Message::Undo)
+                }
+            }
+            _ => None,
+        });
+
+        let window_events = events().map(|(id, event)| {
             match event {
                 //TODO: difference?
                 window::Event::Closed => Message::CloseWindow(id),
                 window::Event::CloseRequested => Message::CloseWindow(id),
-                // TODO: use
-                window::Event::FileHovered(_) => Message::None,
-                window::Event::FileDropped(_) => Message::None,
-                window::Event::FilesHoveredLeft => Message::None,
+                window::Event::FileHovered(path) => Message::FileHovered(path),
+                window::Event::FileDropped(path) => Message::FileDropped(path),
+                window::Event::FilesHoveredLeft => Message::FilesHoveredLeft,
                 _ => Message::None,
             }
+        });
+
+        Subscription::batch([shortcuts, window_events])
+    }
+}
+
+/// Whether the file has the extension of a data file that can be loaded as a sample.
+fn is_data_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            let extension = extension.to_ascii_lowercase();
+            ["arw", "csv", "tsv", "txt", "asc", "cdf"].contains(&extension.as_str())
         })
+}
+
+/// Messages sent many times for one change, such as dragging or typing.
+fn is_continuous(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::ChartStart(_)
+            | Message::ChartEnd(_)
+            | Message::SmoothingRadius(_)
+            | Message::DerivativeRadius(_)
+            | Message::DerivativeOrder(_)
+            | Message::BaselineSmoothness(_)
+            | Message::BaselineAsymmetry(_)
+            | Message::BaselineIterations(_)
+            | Message::BaselineRadius(_)
+            | Message::SkimRatio(_)
+            | Message::IntegrationWindowStart(..)
+            | Message::IntegrationWindowEnd(..)
+            | Message::NoiseStart(_)
+            | Message::NoiseEnd(_)
+            | Message::IntegrationEventStart(..)
+            | Message::IntegrationEventEnd(..)
+            | Message::IntegrationEventValue(..)
+            | Message::HeightRequirement(_)
+            | Message::InflectionRequirement(_)
+            | Message::RetentionTimeTolerance(_)
+            | Message::GlucoseUnitTolerance(_)
+            | Message::InjectedVolume(_)
+            | Message::SampleDilution(_)
+            | Message::ChartMessage(chromatogram::Message::MoveAnchor(..))
+    )
+}
+
+/// Whether a slider message changes its value or range, expanding it does not.
+fn changes(message: &SliderMessage) -> bool {
+    !matches!(
+        message,
+        SliderMessage::Invalid | SliderMessage::Expanded(_) | SliderMessage::Released
+    )
+}
+
+/// Whether text typed into a decimal field is accepted, see [`parse_decimal_input`].
+fn is_decimal(input: &str) -> bool {
+    parse_decimal_input(input).is_some()
+}
+
+/// Value of text typed into a decimal field, `None` rejects the text.
+///
+/// Only digits and a decimal point may be typed,
+/// text which is not a number yet (e.g. empty) is kept without a value.
+fn parse_decimal_input(input: &str) -> Option<Option<f64>> {
+    if input
+        .chars()
+        .any(|character| !character.is_ascii_digit() && character != '.')
+    {
+        return None;
     }
+
+    Some(input.parse().ok())
+}
+
+/// Editable text of the start and end of every integration window.
+fn window_strs(windows: &[IntegrationWindow]) -> Vec<(String, String)> {
+    windows
+        .iter()
+        .map(|window| (window.start.to_string(), window.end.to_string()))
+        .collect()
+}
+
+/// Editable text of the start, end and value of every integration event.
+fn event_strs(events: &[IntegrationEvent]) -> Vec<(String, String, String)> {
+    events
+        .iter()
+        .map(|event| {
+            let end = event.end.map(|end| end.to_string()).unwrap_or_default();
+            (event.start.to_string(), end, event.value.to_string())
+        })
+        .collect()
 }
@@ -1,21 +1,166 @@
+use std::cell::RefCell;
+use std::fmt::{self, Display};
+use std::ops::Range;
+
 use iced::event::Status;
 use iced::widget::canvas;
 use iced::{Point, keyboard, mouse};
 use plotters::prelude::*;
 use plotters_iced::Chart;
 
-use crate::chromatography::{Chromatography, ComponentFilter};
-use crate::component::Component;
+use hplc_core::chromatography::{Chromatography, ComponentFilter};
+use hplc_core::component::Component;
+use hplc_core::editing::PeakEdit;
+use hplc_core::vector::{Point2D, Vector2};
+
+// Distance in pixels within which a click hits an anchor
+const HIT_RADIUS: f64 = 8.0;
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    AddAnchor(Point2D),
+    MoveAnchor(usize, Point2D),
+    /// The dragged anchor was let go.
+    ReleaseAnchor,
+    RemoveAnchor(usize),
+    SelectPeak(f64),
+    EditPeak(PeakEdit),
+}
+
+/// What clicking on the chart does, panning and zooming are always available.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EditMode {
+    #[default]
+    View,
+    Baseline,
+    Labels,
+    Peaks,
+}
+
+impl EditMode {
+    pub const ALL: [EditMode; 4] = [
+        EditMode::View,
+        EditMode::Baseline,
+        EditMode::Labels,
+        EditMode::Peaks,
+    ];
+}
+
+impl Display for EditMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EditMode::View => "View",
+            EditMode::Baseline => "Baseline Anchors",
+            EditMode::Labels => "Peak Labels",
+            EditMode::Peaks => "Peaks",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// What clicking on the chart does to peaks while editing them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PeakTool {
+    /// Drag the start or end marker of a peak.
+    #[default]
+    Adjust,
+    /// Drag over a range to add a peak.
+    Add,
+    /// Click a peak to delete it.
+    Delete,
+    /// Click inside a peak to split it there.
+    Split,
+    /// Drag from one peak to another to merge them and every peak between.
+    Merge,
+}
+
+impl PeakTool {
+    pub const ALL: [PeakTool; 5] = [
+        PeakTool::Adjust,
+        PeakTool::Add,
+        PeakTool::Delete,
+        PeakTool::Split,
+        PeakTool::Merge,
+    ];
+}
+
+impl Display for PeakTool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PeakTool::Adjust => "Move Start/End",
+            PeakTool::Add => "Add",
+            PeakTool::Delete => "Delete",
+            PeakTool::Split => "Split",
+            PeakTool::Merge => "Merge",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// A peak boundary being dragged, `fixed` is the other boundary of the peak.
+#[derive(Debug, Clone, Copy)]
+struct DraggedBound {
+    time: f64,
+    fixed: f64,
+}
+
+/// Where the plotting area was last drawn, used to turn cursor positions into data points.
+#[derive(Debug, Clone)]
+struct PlotMapping {
+    pixels_x: Range<i32>,
+    pixels_y: Range<i32>,
+    range_x: Range<f64>,
+    range_y: Range<f64>,
+}
+
+impl PlotMapping {
+    fn to_data(&self, pixel: Point<f64>) -> Point2D {
+        let fraction_x = (pixel.x - self.pixels_x.start as f64)
+            / (self.pixels_x.end - self.pixels_x.start) as f64;
+        let fraction_y = (pixel.y - self.pixels_y.start as f64)
+            / (self.pixels_y.end - self.pixels_y.start) as f64;
+
+        Point2D::new(
+            self.range_x.start + fraction_x * (self.range_x.end - self.range_x.start),
+            self.range_y.end - fraction_y * (self.range_y.end - self.range_y.start),
+        )
+    }
+
+    fn to_pixel(&self, point: &Point2D) -> Point<f64> {
+        let fraction_x = (point.x() - self.range_x.start) / (self.range_x.end - self.range_x.start);
+        let fraction_y = (self.range_y.end - point.y()) / (self.range_y.end - self.range_y.start);
+
+        Point::new(
+            self.pixels_x.start as f64
+                + fraction_x * (self.pixels_x.end - self.pixels_x.start) as f64,
+            self.pixels_y.start as f64
+                + fraction_y * (self.pixels_y.end - self.pixels_y.start) as f64,
+        )
+    }
+}
+
+fn distance(lhs: Point<f64>, rhs: Point<f64>) -> f64 {
+    (lhs.x - rhs.x).hypot(lhs.y - rhs.y)
+}
 
 #[derive(Debug, Clone)]
 pub struct ChromatogramState {
     mouse_inside: bool,
     mouse_pressed: bool,
     mouse_position: Point<f64>,
+    press_position: Point<f64>,
     ctrl_pressed: bool,
     alt_pressed: bool,
     local_zoom: Point<f64>,
     local_offset: Point<f64>,
+    dragged_anchor: Option<usize>,
+    dragged_bound: Option<DraggedBound>,
+    // Time where a range drag started and the time under the cursor
+    selection: Option<f64>,
+    drag_time: Option<f64>,
+    mapping: RefCell<Option<PlotMapping>>,
 }
 
 impl Default for ChromatogramState {
@@ -24,15 +169,145 @@ impl Default for ChromatogramState {
             mouse_inside: false,
             mouse_pressed: false,
             mouse_position: Point::new(0.0, 0.0),
+            press_position: Point::new(0.0, 0.0),
             ctrl_pressed: false,
             alt_pressed: false,
             local_zoom: Point::new(1.0, 1.0),
             local_offset: Point::new(0.0, 0.0),
+            dragged_anchor: None,
+            dragged_bound: None,
+            selection: None,
+            drag_time: None,
+            mapping: RefCell::new(None),
         }
     }
 }
 
-impl Chart<()> for Chromatography {
+/// Rendering of a single sample, the analysis itself lives in [`Chromatography`]
+pub struct Chromatogram<'a> {
+    sample: &'a Chromatography,
+    global_zoom: Point<f64>,
+    edit_mode: EditMode,
+    peak_tool: PeakTool,
+    selected_peak: Option<f64>,
+    overlay: bool,
+}
+
+impl<'a> Chromatogram<'a> {
+    pub fn new(sample: &'a Chromatography, global_zoom: Point<f64>) -> Self {
+        Self {
+            sample,
+            global_zoom,
+            edit_mode: EditMode::View,
+            peak_tool: PeakTool::Adjust,
+            selected_peak: None,
+            overlay: false,
+        }
+    }
+
+    pub fn with_edit_mode(mut self, edit_mode: EditMode) -> Self {
+        self.edit_mode = edit_mode;
+        self
+    }
+
+    pub fn with_peak_tool(mut self, peak_tool: PeakTool) -> Self {
+        self.peak_tool = peak_tool;
+        self
+    }
+
+    /// Highlights the peak containing the given time.
+    pub fn with_selected_peak(mut self, selected_peak: Option<f64>) -> Self {
+        self.selected_peak = selected_peak;
+        self
+    }
+
+    /// Draws the other channels of the sample behind the analysed one.
+    pub fn with_overlay(mut self, overlay: bool) -> Self {
+        self.overlay = overlay;
+        self
+    }
+
+    /// Position of the cursor within the plotting area in data coordinates.
+    fn cursor_point(state: &ChromatogramState, bounds: &iced::Rectangle) -> Option<Point2D> {
+        let mapping = state.mapping.borrow();
+        let pixel = Self::local_position(state.mouse_position, bounds);
+        mapping.as_ref().map(|mapping| mapping.to_data(pixel))
+    }
+
+    fn local_position(position: Point<f64>, bounds: &iced::Rectangle) -> Point<f64> {
+        Point::new(position.x - bounds.x as f64, position.y - bounds.y as f64)
+    }
+
+    /// Index of the anchor under the cursor.
+    fn anchor_at(&self, state: &ChromatogramState, bounds: &iced::Rectangle) -> Option<usize> {
+        let mapping = state.mapping.borrow();
+        let mapping = mapping.as_ref()?;
+        let cursor = Self::local_position(state.mouse_position, bounds);
+
+        self.sample
+            .get_baseline_anchors()
+            .iter()
+            .position(|anchor| distance(mapping.to_pixel(anchor), cursor) <= HIT_RADIUS)
+    }
+
+    /// Start or end marker of the peak under the cursor, the start of a peak wins over the end of the previous one.
+    fn bound_at(
+        &self,
+        state: &ChromatogramState,
+        bounds: &iced::Rectangle,
+    ) -> Option<DraggedBound> {
+        let mapping = state.mapping.borrow();
+        let mapping = mapping.as_ref()?;
+        let cursor = Self::local_position(state.mouse_position, bounds);
+        let hit = |point: &Point2D| distance(mapping.to_pixel(point), cursor) <= HIT_RADIUS;
+
+        self.sample
+            .get_components(&ComponentFilter::EXISTING_ONLY)
+            .iter()
+            .rev()
+            .filter_map(|component| component.get_peak())
+            .find_map(|peak| {
+                let time = peak.retention_point.x();
+                if hit(&peak.start) {
+                    Some(DraggedBound {
+                        time,
+                        fixed: peak.end.x(),
+                    })
+                } else if hit(&peak.end) {
+                    Some(DraggedBound {
+                        time,
+                        fixed: peak.start.x(),
+                    })
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Edit made by releasing the mouse at `time`, `clicked` if it did not move since it was pressed.
+    fn peak_edit(&self, state: &ChromatogramState, time: f64, clicked: bool) -> Option<PeakEdit> {
+        match self.peak_tool {
+            PeakTool::Adjust if !clicked => state.dragged_bound.map(|bound| PeakEdit::Bounds {
+                time: bound.time,
+                start: bound.fixed.min(time),
+                end: bound.fixed.max(time),
+            }),
+            PeakTool::Add if !clicked => state.selection.map(|start| PeakEdit::Add {
+                start: start.min(time),
+                end: start.max(time),
+            }),
+            PeakTool::Merge if !clicked => state.selection.map(|first| PeakEdit::Merge {
+                first,
+                second: time,
+            }),
+            PeakTool::Delete if clicked => Some(PeakEdit::Delete { time }),
+            PeakTool::Split if clicked => Some(PeakEdit::Split { time }),
+            _ => None,
+        }
+    }
+}
+
+impl Chart<Message> for Chromatogram<'_> {
     type State = ChromatogramState;
 
     fn build_chart<DB: plotters::prelude::DrawingBackend>(
@@ -40,7 +315,7 @@ impl Chart<()> for Chromatography {
         state: &Self::State,
         mut builder: plotters::prelude::ChartBuilder<DB>,
     ) {
-        let range = self.get_data_range();
+        let range = self.sample.get_data_range();
         let scaled_range_x = {
             let start = range.start + state.local_offset.x;
             let end = range.end + state.local_offset.x;
@@ -54,8 +329,8 @@ impl Chart<()> for Chromatography {
 
         let scaled_range_y = {
             let min = state.local_offset.y;
-            let max = self.get_highest_point() + state.local_offset.y;
-            let middle = self.get_highest_point() / 2.0 + state.local_offset.y;
+            let max = self.sample.get_highest_point() + state.local_offset.y;
+            let middle = self.sample.get_highest_point() / 2.0 + state.local_offset.y;
 
             let combined_zoom = state.local_zoom.y * (1.0 / self.global_zoom.y);
             let scaled_start = (min - middle) * combined_zoom + middle;
@@ -64,32 +339,131 @@ impl Chart<()> for Chromatography {
         };
 
         let mut chart = builder
-            .caption(&self.title, ("sans-serif", 30).into_font())
+            .caption(&self.sample.title, ("sans-serif", 30).into_font())
             .margin(40)
             .x_label_area_size(40)
             .y_label_area_size(40)
-            .build_cartesian_2d(scaled_range_x, scaled_range_y)
+            .build_cartesian_2d(scaled_range_x.clone(), scaled_range_y.clone())
             .expect("failed to build chart");
 
+        let (pixels_x, pixels_y) = chart.plotting_area().get_pixel_range();
+        *state.mapping.borrow_mut() = Some(PlotMapping {
+            pixels_x,
+            pixels_y,
+            range_x: scaled_range_x,
+            range_y: scaled_range_y.clone(),
+        });
+
         chart
             .configure_mesh()
             .draw()
             .expect("failed to configure chart");
 
-        let data_series = LineSeries::new(self.get_data(), &RED);
+        let data_series = LineSeries::new(self.sample.get_data(), &RED);
         chart
             .draw_series(data_series)
             .expect("failed to draw series")
             .label("data")
             .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
 
-        let baseline_series = LineSeries::new(self.baseline.clone(), &GREEN);
+        let baseline_series = LineSeries::new(self.sample.baseline.clone(), &GREEN);
         chart
             .draw_series(baseline_series)
             .expect("failed to draw series")
             .label("baseline")
             .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &GREEN));
 
+        // Channels are in their own units, so each is scaled to the height of the analysed one
+        if self.overlay && self.sample.get_channels().len() > 1 {
+            let palette = [BLUE, RGBColor(128, 0, 128), RGBColor(255, 140, 0), BLACK];
+            let highest = self.sample.get_highest_point();
+
+            for (i, channel) in self.sample.get_channels().iter().enumerate() {
+                if i == self.sample.get_channel() {
+                    continue;
+                }
+
+                let points: Vec<Point2D> = channel
+                    .data
+                    .iter()
+                    .filter(|point| range.contains(&point.x()))
+                    .copied()
+                    .collect();
+                let top = points.iter().map(|point| point.y()).fold(0.0, f64::max);
+                let scale = if top > 0.0 { highest / top } else { 1.0 };
+
+                let color = palette[i % palette.len()];
+                let series = points
+                    .into_iter()
+                    .map(|point| Point2D::new(point.x(), point.y() * scale));
+                chart
+                    .draw_series(LineSeries::new(series, &color))
+                    .expect("failed to draw series")
+                    .label(format!("{} (x{:.3})", channel.name, scale))
+                    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+            }
+
+            chart
+                .configure_series_labels()
+                .border_style(BLACK)
+                .background_style(WHITE.mix(0.8))
+                .draw()
+                .expect("failed to draw legend");
+        }
+
+        let anchor_style = ShapeStyle {
+            color: RGBAColor(0, 128, 0, 1.0),
+            filled: false,
+            stroke_width: 2,
+        };
+
+        let anchors = self
+            .sample
+            .get_baseline_anchors()
+            .iter()
+            .map(|anchor| Circle::new(*anchor, 5, anchor_style));
+
+        chart.draw_series(anchors).expect("failed to draw series");
+
+        // Baselines of peaks which are not integrated down to the common baseline
+        let peak_baselines = self
+            .sample
+            .get_components(&ComponentFilter::EXISTING_ONLY)
+            .into_iter()
+            .filter_map(|component| match component {
+                Component::Unknown(peak) => Some(peak),
+                Component::Located(peak, _) => Some(peak),
+                Component::Reference(_) => None,
+            })
+            .filter(|peak| {
+                let start = self
+                    .sample
+                    .baseline
+                    .partition_point(|point| point.x() < peak.start.x());
+                let common = &self.sample.baseline[start..];
+                !common.starts_with(&peak.baseline)
+            })
+            .map(|peak| PathElement::new(peak.baseline, &MAGENTA));
+
+        chart
+            .draw_series(peak_baselines)
+            .expect("failed to draw series");
+
+        let fitted_peaks = self
+            .sample
+            .get_components(&ComponentFilter::EXISTING_ONLY)
+            .into_iter()
+            .filter_map(|component| match component {
+                Component::Unknown(peak) => peak.fit,
+                Component::Located(peak, _) => peak.fit,
+                Component::Reference(_) => None,
+            })
+            .map(|fit| PathElement::new(fit.curve, &CYAN));
+
+        chart
+            .draw_series(fitted_peaks)
+            .expect("failed to draw series");
+
         let blue_circle = ShapeStyle {
             color: RGBAColor(0, 0, 255, 1.0),
             filled: true,
@@ -97,6 +471,7 @@ impl Chart<()> for Chromatography {
         };
 
         let start_points = self
+            .sample
             .get_components(&ComponentFilter::EXISTING_ONLY)
             .into_iter()
             .map(|component| match component {
@@ -118,11 +493,12 @@ impl Chart<()> for Chromatography {
         let text_style = ("sans-serif", 10).into_font();
 
         let retention_points = self
+            .sample
             .get_components(&ComponentFilter::EXISTING_ONLY)
             .into_iter()
             .map(|component| {
                 let label = component
-                    .point_label(self.glucose_transformer.as_ref())
+                    .point_label(self.sample.glucose_transformer.as_ref())
                     .unwrap();
 
                 let origin = match component {
@@ -139,6 +515,51 @@ impl Chart<()> for Chromatography {
         chart
             .draw_series(retention_points)
             .expect("failed to draw series");
+
+        if self.edit_mode == EditMode::Peaks {
+            let end_points = self
+                .sample
+                .get_components(&ComponentFilter::EXISTING_ONLY)
+                .into_iter()
+                .filter_map(|component| component.get_peak().map(|peak| peak.end))
+                .map(|end| Circle::new(end, 3, blue_circle));
+
+            chart
+                .draw_series(end_points)
+                .expect("failed to draw series");
+        }
+
+        // Preview of the boundary or range being dragged
+        if let Some(time) = state.drag_time {
+            let (bottom, top) = (scaled_range_y.start, scaled_range_y.end);
+            let from = state
+                .selection
+                .or(state.dragged_bound.map(|bound| bound.fixed));
+
+            if let Some(from) = from {
+                let area = Rectangle::new(
+                    [(from.min(time), bottom), (from.max(time), top)],
+                    BLUE.mix(0.2).filled(),
+                );
+                chart
+                    .draw_series(Some(area))
+                    .expect("failed to draw series");
+            }
+        }
+
+        let selected_style = ShapeStyle {
+            color: RGBAColor(0, 0, 0, 1.0),
+            filled: false,
+            stroke_width: 2,
+        };
+
+        let selected = self
+            .selected_peak
+            .and_then(|time| self.sample.get_component_at(time))
+            .and_then(|component| component.get_peak())
+            .map(|peak| Circle::new(peak.retention_point, 8, selected_style));
+
+        chart.draw_series(selected).expect("failed to draw series");
     }
 
     fn update(
@@ -147,17 +568,70 @@ impl Chart<()> for Chromatography {
         event: canvas::Event,
         bounds: iced::Rectangle,
         _cursor: iced::mouse::Cursor,
-    ) -> (Status, Option<()>) {
+    ) -> (Status, Option<Message>) {
+        let editing_baseline = self.edit_mode == EditMode::Baseline;
+
         if let canvas::Event::Mouse(mouse_ev) = event {
+            let mut message = None;
+
             match mouse_ev {
                 mouse::Event::ButtonPressed(btn) => {
                     if state.mouse_inside && btn == mouse::Button::Left {
                         state.mouse_pressed = true;
+                        state.press_position = state.mouse_position;
+
+                        if editing_baseline {
+                            state.dragged_anchor = self.anchor_at(state, &bounds);
+                        }
+
+                        if self.edit_mode == EditMode::Peaks {
+                            match self.peak_tool {
+                                PeakTool::Adjust => {
+                                    state.dragged_bound = self.bound_at(state, &bounds);
+                                }
+                                PeakTool::Add | PeakTool::Merge => {
+                                    state.selection =
+                                        Self::cursor_point(state, &bounds).map(|point| point.x());
+                                }
+                                PeakTool::Delete | PeakTool::Split => {}
+                            }
+                        }
+                    }
+
+                    if state.mouse_inside && btn == mouse::Button::Right && editing_baseline {
+                        message = self.anchor_at(state, &bounds).map(Message::RemoveAnchor);
                     }
                 }
                 mouse::Event::ButtonReleased(btn) => {
                     if btn == mouse::Button::Left {
+                        let clicked = state.mouse_pressed
+                            && state.dragged_anchor.is_none()
+                            && distance(state.mouse_position, state.press_position) < 3.0;
+
+                        if clicked && editing_baseline {
+                            message = Self::cursor_point(state, &bounds).map(Message::AddAnchor);
+                        }
+
+                        if clicked && self.edit_mode == EditMode::Labels {
+                            message = Self::cursor_point(state, &bounds)
+                                .map(|point| Message::SelectPeak(point.x()));
+                        }
+
+                        if state.mouse_pressed && self.edit_mode == EditMode::Peaks {
+                            message = Self::cursor_point(state, &bounds)
+                                .and_then(|point| self.peak_edit(state, point.x(), clicked))
+                                .map(Message::EditPeak);
+                        }
+
+                        if message.is_none() && state.dragged_anchor.is_some() {
+                            message = Some(Message::ReleaseAnchor);
+                        }
+
                         state.mouse_pressed = false;
+                        state.dragged_anchor = None;
+                        state.dragged_bound = None;
+                        state.selection = None;
+                        state.drag_time = None;
                     }
                 }
                 mouse::Event::CursorMoved { position } => {
@@ -166,7 +640,14 @@ impl Chart<()> for Chromatography {
                         y: position.y as f64,
                     };
 
-                    if state.mouse_inside && state.mouse_pressed {
+                    if let Some(index) = state.dragged_anchor {
+                        state.mouse_position = positionf64;
+                        message = Self::cursor_point(state, &bounds)
+                            .map(|point| Message::MoveAnchor(index, point));
+                    } else if state.dragged_bound.is_some() || state.selection.is_some() {
+                        state.mouse_position = positionf64;
+                        state.drag_time = Self::cursor_point(state, &bounds).map(|point| point.x());
+                    } else if state.mouse_inside && state.mouse_pressed {
                         let difference = positionf64 - state.mouse_position;
 
                         if !state.ctrl_pressed {
@@ -197,7 +678,7 @@ impl Chart<()> for Chromatography {
                 }
             }
 
-            return (Status::Captured, None);
+            return (Status::Captured, message);
         }
 
         if let canvas::Event::Keyboard(keyboard_ev) = event {
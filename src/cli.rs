@@ -0,0 +1,865 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use iced::Point;
+
+use hplc_core::{
+    baseline::BaselineSettings,
+    chromatography::{Chromatography, SampleType},
+    deconvolution::PeakModel,
+    derivative::Derivatives,
+    import::{DataFormat, ImportProfile},
+    integration::{EventKind, IntegrationEvent, IntegrationSettings, IntegrationWindow},
+    noise::NoiseSettings,
+    reference::Reference,
+    smoothing::Smoothing,
+    vector::Point2D,
+};
+
+use crate::{
+    config::Config,
+    exporter::{ExportSettings, Exporter},
+};
+
+// Enough of a file to tell its format, vendor text exports name it in their first lines
+const DETECT_BYTES: u64 = 8192;
+
+const USAGE: &str = "\
+Usage: hplc-rs batch --samples <DIR> --reference <FILE> --output <DIR> [OPTIONS]
+
+Processes every .arw and .cdf file in <DIR>, and every ChemStation, LabSolutions or
+Chromeleon text export (or other text file with --import-profile), without opening a
+window and writes table.csv and one svg profile per sample into the output directory.
+
+Options:
+    --config <FILE>                   Read the default parameters and import profiles from FILE
+    --no-config                       Use the built-in defaults rather than the saved ones
+    --dex <FILE NAME>                 Sample used to calculate GU
+    --standard <FILE NAME>            Sample used to calculate concentration
+    --blank <FILE NAME>               Sample used to flag system peaks
+    --subtract-blank                  Subtract the blank from data samples
+    --channel <NAME|N>                Analyse the signal column with this name, or the Nth one
+    --import-profile <NAME>           Read text files in no known format with this saved profile
+    --chart-start <MINUTES>
+    --chart-end <MINUTES>
+    --smoothing <METHOD>              One of none, mean, savitzky-golay, gaussian, median
+    --smoothing-radius <POINTS>       Points on either side used by the smoothing filter
+    --derivatives <METHOD>            One of finite-difference, savitzky-golay
+    --derivative-radius <POINTS>      Points on either side of the Savitzky-Golay fit
+    --derivative-order <ORDER>        Polynomial order of the Savitzky-Golay fit
+    --baseline <METHOD>               One of hull, asls, arpls, rolling-ball, snip
+    --baseline-smoothness <LOG10>     log10 of the stiffness (lambda) of asls and arpls
+    --baseline-asymmetry <VALUE>      Weight of points above the asls baseline
+    --baseline-iterations <COUNT>     Reweighting steps of asls and arpls
+    --baseline-radius <POINTS>        Radius of rolling-ball, largest window of snip
+    --integration <MODE>              One of drop-line, valley-to-valley, tangent-skim,
+                                      exponential-skim
+    --integration-window <S>:<E>:<MODE>
+                                      Integrate peaks between S and E minutes with MODE,
+                                      may be repeated
+    --skim-ratio <RATIO>              Height ratio above which a neighbouring peak is skimmed
+    --event <KIND>:<S>:<E>[:<VALUE>]  Timed event, one of inhibit, force-peak, height, inflection
+                                      active from S to E minutes (E may be empty for the end
+                                      of the data), may be repeated
+    --deconvolution <MODEL>           Fit overlapping peaks with none, gaussian, emg or bi-gaussian
+    --noise <METHOD>                  One of mad, quiet-region
+    --noise-region <S>:<E>            Quiet region (minutes) used by --noise quiet-region
+    --height-sn                       --height-requirement is a multiple of the noise
+    --height-requirement <VALUE>
+    --inflection-requirement <VALUE>
+    --rt-tolerance <MINUTES>
+    --gu-tolerance <GU>
+    --injected-volume <MICROLITRES>
+    --dilution <VALUE>
+    --columns <LIST>                  Comma separated subset of rt,gu,area,concentration,system,
+                                      width,base-width,tailing,asymmetry,plates,resolution,sn,
+                                      confidence,metadata
+                                      [default: rt,area]
+    --include-expected                Export the section of reference lipids (default)
+    --include-existing                Export the section of unknown peaks
+    --include-unknowns                Consider unknown lipids during analysis
+    --transpose                       Rows are samples rather than lipids
+    --no-profiles                     Do not export svg profiles
+
+Analysis parameters default to those saved with \"Save as Defaults\" in the app,
+or to those in the --config file.
+";
+
+#[derive(Debug)]
+struct BatchArgs {
+    samples: PathBuf,
+    reference: PathBuf,
+    output: PathBuf,
+    dex: Option<String>,
+    standard: Option<String>,
+    blank: Option<String>,
+    channel: Option<String>,
+    import_profile: Option<ImportProfile>,
+    chart_start: f64,
+    chart_end: f64,
+    smoothing: Smoothing,
+    derivatives: Derivatives,
+    baseline: BaselineSettings,
+    integration: IntegrationSettings,
+    peak_model: PeakModel,
+    noise: NoiseSettings,
+    height_signal_to_noise: bool,
+    height_requirement: f64,
+    inflection_requirement: f64,
+    retention_time_tolerance: f64,
+    glucose_unit_tolerance: f64,
+    injected_volume: f64,
+    sample_dilution: f64,
+    include_unknowns: bool,
+    subtract_blank: bool,
+    profiles: bool,
+    export: ExportSettings,
+}
+
+impl BatchArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut samples = None;
+        let mut reference = None;
+        let mut output = None;
+
+        // Every other argument defaults to the config, so it is read first
+        let config_path = args
+            .iter()
+            .position(|arg| arg == "--config")
+            .map(|index| args.get(index + 1).ok_or("Missing value for --config"))
+            .transpose()?;
+        let config = match (config_path, args.iter().any(|arg| arg == "--no-config")) {
+            (Some(_), true) => {
+                return Err("--config and --no-config exclude each other".to_string());
+            }
+            (Some(path), false) => Config::read(Path::new(path))?,
+            (None, true) => Config::default(),
+            (None, false) => Config::load(),
+        };
+        let defaults = &config.parameters;
+
+        let mut parsed = Self {
+            samples: PathBuf::new(),
+            reference: PathBuf::new(),
+            output: PathBuf::new(),
+            dex: None,
+            standard: None,
+            blank: None,
+            channel: None,
+            import_profile: None,
+            chart_start: defaults.chart_start.value,
+            chart_end: defaults.chart_end.value,
+            smoothing: Smoothing {
+                method: defaults.smoothing,
+                radius: defaults.smoothing_radius.value.round().max(0.0) as usize,
+            },
+            derivatives: Derivatives {
+                method: defaults.derivatives,
+                radius: defaults.derivative_radius.value.round().max(0.0) as usize,
+                order: defaults.derivative_order.value.round().max(0.0) as usize,
+            },
+            baseline: BaselineSettings {
+                method: defaults.baseline,
+                lambda: 10f64.powf(defaults.baseline_smoothness.value),
+                asymmetry: defaults.baseline_asymmetry.value,
+                iterations: defaults.baseline_iterations.value.round().max(0.0) as usize,
+                radius: defaults.baseline_radius.value.round().max(0.0) as usize,
+            },
+            integration: IntegrationSettings {
+                mode: defaults.integration,
+                windows: defaults.integration_windows.clone(),
+                skim_ratio: defaults.skim_ratio.value,
+                events: defaults.integration_events.clone(),
+            },
+            peak_model: defaults.peak_model,
+            noise: NoiseSettings {
+                method: defaults.noise,
+                start: defaults.noise_start,
+                end: defaults.noise_end,
+            },
+            height_signal_to_noise: defaults.height_signal_to_noise,
+            height_requirement: defaults.height_requirement.value,
+            inflection_requirement: defaults.inflection_requirement.value,
+            retention_time_tolerance: defaults.retention_time_tolerance.value,
+            glucose_unit_tolerance: defaults.glucose_unit_tolerance.value,
+            injected_volume: defaults.injected_volume,
+            sample_dilution: defaults.sample_dilution,
+            include_unknowns: defaults.include_unknowns,
+            subtract_blank: defaults.subtract_blank,
+            profiles: true,
+            export: ExportSettings {
+                retention_time: true,
+                area: true,
+                include_expected: true,
+                ..ExportSettings::default()
+            },
+        };
+
+        let mut include_expected = false;
+        let mut include_existing = false;
+
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let mut value = || {
+                iter.next()
+                    .cloned()
+                    .ok_or_else(|| format!("Missing value for {}", flag))
+            };
+
+            match flag.as_str() {
+                "--config" => {
+                    value()?;
+                }
+                "--no-config" => {}
+                "--samples" => samples = Some(PathBuf::from(value()?)),
+                "--reference" => reference = Some(PathBuf::from(value()?)),
+                "--output" => output = Some(PathBuf::from(value()?)),
+                "--dex" => parsed.dex = Some(value()?),
+                "--standard" => parsed.standard = Some(value()?),
+                "--blank" => parsed.blank = Some(value()?),
+                "--channel" => parsed.channel = Some(value()?),
+                "--import-profile" => {
+                    let name = value()?;
+                    let profile = config
+                        .import_profiles
+                        .iter()
+                        .find(|profile| profile.name == name)
+                        .ok_or(format!("No import profile named {}", name))?;
+                    parsed.import_profile = Some(profile.clone());
+                }
+                "--chart-start" => parsed.chart_start = parse_number(flag, &value()?)?,
+                "--chart-end" => parsed.chart_end = parse_number(flag, &value()?)?,
+                "--smoothing" => parsed.smoothing.method = value()?.parse()?,
+                "--smoothing-radius" => parsed.smoothing.radius = parse_count(flag, &value()?)?,
+                "--derivatives" => parsed.derivatives.method = value()?.parse()?,
+                "--derivative-radius" => parsed.derivatives.radius = parse_count(flag, &value()?)?,
+                "--derivative-order" => parsed.derivatives.order = parse_count(flag, &value()?)?,
+                "--baseline" => parsed.baseline.method = value()?.parse()?,
+                "--baseline-smoothness" => {
+                    parsed.baseline.lambda = 10f64.powf(parse_number(flag, &value()?)?)
+                }
+                "--baseline-asymmetry" => {
+                    parsed.baseline.asymmetry = parse_number(flag, &value()?)?
+                }
+                "--baseline-iterations" => {
+                    parsed.baseline.iterations = parse_count(flag, &value()?)?
+                }
+                "--baseline-radius" => parsed.baseline.radius = parse_count(flag, &value()?)?,
+                "--integration" => parsed.integration.mode = value()?.parse()?,
+                "--integration-window" => {
+                    let window = parse_window(flag, &value()?)?;
+                    parsed.integration.windows.push(window);
+                }
+                "--event" => {
+                    let event = parse_event(flag, &value()?)?;
+                    parsed.integration.events.push(event);
+                }
+                "--skim-ratio" => parsed.integration.skim_ratio = parse_number(flag, &value()?)?,
+                "--noise" => parsed.noise.method = value()?.parse()?,
+                "--noise-region" => {
+                    let region = value()?;
+                    let Some((start, end)) = region.split_once(':') else {
+                        return Err(format!(
+                            "Expected <START>:<END> for {}, found {}",
+                            flag, region
+                        ));
+                    };
+
+                    parsed.noise.start = parse_number(flag, start)?;
+                    parsed.noise.end = parse_number(flag, end)?;
+                }
+                "--height-sn" => parsed.height_signal_to_noise = true,
+                "--deconvolution" => parsed.peak_model = value()?.parse()?,
+                "--height-requirement" => {
+                    parsed.height_requirement = parse_number(flag, &value()?)?
+                }
+                "--inflection-requirement" => {
+                    parsed.inflection_requirement = parse_number(flag, &value()?)?
+                }
+                "--rt-tolerance" => {
+                    parsed.retention_time_tolerance = parse_number(flag, &value()?)?
+                }
+                "--gu-tolerance" => parsed.glucose_unit_tolerance = parse_number(flag, &value()?)?,
+                "--injected-volume" => parsed.injected_volume = parse_number(flag, &value()?)?,
+                "--dilution" => parsed.sample_dilution = parse_number(flag, &value()?)?,
+                "--columns" => {
+                    let columns = value()?;
+                    parsed.export.retention_time = false;
+                    parsed.export.glucose_units = false;
+                    parsed.export.area = false;
+                    parsed.export.concentration = false;
+                    parsed.export.system_peaks = false;
+                    parsed.export.manual_labels = false;
+                    parsed.export.peak_width = false;
+                    parsed.export.base_width = false;
+                    parsed.export.tailing = false;
+                    parsed.export.asymmetry = false;
+                    parsed.export.plates = false;
+                    parsed.export.resolution = false;
+                    parsed.export.signal_to_noise = false;
+                    parsed.export.match_confidence = false;
+                    parsed.export.sample_metadata = false;
+
+                    for column in columns.split(',') {
+                        match column.trim() {
+                            "rt" => parsed.export.retention_time = true,
+                            "gu" => parsed.export.glucose_units = true,
+                            "area" => parsed.export.area = true,
+                            "concentration" => parsed.export.concentration = true,
+                            "system" => parsed.export.system_peaks = true,
+                            "width" => parsed.export.peak_width = true,
+                            "base-width" => parsed.export.base_width = true,
+                            "tailing" => parsed.export.tailing = true,
+                            "asymmetry" => parsed.export.asymmetry = true,
+                            "plates" => parsed.export.plates = true,
+                            "resolution" => parsed.export.resolution = true,
+                            "sn" => parsed.export.signal_to_noise = true,
+                            "confidence" => parsed.export.match_confidence = true,
+                            "metadata" => parsed.export.sample_metadata = true,
+                            other => return Err(format!("Unknown column {}", other)),
+                        }
+                    }
+                }
+                "--include-expected" => include_expected = true,
+                "--include-existing" => include_existing = true,
+                "--include-unknowns" => parsed.include_unknowns = true,
+                "--subtract-blank" => parsed.subtract_blank = true,
+                "--transpose" => parsed.export.transpose = true,
+                "--no-profiles" => parsed.profiles = false,
+                other => return Err(format!("Unknown argument {}", other)),
+            }
+        }
+
+        // Only override the default sections if the user asked for specific ones
+        if include_expected || include_existing {
+            parsed.export.include_expected = include_expected;
+            parsed.export.include_existing = include_existing;
+        }
+
+        parsed.samples = samples.ok_or("--samples is required")?;
+        parsed.reference = reference.ok_or("--reference is required")?;
+        parsed.output = output.ok_or("--output is required")?;
+
+        Ok(parsed)
+    }
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .map_err(|_| format!("Expected a number for {}, found {}", flag, value))
+}
+
+fn parse_count(flag: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse::<usize>()
+        .map_err(|_| format!("Expected a whole number for {}, found {}", flag, value))
+}
+
+/// `.arw` and `.cdf` files, or text files in the format of another vendor.
+///
+/// Other text files are read with the import profile if one is given, else they are skipped
+/// as they are usually references or earlier exports. References are always skipped.
+fn is_sample_file(path: &Path, profile: bool) -> bool {
+    if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("arw"))
+    {
+        return true;
+    }
+
+    match detect_format(path) {
+        Ok(DataFormat::Andi) => true,
+        Ok(DataFormat::Empower) => {
+            profile && is_text_file(path) && !Reference::is_reference_file(&path)
+        }
+        Ok(_) => is_text_file(path),
+        Err(_) => false,
+    }
+}
+
+fn is_text_file(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    ["csv", "tsv", "txt", "asc", "dat"].contains(&extension.as_str())
+}
+
+/// Format of the file judged by its start only, so skipped files are not read in full.
+fn detect_format(path: &Path) -> std::io::Result<DataFormat> {
+    let mut bytes = vec![];
+    fs::File::open(path)?
+        .take(DETECT_BYTES)
+        .read_to_end(&mut bytes)?;
+
+    // A cut off character or line would fail to decode
+    if bytes.len() as u64 == DETECT_BYTES
+        && let Some(end) = bytes.iter().rposition(|byte| *byte == b'\n')
+    {
+        bytes.truncate(end + 1);
+    }
+
+    Ok(DataFormat::detect(&bytes))
+}
+
+/// Reads a text file not in a vendor format with `profile`, anything else as detected.
+fn read_sample(path: &Path, profile: Option<&ImportProfile>) -> Result<Chromatography, String> {
+    let detected = detect_format(path);
+    let sample = match profile {
+        Some(profile) if is_text_file(path) && matches!(detected, Ok(DataFormat::Empower)) => {
+            Chromatography::from_delimited_file(&path, profile)
+        }
+        _ => Chromatography::from_file(&path),
+    };
+
+    sample.map_err(|err| format!("Skipping unreadable file {}: {}", path.display(), err))
+}
+
+/// Channel named `channel`, or numbered `channel` counting from 1.
+fn channel_index(sample: &Chromatography, channel: &str) -> Option<usize> {
+    let channels = sample.get_channels();
+    channels
+        .iter()
+        .position(|candidate| candidate.name == channel)
+        .or_else(|| {
+            channel
+                .parse::<usize>()
+                .ok()
+                .filter(|number| (1..=channels.len()).contains(number))
+                .map(|number| number - 1)
+        })
+}
+
+fn parse_window(flag: &str, value: &str) -> Result<IntegrationWindow, String> {
+    let parts: Vec<&str> = value.split(':').collect();
+    let [start, end, mode] = parts.as_slice() else {
+        return Err(format!(
+            "Expected <START>:<END>:<MODE> for {}, found {}",
+            flag, value
+        ));
+    };
+
+    Ok(IntegrationWindow {
+        start: parse_number(flag, start)?,
+        end: parse_number(flag, end)?,
+        mode: mode.parse()?,
+    })
+}
+
+fn parse_event(flag: &str, value: &str) -> Result<IntegrationEvent, String> {
+    let parts: Vec<&str> = value.split(':').collect();
+    let (kind, start, end, event_value) = match parts.as_slice() {
+        [kind, start, end] => (kind, start, end, None),
+        [kind, start, end, event_value] => (kind, start, end, Some(event_value)),
+        _ => {
+            return Err(format!(
+                "Expected <KIND>:<START>:<END>[:<VALUE>] for {}, found {}",
+                flag, value
+            ));
+        }
+    };
+
+    let kind: EventKind = kind.parse()?;
+    let end = match end.trim() {
+        "" => None,
+        end => Some(parse_number(flag, end)?),
+    };
+
+    let event_value = match event_value.filter(|event_value| !event_value.trim().is_empty()) {
+        Some(event_value) => parse_number(flag, event_value)?,
+        None if kind.has_value() => {
+            return Err(format!("{} events require a value for {}", kind, flag));
+        }
+        None => 0.0,
+    };
+
+    Ok(IntegrationEvent {
+        kind,
+        start: parse_number(flag, start)?,
+        end,
+        value: event_value,
+    })
+}
+
+pub fn run(args: &[String]) -> i32 {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print!("{}", USAGE);
+        return 0;
+    }
+
+    let args = match BatchArgs::parse(args) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return 2;
+        }
+    };
+
+    match process(&args) {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("{}", err);
+            1
+        }
+    }
+}
+
+fn process(args: &BatchArgs) -> Result<(), String> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(&args.samples)
+        .map_err(|err| format!("Could not read {}: {}", args.samples.display(), err))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_sample_file(path, args.import_profile.is_some()))
+        .collect();
+    paths.sort();
+
+    if paths.is_empty() {
+        return Err(format!(
+            "No sample files found in {}",
+            args.samples.display()
+        ));
+    }
+
+    let reference = Reference::parse_file(&args.reference).map_err(|err| {
+        format!(
+            "Could not read reference {}: {}",
+            args.reference.display(),
+            err
+        )
+    })?;
+    let lipid_reference: Rc<[Reference]> = Rc::from(reference.as_slice());
+
+    let range = args.chart_start..args.chart_end;
+    let mut samples = vec![];
+    for path in paths {
+        let mut sample = match read_sample(&path, args.import_profile.as_ref()) {
+            Ok(value) => value,
+            Err(err) => {
+                eprintln!("{}", err);
+                continue;
+            }
+        };
+
+        if let Some(channel) = &args.channel {
+            match channel_index(&sample, channel) {
+                Some(index) => {
+                    sample.set_channel(&index);
+                }
+                None => eprintln!("{} has no channel {}", path.display(), channel),
+            }
+        }
+
+        sample.set_smoothing(&args.smoothing);
+        sample.set_derivatives(&args.derivatives);
+        sample.set_baseline_settings(&args.baseline);
+        sample.set_integration_mode(&args.integration.mode);
+        sample.set_integration_windows(&args.integration.windows);
+        sample.set_skim_ratio(&args.integration.skim_ratio);
+        sample.set_integration_events(&args.integration.events);
+        sample.set_data_range(&range);
+        sample.set_lipid_references(Rc::clone(&lipid_reference));
+        sample.set_include_unknowns(&args.include_unknowns);
+        sample.set_height_requirement(&args.height_requirement);
+        sample.set_inflection_requirement(&args.inflection_requirement);
+        sample.set_retention_time_tolerance(&args.retention_time_tolerance);
+        sample.set_glucose_unit_tolerance(&args.glucose_unit_tolerance);
+        sample.set_subtract_blank(&args.subtract_blank);
+        sample.set_noise_settings(&args.noise);
+        sample.set_signal_to_noise_threshold(&args.height_signal_to_noise);
+        sample.set_peak_model(&args.peak_model);
+        samples.push(sample);
+    }
+
+    let find_sample = |name: &str| {
+        samples
+            .iter()
+            .position(|sample| sample.file_name == name)
+            .ok_or_else(|| format!("Sample {} is not in {}", name, args.samples.display()))
+    };
+
+    let dex_handle = args.dex.as_deref().map(find_sample).transpose()?;
+    let standard_handle = args.standard.as_deref().map(find_sample).transpose()?;
+    let blank_handle = args.blank.as_deref().map(find_sample).transpose()?;
+
+    let mut exporter = Exporter::default();
+    exporter.set_settings(args.export.clone());
+    exporter.set_lipid_references(Rc::clone(&lipid_reference));
+    exporter.set_global_zoom(Point::new(1.0, 1.0));
+
+    if let Some(handle) = blank_handle {
+        samples[handle].set_sample_type(&SampleType::Blank);
+        let blank: Rc<[Point2D]> = Rc::from(samples[handle].get_raw_data());
+        for (i, sample) in samples.iter_mut().enumerate() {
+            if i != handle {
+                sample.set_blank(Some(Rc::clone(&blank)));
+            }
+        }
+    }
+
+    if let Some(handle) = dex_handle {
+        samples[handle].set_sample_type(&SampleType::Dex);
+        let glucose_transformer = samples[handle].get_glucose_transformer();
+        if glucose_transformer.is_none() {
+            eprintln!("Could not calculate GU from {}", samples[handle].title);
+        }
+
+        exporter.set_glucose_spline(glucose_transformer.as_ref());
+        for sample in samples.iter_mut() {
+            sample.set_glucose_transformer(&glucose_transformer);
+        }
+    }
+
+    if let Some(handle) = standard_handle {
+        samples[handle].set_sample_type(&SampleType::Standard);
+        let area = samples[handle]
+            .get_unqualified_components()
+            .first()
+            .map(|peak| peak.area);
+        let multiplier = samples[handle]
+            .get_concentration_multiplier(args.injected_volume, args.sample_dilution);
+        if multiplier.is_none() {
+            eprintln!("Standard {} has no peaks", samples[handle].title);
+        }
+
+        exporter.set_concentration_multiplier(area, multiplier);
+    }
+
+    fs::create_dir_all(&args.output)
+        .map_err(|err| format!("Could not create {}: {}", args.output.display(), err))?;
+
+    let table = args.output.join("table.csv");
+    exporter
+        .write_csv(&table, &samples)
+        .map_err(|err| format!("Could not write {}: {}", table.display(), err))?;
+
+    if args.profiles {
+        exporter.write_profiles(&args.output, &samples);
+    }
+
+    println!(
+        "Processed {} samples into {}",
+        samples.len(),
+        args.output.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::Parameters;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test/samples")
+            .join(name)
+    }
+
+    // Built-in defaults, so the tests do not depend on the user's config
+    fn parse(extra: &[&str]) -> Result<BatchArgs, String> {
+        let mut args: Vec<String> = [
+            "--no-config",
+            "--samples",
+            "in",
+            "--reference",
+            "reference.csv",
+            "--output",
+            "out",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+
+        BatchArgs::parse(&args)
+    }
+
+    #[test]
+    fn required_arguments() {
+        let args = parse(&[]).unwrap();
+        assert_eq!(args.samples, PathBuf::from("in"));
+        assert_eq!(args.reference, PathBuf::from("reference.csv"));
+        assert_eq!(args.output, PathBuf::from("out"));
+        assert_eq!(args.injected_volume, Parameters::default().injected_volume);
+
+        let missing = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            BatchArgs::parse(&args).unwrap_err()
+        };
+        assert_eq!(
+            missing(&["--no-config", "--reference", "r.csv", "--output", "out"]),
+            "--samples is required"
+        );
+        assert_eq!(
+            missing(&["--no-config", "--samples", "in", "--output", "out"]),
+            "--reference is required"
+        );
+        assert_eq!(
+            missing(&["--no-config", "--samples", "in", "--reference", "r.csv"]),
+            "--output is required"
+        );
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert_eq!(
+            parse(&["--unknown"]).unwrap_err(),
+            "Unknown argument --unknown"
+        );
+        assert_eq!(
+            parse(&["--dilution"]).unwrap_err(),
+            "Missing value for --dilution"
+        );
+        assert_eq!(
+            parse(&["--dilution", "lots"]).unwrap_err(),
+            "Expected a number for --dilution, found lots"
+        );
+        assert_eq!(
+            parse(&["--smoothing-radius", "2.5"]).unwrap_err(),
+            "Expected a whole number for --smoothing-radius, found 2.5"
+        );
+        assert!(parse(&["--smoothing", "wavelet"]).is_err());
+    }
+
+    #[test]
+    fn columns() {
+        let args = parse(&["--columns", "gu, sn,confidence"]).unwrap();
+        let export = &args.export;
+        assert!(export.glucose_units && export.signal_to_noise && export.match_confidence);
+        assert!(!export.retention_time && !export.area && !export.concentration);
+        assert!(export.include_expected && !export.include_existing);
+
+        let args = parse(&["--include-existing"]).unwrap();
+        assert!(args.export.retention_time && args.export.area);
+        assert!(!args.export.include_expected && args.export.include_existing);
+
+        assert_eq!(
+            parse(&["--columns", "rt,height"]).unwrap_err(),
+            "Unknown column height"
+        );
+    }
+
+    #[test]
+    fn windows() {
+        let window = parse_window("--integration-window", "10:20.5:valley").unwrap();
+        assert_eq!(
+            window,
+            IntegrationWindow {
+                start: 10.0,
+                end: 20.5,
+                mode: "valley-to-valley".parse().unwrap(),
+            }
+        );
+
+        assert!(parse_window("--integration-window", "10:20").is_err());
+        assert!(parse_window("--integration-window", "10:20:drop:1").is_err());
+        assert!(parse_window("--integration-window", "a:20:drop").is_err());
+        assert!(parse_window("--integration-window", "10:20:sideways").is_err());
+
+        let args = parse(&[
+            "--integration-window",
+            "1:2:drop",
+            "--integration-window",
+            "3:4:tangent",
+        ])
+        .unwrap();
+        assert_eq!(args.integration.windows.len(), 2);
+    }
+
+    #[test]
+    fn events() {
+        let event = parse_event("--event", "height:5:10:2.5").unwrap();
+        assert_eq!(
+            event,
+            IntegrationEvent {
+                kind: EventKind::HeightRequirement,
+                start: 5.0,
+                end: Some(10.0),
+                value: 2.5,
+            }
+        );
+
+        // An empty end lasts until the end of the data, inhibiting needs no value
+        let event = parse_event("--event", "inhibit:30:").unwrap();
+        assert_eq!(event.kind, EventKind::InhibitIntegration);
+        assert_eq!(event.end, None);
+        assert_eq!(event.value, 0.0);
+
+        assert!(parse_event("--event", "height:5:10").is_err());
+        assert!(parse_event("--event", "height:5:10:").is_err());
+        assert!(parse_event("--event", "inhibit:5").is_err());
+        assert!(parse_event("--event", "pause:5:10").is_err());
+        assert!(parse_event("--event", "inhibit:x:10").is_err());
+    }
+
+    #[test]
+    fn config_file() {
+        let path = std::env::temp_dir().join(format!("hplc_rs_config_{}.toml", std::process::id()));
+        fs::write(&path, "[parameters]\ninjected_volume = 12.5\n").unwrap();
+
+        let args: Vec<String> = [
+            "--config",
+            path.to_str().unwrap(),
+            "--samples",
+            "in",
+            "--reference",
+            "r.csv",
+            "--output",
+            "out",
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        let parsed = BatchArgs::parse(&args);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(parsed.unwrap().injected_volume, 12.5);
+
+        // Missing once removed
+        assert!(BatchArgs::parse(&args).is_err());
+        assert_eq!(
+            parse(&["--config", "config.toml"]).unwrap_err(),
+            "--config and --no-config exclude each other"
+        );
+    }
+
+    #[test]
+    fn sample_files() {
+        assert!(is_sample_file(
+            &fixture("PC12/chromatogram_timeseries_46739.arw"),
+            false
+        ));
+        assert!(is_sample_file(
+            &fixture("ANDI/chromatogram_46739.cdf"),
+            false
+        ));
+        assert!(is_sample_file(&fixture("Chromeleon/PC12-9.txt"), false));
+        assert!(is_sample_file(&fixture("Shimadzu/PC12-9.txt"), false));
+        assert!(is_sample_file(&fixture("Agilent/PC12-9.csv"), false));
+        assert!(!is_sample_file(&fixture("PC12/PC12_reference.csv"), false));
+        assert!(!is_sample_file(&fixture("PC12/PC12_reference.csv"), true));
+        assert!(!is_sample_file(
+            &fixture("PC12-export/PC12_export.csv"),
+            false
+        ));
+
+        // Only the start is read, the data of the larger exports lies far beyond it
+        assert_eq!(
+            detect_format(&fixture("Chromeleon/PC12-9.txt")).unwrap(),
+            DataFormat::Chromeleon
+        );
+        assert_eq!(
+            detect_format(&fixture("Shimadzu/PC12-9.txt")).unwrap(),
+            DataFormat::LabSolutions
+        );
+        assert_eq!(
+            detect_format(&fixture("Agilent/PC12-9.csv")).unwrap(),
+            DataFormat::ChemStation
+        );
+    }
+}
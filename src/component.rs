@@ -1,109 +0,0 @@
-use crate::{reference::Reference, spline::Spline, vector::*};
-
-#[derive(Clone, Debug, Default, PartialEq)]
-pub struct Peak {
-    pub start: Point2D,
-    pub retention_point: Point2D,
-    pub end: Point2D,
-    pub height: f64,
-    pub area: f64,
-}
-
-impl Peak {
-    pub fn get_retention_location(&self, spline: Option<&Spline>) -> Option<f64> {
-        match spline {
-            None => Some(self.retention_point.x()),
-            Some(spline) => spline.evaluate(self.retention_point.x()),
-        }
-    }
-}
-
-#[derive(Clone, Debug)]
-pub enum Component {
-    Unknown(Peak),
-    Located(Peak, Reference),
-    Reference(Reference),
-}
-
-impl Component {
-    pub fn get_experimental_rt(&self) -> Option<f64> {
-        match self {
-            Component::Unknown(peak) => Some(peak.retention_point.x()),
-            Component::Located(peak, _) => Some(peak.retention_point.x()),
-            Component::Reference(_) => None,
-        }
-    }
-
-    pub fn get_expected_rt(&self) -> Option<f64> {
-        match self {
-            Component::Unknown(_) => None,
-            Component::Located(_, reference) => reference.get_expected_rt(),
-            Component::Reference(reference) => reference.get_expected_rt(),
-        }
-    }
-
-    pub fn get_experimental_gu(&self, maybe_spline: Option<&Spline>) -> Option<f64> {
-        match self {
-            Component::Unknown(peak) => {
-                maybe_spline.map_or(None, |spline| spline.evaluate(peak.retention_point.x()))
-            }
-            Component::Located(peak, _) => {
-                maybe_spline.map_or(None, |spline| spline.evaluate(peak.retention_point.x()))
-            }
-            Component::Reference(_) => None,
-        }
-    }
-
-    pub fn get_expected_gu(&self, spline: Option<&Spline>) -> Option<f64> {
-        match self {
-            Component::Unknown(_) => None,
-            Component::Located(_, reference) => reference.get_expected_gu(spline),
-            Component::Reference(reference) => reference.get_expected_gu(spline),
-        }
-    }
-
-    pub fn get_area(&self) -> Option<f64> {
-        match self {
-            Component::Unknown(peak) => Some(peak.area),
-            Component::Located(peak, _) => Some(peak.area),
-            Component::Reference(_) => None,
-        }
-    }
-
-    pub fn point_label(&self, spline: Option<&Spline>) -> Option<String> {
-        let mut builder = String::from("[");
-        match &self {
-            Component::Unknown(peak) => {
-                // [Unknown, {}, {}] = rt, gu
-                builder.push_str("Unknown, ");
-                builder.push_str(&format!("{:.3}", peak.retention_point.x()));
-
-                if let Some(spline) = spline {
-                    if let Some(gu) = spline.evaluate(peak.retention_point.x()) {
-                        builder.push_str(&format!(", {:.3}", gu));
-                    }
-                }
-            }
-            Component::Located(peak, reference) => {
-                // [{}, {}, {}] = name, rt, gu
-                if let Some(name) = &reference.name {
-                    builder.push_str(&format!("{}, ", name));
-                } else {
-                    builder.push_str("Unknown, ");
-                }
-
-                builder.push_str(&format!("{:.3}", peak.retention_point.x()));
-
-                if let Some(spline) = spline {
-                    if let Some(gu) = spline.evaluate(peak.retention_point.x()) {
-                        builder.push_str(&format!(", {:.3}", gu));
-                    }
-                }
-            }
-            Component::Reference(_) => return None,
-        }
-
-        builder.push_str("]");
-        Some(builder)
-    }
-}
@@ -0,0 +1,67 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use hplc_core::import::ImportProfile;
+use serde::{Deserialize, Serialize};
+
+use crate::{exporter::ExportSettings, project::Parameters};
+
+/// Defaults loaded at startup, stored in the user's config directory.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub parameters: Parameters,
+    pub export: ExportSettings,
+    /// Saved layouts of delimited text files, see [`crate::importer::Importer`].
+    pub import_profiles: Vec<ImportProfile>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("hplc-rs").join("config.toml"))
+    }
+
+    /// Falls back to the built-in defaults if the file is missing or invalid.
+    pub fn load() -> Self {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Self::default(),
+        };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => return Self::default(),
+        };
+
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("Ignoring invalid config {}: {}", path.display(), err);
+                Self::default()
+            }
+        }
+    }
+
+    /// Reads the config at `path`, unlike [`Config::load`] a missing or invalid file is an error.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+
+        toml::from_str(&content)
+            .map_err(|err| format!("Invalid config {}: {}", path.display(), err))
+    }
+
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = Self::path().ok_or("Could not find a config directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|err| format!("Could not create {}: {}", dir.display(), err))?;
+        }
+
+        let content = toml::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(&path, content)
+            .map_err(|err| format!("Could not write {}: {}", path.display(), err))?;
+
+        Ok(path)
+    }
+}
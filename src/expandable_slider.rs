@@ -3,6 +3,7 @@ use iced::{
     alignment::{Horizontal, Vertical},
     widget::{column, row, slider, text, text_input, toggler},
 };
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug)]
 pub enum Message {
@@ -13,6 +14,17 @@ pub enum Message {
     End(String),
     Step(String),
     Expanded(bool),
+    /// The slider was let go, ending a drag.
+    Released,
+}
+
+/// Everything needed to restore a slider, `value` is `x` for exponential sliders.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SliderState {
+    pub value: f64,
+    pub start: f64,
+    pub end: f64,
+    pub step: f64,
 }
 
 #[derive(Clone, Debug)]
@@ -57,6 +69,33 @@ impl ExpandableSlider {
         }
     }
 
+    pub fn get_state(&self) -> SliderState {
+        SliderState {
+            value: self.value,
+            start: self.start,
+            end: self.end,
+            step: self.step,
+        }
+    }
+
+    pub fn set_state(&mut self, state: &SliderState) -> &mut Self {
+        self.value = state.value;
+        self.start = state.start;
+        self.end = state.end;
+        self.step = state.step;
+
+        self.value_str = if self.exponential {
+            format!("{:.3}", self.get_value())
+        } else {
+            self.value.to_string()
+        };
+        self.start_str = self.start.to_string();
+        self.end_str = self.end.to_string();
+        self.step_str = self.step.to_string();
+
+        self
+    }
+
     pub fn set_exponential(&mut self, exponential: bool) -> &mut Self {
         self.exponential = exponential;
 
@@ -76,6 +115,7 @@ impl ExpandableSlider {
             Message::Value(float)
         })
         .step(self.step)
+        .on_release(Message::Released)
         .width(Length::FillPortion(5));
 
         if self.expanded {
@@ -192,6 +232,7 @@ impl ExpandableSlider {
                 self.expanded = expanded;
                 None
             }
+            Message::Released => None,
         }
     }
 
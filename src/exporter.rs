@@ -1,5 +1,5 @@
 use iced::{
-    Element, Length, Task,
+    Element, Length, Point, Task,
     alignment::{Horizontal, Vertical},
     widget::{self, Column, Space, button, checkbox, column, container, row, scrollable, text},
     window::{self, Settings},
@@ -7,17 +7,21 @@ use iced::{
 use plotters::prelude::*;
 use plotters_iced::Chart;
 use rfd::FileHandle;
+use serde::{Deserialize, Serialize};
 
-use std::{fs, rc::Rc};
+use std::{fs, io, path::Path, rc::Rc};
 
-use crate::{
-    chromatogram::ChromatogramState,
+use hplc_core::{
     chromatography::{Chromatography, ComponentFilter},
     component::Component,
+    metrics::PeakMetrics,
     reference::Reference,
     spline::Spline,
+    vector::Vector2,
 };
 
+use crate::chromatogram::{Chromatogram, ChromatogramState};
+
 #[derive(Clone, Debug)]
 pub enum Message {
     None,
@@ -33,6 +37,18 @@ pub enum Message {
     Transpose(bool),
     IncludeExpected(bool),
     IncludeExisting(bool),
+    SystemPeaks(bool),
+    ManualLabels(bool),
+    BaselineAnchors(bool),
+    PeakWidth(bool),
+    BaseWidth(bool),
+    Tailing(bool),
+    Asymmetry(bool),
+    Plates(bool),
+    Resolution(bool),
+    SignalToNoise(bool),
+    MatchConfidence(bool),
+    SampleMetadata(bool),
 }
 
 #[derive(Debug, Default)]
@@ -46,19 +62,38 @@ pub struct Exporter {
     glucose_spline: Option<Spline>,
     standard_area: Option<f64>,
     conc_multiplier: Option<f64>,
+    global_zoom: Point<f64>,
 
-    //User-defined state in order of appearance
-    // Content.
-    retention_time: bool,
-    glucose_units: bool,
-    area: bool,
-    concentration: bool,
+    //User-defined state
+    settings: ExportSettings,
+}
 
-    include_expected: bool,
-    include_existing: bool,
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportSettings {
+    // Content in order of appearance.
+    pub retention_time: bool,
+    pub glucose_units: bool,
+    pub area: bool,
+    pub concentration: bool,
+    pub peak_width: bool,
+    pub base_width: bool,
+    pub tailing: bool,
+    pub asymmetry: bool,
+    pub plates: bool,
+    pub resolution: bool,
+    pub signal_to_noise: bool,
+    pub match_confidence: bool,
+
+    pub include_expected: bool,
+    pub include_existing: bool,
+    pub system_peaks: bool,
+    pub manual_labels: bool,
+    pub baseline_anchors: bool,
+    pub sample_metadata: bool,
 
     // Other Settings.
-    transpose: bool,
+    pub transpose: bool,
 }
 
 impl Exporter {
@@ -94,42 +129,71 @@ impl Exporter {
         enable_gu: bool,
         enable_concentration: bool,
     ) -> Element<'_, Message> {
-        let retention_time =
-            checkbox("Retention Time", self.retention_time).on_toggle(Message::RetentionTime);
+        let retention_time = checkbox("Retention Time", self.settings.retention_time)
+            .on_toggle(Message::RetentionTime);
 
         let glucose_units: Element<'_, Message> = if enable_gu {
-            checkbox("Glucose Units", self.glucose_units)
+            checkbox("Glucose Units", self.settings.glucose_units)
                 .on_toggle(Message::GlucoseUnits)
                 .into()
         } else {
             let disable: Option<fn(bool) -> Message> = None;
-            let toggle = checkbox("Glucose Units", self.glucose_units).on_toggle_maybe(disable);
+            let toggle =
+                checkbox("Glucose Units", self.settings.glucose_units).on_toggle_maybe(disable);
             let warning = text("Dex not set! Cannot calculate Glucose Units")
                 .color(iced::Color::new(1.0, 0.0, 0.0, 1.0));
             row![toggle, warning].into()
         };
 
-        let area = checkbox("Area", self.area).on_toggle(Message::Area);
+        let area = checkbox("Area", self.settings.area).on_toggle(Message::Area);
 
         let concentration: Element<'_, Message> = if enable_concentration {
-            checkbox("Concentration", self.concentration)
+            checkbox("Concentration", self.settings.concentration)
                 .on_toggle(Message::Concentration)
                 .into()
         } else {
             let disable: Option<fn(bool) -> Message> = None;
-            let toggle = checkbox("Concentration", self.concentration).on_toggle_maybe(disable);
+            let toggle =
+                checkbox("Concentration", self.settings.concentration).on_toggle_maybe(disable);
             let warning = text("Standard not set! Cannot calculate concentration")
                 .color(iced::Color::new(1.0, 0.0, 0.0, 1.0));
             row![toggle, warning].into()
         };
 
-        let transpose = checkbox("Transpose", self.transpose).on_toggle(Message::Transpose);
+        let metrics = row![
+            checkbox("Width (50%)", self.settings.peak_width).on_toggle(Message::PeakWidth),
+            checkbox("Base Width", self.settings.base_width).on_toggle(Message::BaseWidth),
+            checkbox("Tailing", self.settings.tailing).on_toggle(Message::Tailing),
+            checkbox("Asymmetry", self.settings.asymmetry).on_toggle(Message::Asymmetry),
+            checkbox("Plates", self.settings.plates).on_toggle(Message::Plates),
+            checkbox("Resolution", self.settings.resolution).on_toggle(Message::Resolution),
+            checkbox("S/N", self.settings.signal_to_noise).on_toggle(Message::SignalToNoise),
+        ]
+        .spacing(10);
+
+        let match_confidence = checkbox("Match Confidence", self.settings.match_confidence)
+            .on_toggle(Message::MatchConfidence);
+
+        let transpose =
+            checkbox("Transpose", self.settings.transpose).on_toggle(Message::Transpose);
+
+        let include_expected = checkbox("Include Expected", self.settings.include_expected)
+            .on_toggle(Message::IncludeExpected);
 
-        let include_expected =
-            checkbox("Include Expected", self.include_expected).on_toggle(Message::IncludeExpected);
+        let include_existing = checkbox("Include Existing", self.settings.include_existing)
+            .on_toggle(Message::IncludeExisting);
 
-        let include_existing =
-            checkbox("Include Existing", self.include_existing).on_toggle(Message::IncludeExisting);
+        let system_peaks = checkbox("Flag System Peaks", self.settings.system_peaks)
+            .on_toggle(Message::SystemPeaks);
+
+        let manual_labels = checkbox("Flag Manual Labels", self.settings.manual_labels)
+            .on_toggle(Message::ManualLabels);
+
+        let baseline_anchors = checkbox("Baseline Anchors", self.settings.baseline_anchors)
+            .on_toggle(Message::BaselineAnchors);
+
+        let sample_metadata = checkbox("Sample Metadata", self.settings.sample_metadata)
+            .on_toggle(Message::SampleMetadata);
 
         let preview = {
             let builder = TableBuilderElement::new(self.references.clone(), samples);
@@ -153,9 +217,15 @@ impl Exporter {
             glucose_units,
             area,
             concentration,
+            metrics,
+            match_confidence,
             transpose,
             include_expected,
             include_existing,
+            system_peaks,
+            manual_labels,
+            baseline_anchors,
+            sample_metadata,
             preview,
             export
         ];
@@ -201,66 +271,149 @@ impl Exporter {
                 })
             }
             Message::TargetFile(file_handle) => {
-                let builder = TableBuilderCsv::new(self.references.clone(), samples);
-                let mut content = self.export_table(builder);
-                if let Some(area) = self.standard_area {
-                    content.push_str(&format!("Standard Area: {:.3}", area));
-                }
                 let path = file_handle.path();
-                let _ = fs::write(path, content);
+                if let Err(err) = self.write_csv(&path, samples) {
+                    eprintln!("{}", err);
+                }
                 Task::none()
             }
             Message::TargetDirectory(file_handle) => {
                 let root = file_handle.path();
-
-                for sample in samples {
-                    let mut path = root.join(&sample.file_name);
-                    path.set_extension("svg");
-
-                    let image = SVGBackend::new(&path, (1980, 1080)).into_drawing_area();
-                    image.fill(&WHITE).expect("failed");
-
-                    let builder = ChartBuilder::on(&image);
-                    sample.build_chart(&ChromatogramState::default(), builder);
-                }
-
+                self.write_profiles(&root, samples);
                 Task::none()
             }
             Message::RetentionTime(enable) => {
-                self.retention_time = enable;
+                self.settings.retention_time = enable;
                 Task::none()
             }
             Message::GlucoseUnits(enable) => {
-                self.glucose_units = enable;
+                self.settings.glucose_units = enable;
                 Task::none()
             }
             Message::Area(enable) => {
-                self.area = enable;
+                self.settings.area = enable;
                 Task::none()
             }
             Message::Concentration(enable) => {
-                self.concentration = enable;
+                self.settings.concentration = enable;
                 Task::none()
             }
             Message::Transpose(enable) => {
-                self.transpose = enable;
+                self.settings.transpose = enable;
                 Task::none()
             }
             Message::IncludeExpected(enable) => {
-                self.include_expected = enable;
+                self.settings.include_expected = enable;
                 Task::none()
             }
             Message::IncludeExisting(enable) => {
-                self.include_existing = enable;
+                self.settings.include_existing = enable;
+                Task::none()
+            }
+            Message::SystemPeaks(enable) => {
+                self.settings.system_peaks = enable;
+                Task::none()
+            }
+            Message::ManualLabels(enable) => {
+                self.settings.manual_labels = enable;
+                Task::none()
+            }
+            Message::BaselineAnchors(enable) => {
+                self.settings.baseline_anchors = enable;
+                Task::none()
+            }
+            Message::MatchConfidence(enable) => {
+                self.settings.match_confidence = enable;
+                Task::none()
+            }
+            Message::SampleMetadata(enable) => {
+                self.settings.sample_metadata = enable;
+                Task::none()
+            }
+            Message::PeakWidth(enable) => {
+                self.settings.peak_width = enable;
+                Task::none()
+            }
+            Message::BaseWidth(enable) => {
+                self.settings.base_width = enable;
+                Task::none()
+            }
+            Message::Tailing(enable) => {
+                self.settings.tailing = enable;
+                Task::none()
+            }
+            Message::Asymmetry(enable) => {
+                self.settings.asymmetry = enable;
+                Task::none()
+            }
+            Message::Plates(enable) => {
+                self.settings.plates = enable;
+                Task::none()
+            }
+            Message::Resolution(enable) => {
+                self.settings.resolution = enable;
+                Task::none()
+            }
+            Message::SignalToNoise(enable) => {
+                self.settings.signal_to_noise = enable;
                 Task::none()
             }
         }
     }
 
+    pub fn write_csv<P: AsRef<Path>>(
+        &self,
+        path: &P,
+        samples: &[Chromatography],
+    ) -> io::Result<()> {
+        let builder = TableBuilderCsv::new(self.references.clone(), samples);
+        let mut content = self.export_table(builder);
+        if self.settings.baseline_anchors {
+            content.push_str("[Baseline Anchors]\nSample,Time,Height\n");
+            for sample in samples {
+                for anchor in sample.get_baseline_anchors() {
+                    content.push_str(&format!(
+                        "\"{}\",{:.3},{:.3}\n",
+                        sample.title,
+                        anchor.x(),
+                        anchor.y()
+                    ));
+                }
+            }
+            content.push('\n');
+        }
+        if let Some(area) = self.standard_area {
+            content.push_str(&format!("Standard Area: {:.3}", area));
+        }
+        fs::write(path, content)
+    }
+
+    pub fn write_profiles<P: AsRef<Path>>(&self, root: &P, samples: &[Chromatography]) {
+        for sample in samples {
+            let mut path = root.as_ref().join(&sample.file_name);
+            path.set_extension("svg");
+
+            let image = SVGBackend::new(&path, (1980, 1080)).into_drawing_area();
+            image.fill(&WHITE).expect("failed");
+
+            let builder = ChartBuilder::on(&image);
+            Chromatogram::new(sample, self.global_zoom)
+                .build_chart(&ChromatogramState::default(), builder);
+        }
+    }
+
     pub fn owns_window(&self, window_id: window::Id) -> bool {
         Some(window_id) == self.database_id || Some(window_id) == self.profiles_id
     }
 
+    pub fn get_settings(&self) -> &ExportSettings {
+        &self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: ExportSettings) {
+        self.settings = settings;
+    }
+
     pub fn set_lipid_references(&mut self, references: Rc<[Reference]>) {
         self.references = references;
     }
@@ -269,16 +422,24 @@ impl Exporter {
         self.glucose_spline = spline.cloned();
     }
 
+    pub fn set_global_zoom(&mut self, zoom: Point<f64>) {
+        self.global_zoom = zoom;
+    }
+
     pub fn set_concentration_multiplier(&mut self, area: Option<f64>, multiplier: Option<f64>) {
         self.standard_area = area;
         self.conc_multiplier = multiplier;
     }
 
     fn export_table<TOut, B: TableBuilder<TOut>>(&self, mut builder: B) -> TOut {
-        builder.set_transpose(self.transpose);
+        builder.set_transpose(self.settings.transpose);
 
-        if self.include_expected {
-            if self.retention_time {
+        if self.settings.sample_metadata {
+            builder.build_metadata_section("Sample Metadata");
+        }
+
+        if self.settings.include_expected {
+            if self.settings.retention_time {
                 builder.set_reference_additional("Expected Time", &Reference::get_expected_rt);
                 builder.build_expected_section(
                     "Retention Time (Expected)",
@@ -286,7 +447,7 @@ impl Exporter {
                 );
             }
 
-            if self.glucose_units {
+            if self.settings.glucose_units {
                 let copy = self.glucose_spline.clone();
                 builder.set_reference_additional("Expected GU", move |reference: &Reference| {
                     reference.get_expected_gu(copy.as_ref())
@@ -296,7 +457,7 @@ impl Exporter {
                 });
             }
 
-            if self.area {
+            if self.settings.area {
                 builder.set_sample_additional("Total Area", |sample: &Chromatography| {
                     Some(sample.total_area)
                 });
@@ -304,7 +465,7 @@ impl Exporter {
                 builder.build_expected_section("Area (Expected)", &Component::get_area);
             }
 
-            if self.concentration {
+            if self.settings.concentration {
                 if let Some(factor) = self.conc_multiplier {
                     builder.set_sample_additional(
                         "Total Concentration",
@@ -318,27 +479,60 @@ impl Exporter {
                     println!("Attempted to export concentrations without a standard set.");
                 }
             }
+
+            if self.settings.manual_labels {
+                builder.build_expected_section("Manual Label (Expected)", |component| {
+                    component.is_manual().then_some(1.0)
+                });
+            }
+
+            // Only located components have a confidence, so it has no existing section
+            if self.settings.match_confidence {
+                builder.build_expected_section("Match Confidence (Expected)", |component| {
+                    component
+                        .get_confidence()
+                        .map(|confidence| confidence.score)
+                });
+                builder.build_expected_section("Ambiguous Match (Expected)", |component| {
+                    component
+                        .get_confidence()
+                        .and_then(|confidence| confidence.is_ambiguous().then_some(1.0))
+                });
+            }
+
+            if !self.metric_columns().is_empty() {
+                builder.set_reference_additional("Expected Time", &Reference::get_expected_rt);
+                builder.set_sample_additional("Noise", |sample: &Chromatography| {
+                    Some(sample.get_noise())
+                });
+            }
+
+            for (title, extract) in self.metric_columns() {
+                builder.build_expected_section(&format!("{} (Expected)", title), |component| {
+                    component.get_peak().and_then(|peak| extract(&peak.metrics))
+                });
+            }
         }
 
-        if self.include_existing {
-            if self.retention_time {
+        if self.settings.include_existing {
+            if self.settings.retention_time {
                 builder.build_existing_section(
                     "Retention Time (Unknown)",
                     &Component::get_experimental_rt,
                 );
             }
 
-            if self.glucose_units {
+            if self.settings.glucose_units {
                 builder.build_existing_section("Glucose Units (Unknown)", |component| {
                     component.get_experimental_gu(self.glucose_spline.as_ref())
                 });
             }
 
-            if self.area {
+            if self.settings.area {
                 builder.build_existing_section("Area (Unknown)", &Component::get_area);
             }
 
-            if self.concentration {
+            if self.settings.concentration {
                 if let Some(factor) = self.conc_multiplier {
                     builder
                         .build_existing_section("Concentration (nmol/ml) (Unknown)", |component| {
@@ -348,10 +542,61 @@ impl Exporter {
                     println!("Attempted to export concentrations without a standard set.");
                 }
             }
+
+            if self.settings.system_peaks {
+                builder.build_existing_section("System Peak (Unknown)", |component| {
+                    component.is_system_peak().then_some(1.0)
+                });
+            }
+
+            if self.settings.manual_labels {
+                builder.build_existing_section("Manual Label (Unknown)", |component| {
+                    component.is_manual().then_some(1.0)
+                });
+            }
+
+            for (title, extract) in self.metric_columns() {
+                builder.build_existing_section(&format!("{} (Unknown)", title), |component| {
+                    component.get_peak().and_then(|peak| extract(&peak.metrics))
+                });
+            }
         }
 
         builder.build()
     }
+
+    /// Title and value of every selected peak metric, in order of appearance.
+    fn metric_columns(&self) -> Vec<(&'static str, fn(&PeakMetrics) -> Option<f64>)> {
+        let columns: [(bool, &'static str, fn(&PeakMetrics) -> Option<f64>); 7] = [
+            (
+                self.settings.peak_width,
+                "Width at Half Height",
+                |metrics| metrics.width_half_height,
+            ),
+            (self.settings.base_width, "Width at Base", |metrics| {
+                metrics.width_base
+            }),
+            (self.settings.tailing, "USP Tailing", |metrics| {
+                metrics.tailing
+            }),
+            (self.settings.asymmetry, "Asymmetry (10%)", |metrics| {
+                metrics.asymmetry
+            }),
+            (self.settings.plates, "Plates", |metrics| metrics.plates),
+            (self.settings.resolution, "Resolution", |metrics| {
+                metrics.resolution
+            }),
+            (self.settings.signal_to_noise, "S/N", |metrics| {
+                metrics.signal_to_noise
+            }),
+        ];
+
+        columns
+            .into_iter()
+            .filter(|(enabled, _, _)| *enabled)
+            .map(|(_, title, extract)| (title, extract))
+            .collect()
+    }
 }
 
 // There are 8 export variants depending on filter settings
@@ -384,12 +629,45 @@ trait TableBuilder<T> {
 
     fn set_transpose(&mut self, enable: bool);
 
+    /// Section of the file and every header of each sample, dates formatted for sorting.
+    fn build_metadata_section(&mut self, title: &str);
     fn build_existing_section<F: Fn(&Component) -> Option<f64>>(&mut self, title: &str, extract: F);
     fn build_expected_section<F: Fn(&Component) -> Option<f64>>(&mut self, title: &str, extract: F);
 
     fn build(self) -> T;
 }
 
+/// `File` and `Format` followed by every header found in any sample, in order of first appearance.
+fn metadata_keys(samples: &[Chromatography]) -> Vec<String> {
+    let mut keys = vec!["File".to_string(), "Format".to_string()];
+    for sample in samples {
+        for (key, _) in sample.get_metadata().headers.iter() {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+    }
+
+    keys
+}
+
+fn metadata_value(sample: &Chromatography, key: &str) -> Option<String> {
+    match key {
+        "File" => Some(sample.file_name.to_string_lossy().to_string()),
+        "Format" => Some(sample.get_format().to_string()),
+        key => sample.get_metadata().format(key),
+    }
+}
+
+/// Quotes text containing commas or quotes so it stays in one cell.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 struct TableBuilderCsv<'a> {
     builder: String,
     transpose: bool,
@@ -440,6 +718,43 @@ impl<'a> TableBuilder<String> for TableBuilderCsv<'a> {
         self.transpose = enable;
     }
 
+    fn build_metadata_section(&mut self, title: &str) {
+        self.builder.push_str(&format!("[{}]", title));
+
+        let keys = metadata_keys(self.samples);
+        let value = |sample: &Chromatography, key: &str| {
+            metadata_value(sample, key).map_or(String::new(), |value| csv_field(&value))
+        };
+
+        if self.transpose {
+            self.builder.push_str("\nTitle");
+            for key in keys.iter() {
+                self.builder.push_str(&format!(",{}", csv_field(key)));
+            }
+
+            for sample in self.samples.iter() {
+                self.builder.push_str(&format!("\n{}", sample.title));
+                for key in keys.iter() {
+                    self.builder.push_str(&format!(",{}", value(sample, key)));
+                }
+            }
+        } else {
+            self.builder.push_str("\nHeader");
+            for sample in self.samples.iter() {
+                self.builder.push_str(&format!(",{}", sample.title));
+            }
+
+            for key in keys.iter() {
+                self.builder.push_str(&format!("\n{}", csv_field(key)));
+                for sample in self.samples.iter() {
+                    self.builder.push_str(&format!(",{}", value(sample, key)));
+                }
+            }
+        }
+
+        self.builder.push_str("\n\n");
+    }
+
     fn build_existing_section<F: Fn(&Component) -> Option<f64>>(
         &mut self,
         title: &str,
@@ -566,11 +881,8 @@ impl<'a> TableBuilder<String> for TableBuilderCsv<'a> {
                     self.builder.push_str(&entry);
                 }
 
-                for lipid in sample
-                    .get_components(&ComponentFilter::EXPECTED_ONLY)
-                    .iter()
-                {
-                    let maybe_entry = extract(lipid);
+                for reference in self.references.iter() {
+                    let maybe_entry = sample.get_reference_component(reference).and_then(&extract);
                     let entry = self.format_maybe(maybe_entry);
                     self.builder.push_str(&entry);
                 }
@@ -595,7 +907,7 @@ impl<'a> TableBuilder<String> for TableBuilderCsv<'a> {
                 }
             }
 
-            for (i, reference) in self.references.iter().enumerate() {
+            for reference in self.references.iter() {
                 let name = reference.name.as_ref().map_or("[Unnamed]", |inner| &inner);
                 self.builder.push_str(&format!("\n{}", name));
 
@@ -606,10 +918,7 @@ impl<'a> TableBuilder<String> for TableBuilderCsv<'a> {
                 }
 
                 for sample in self.samples.iter() {
-                    let maybe_value = sample
-                        .get_components(&ComponentFilter::EXPECTED_ONLY)
-                        .get(i)
-                        .map_or(None, &extract);
+                    let maybe_value = sample.get_reference_component(reference).and_then(&extract);
                     let entry = self.format_maybe(maybe_value);
                     self.builder.push_str(&entry);
                 }
@@ -677,6 +986,56 @@ impl<'a> TableBuilder<Element<'static, ()>> for TableBuilderElement<'a> {
         self.transpose = enable;
     }
 
+    fn build_metadata_section(&mut self, title: &str) {
+        const ENTRY_WIDTH: u16 = 70;
+
+        let header = text(title.to_string()).into();
+        self.builder.push(header);
+
+        let keys = metadata_keys(self.samples);
+        let value = |sample: &Chromatography, key: &str| {
+            text(metadata_value(sample, key).unwrap_or_default()).width(ENTRY_WIDTH)
+        };
+
+        let mut table = column![];
+
+        if self.transpose {
+            let mut top = row![text("Title").width(ENTRY_WIDTH)];
+            for key in keys.iter() {
+                top = top.push(text(key.clone()).width(ENTRY_WIDTH));
+            }
+
+            table = table.push(top);
+
+            for sample in self.samples.iter() {
+                let mut line = row![text(sample.title.clone()).width(ENTRY_WIDTH)];
+                for key in keys.iter() {
+                    line = line.push(value(sample, key));
+                }
+
+                table = table.push(line);
+            }
+        } else {
+            let mut top = row![text("Header").width(ENTRY_WIDTH)];
+            for sample in self.samples.iter() {
+                top = top.push(text(sample.title.clone()).width(ENTRY_WIDTH));
+            }
+
+            table = table.push(top);
+
+            for key in keys.iter() {
+                let mut line = row![text(key.clone()).width(ENTRY_WIDTH)];
+                for sample in self.samples.iter() {
+                    line = line.push(value(sample, key));
+                }
+
+                table = table.push(line);
+            }
+        }
+
+        self.builder.push(table.into());
+    }
+
     fn build_existing_section<F: Fn(&Component) -> Option<f64>>(
         &mut self,
         title: &str,
@@ -827,11 +1186,8 @@ impl<'a> TableBuilder<Element<'static, ()>> for TableBuilderElement<'a> {
                     standard_row = standard_row.push(text(entry).width(ENTRY_WIDTH));
                 }
 
-                for component in sample
-                    .get_components(&ComponentFilter::EXPECTED_ONLY)
-                    .iter()
-                {
-                    let maybe_entry = extract(component);
+                for reference in self.references.iter() {
+                    let maybe_entry = sample.get_reference_component(reference).and_then(&extract);
                     let entry = self.format_maybe(maybe_entry);
                     standard_row = standard_row.push(text(entry).width(ENTRY_WIDTH));
                 }
@@ -859,7 +1215,7 @@ impl<'a> TableBuilder<Element<'static, ()>> for TableBuilderElement<'a> {
                 table = table.push(additional_row);
             }
 
-            for (i, reference) in self.references.iter().enumerate() {
+            for reference in self.references.iter() {
                 let name = reference.name.clone().unwrap_or("[Unnamed]".to_string());
                 let mut standard_row = row![text(name).width(ENTRY_WIDTH)].spacing(5);
 
@@ -870,10 +1226,7 @@ impl<'a> TableBuilder<Element<'static, ()>> for TableBuilderElement<'a> {
                 }
 
                 for sample in self.samples.iter() {
-                    let maybe_value = sample
-                        .get_components(&ComponentFilter::EXPECTED_ONLY)
-                        .get(i)
-                        .map_or(None, &extract);
+                    let maybe_value = sample.get_reference_component(reference).and_then(&extract);
                     let entry = self.format_maybe(maybe_value);
                     standard_row = standard_row.push(text(entry).width(ENTRY_WIDTH));
                 }
@@ -894,3 +1247,62 @@ impl<'a> TableBuilder<Element<'static, ()>> for TableBuilderElement<'a> {
             .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test/samples/PC12")
+            .join(name)
+    }
+
+    /// The table of one sample and the title of that sample.
+    fn export(settings: ExportSettings) -> (String, String) {
+        let references: Rc<[Reference]> =
+            Rc::from(Reference::parse_file(&fixture("PC12_reference.csv")).unwrap());
+
+        let mut sample =
+            Chromatography::from_file(&fixture("chromatogram_timeseries_46751.arw")).unwrap();
+        sample.set_data_range(&(8.5..36.5));
+        sample.set_lipid_references(Rc::clone(&references));
+        let title = sample.title.clone();
+        let samples = [sample];
+
+        let mut exporter = Exporter::default();
+        exporter.set_lipid_references(Rc::clone(&references));
+        exporter.set_settings(settings);
+        let csv = exporter.export_table(TableBuilderCsv::new(references, &samples));
+        (csv, title)
+    }
+
+    /// Header row of the section `title`.
+    fn header<'a>(csv: &'a str, title: &str) -> &'a str {
+        let start = csv
+            .find(&format!("[{}]\n", title))
+            .expect("section should be exported");
+        csv[start..].lines().nth(1).unwrap()
+    }
+
+    #[test]
+    fn metric_columns_after_labels_and_confidence() {
+        let (csv, title) = export(ExportSettings {
+            include_expected: true,
+            match_confidence: true,
+            manual_labels: true,
+            plates: true,
+            ..Default::default()
+        });
+
+        let plain = format!("Lipid,{}", title);
+        let extra = format!("Lipid,Expected Time,{}", title);
+        assert_eq!(header(&csv, "Manual Label (Expected)"), plain);
+        assert_eq!(header(&csv, "Match Confidence (Expected)"), plain);
+        assert_eq!(header(&csv, "Ambiguous Match (Expected)"), plain);
+        assert_eq!(header(&csv, "Plates (Expected)"), extra);
+
+        let noise = csv.find("[Plates (Expected)]").unwrap();
+        assert!(csv[noise..].lines().nth(2).unwrap().starts_with("Noise,"));
+    }
+}
@@ -0,0 +1,212 @@
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use hplc_core::{chromatography::Chromatography, reference::Reference, spline::Spline};
+
+use crate::project::Parameters;
+
+// Every snapshot holds a copy of all samples, so the history is kept short
+const LIMIT: usize = 30;
+
+/// Analysis state of the app before an action, restored by undo and redo.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub parameters: Parameters,
+    pub lipid_reference: Rc<[Reference]>,
+    pub reference_path: Option<PathBuf>,
+    pub samples: Vec<Chromatography>,
+    pub sample_handle: Option<usize>,
+    pub blank_handle: Option<usize>,
+    pub dex_handle: Option<usize>,
+    pub standard_handle: Option<usize>,
+    pub glucose_transformer: Option<Spline>,
+    pub concentration_multiplier: Option<f64>,
+}
+
+/// Undo and redo stacks of named actions.
+#[derive(Debug, Default)]
+pub struct History {
+    // The state before every action, oldest first
+    done: Vec<(String, Snapshot)>,
+    // The state after every undone action, most recently undone last
+    undone: Vec<(String, Snapshot)>,
+    // Whether a continuous action may still be merged into the last one
+    merging: bool,
+}
+
+impl History {
+    /// Records an action made from the `before` state, which discards every undone action.
+    ///
+    /// Continuous actions (e.g. dragging a slider or typing) are merged into the last action of the same name
+    /// until [`History::end_interaction`] or any other action,
+    /// check [`History::would_merge`] first to skip taking a snapshot which is not needed.
+    pub fn record(&mut self, action: String, before: Snapshot, continuous: bool) {
+        self.undone.clear();
+
+        let merge = self.would_merge(&action, continuous);
+        self.merging = continuous;
+        if merge {
+            return;
+        }
+
+        self.done.push((action, before));
+        self.trim();
+    }
+
+    /// Whether the action would be merged into the last one rather than recorded as a step of its own.
+    pub fn would_merge(&self, action: &str, continuous: bool) -> bool {
+        continuous && self.merging && self.done.last().is_some_and(|(last, _)| last == action)
+    }
+
+    /// Ends the current drag or typing, the next continuous action is a step of its own.
+    pub fn end_interaction(&mut self) {
+        self.merging = false;
+    }
+
+    /// State before the last action, `current` is kept for redo.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let (action, before) = self.done.pop()?;
+        self.undone.push((action, current));
+        self.merging = false;
+        Some(before)
+    }
+
+    /// State after the last undone action, `current` is kept for undo.
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let (action, after) = self.undone.pop()?;
+        self.done.push((action, current));
+        self.trim();
+        self.merging = false;
+        Some(after)
+    }
+
+    // Drops the oldest actions beyond the limit
+    fn trim(&mut self) {
+        if self.done.len() > LIMIT {
+            self.done.drain(..self.done.len() - LIMIT);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Names of the actions which can be undone, oldest first.
+    pub fn done(&self) -> impl Iterator<Item = &str> {
+        self.done.iter().map(|(action, _)| action.as_str())
+    }
+
+    /// Names of the actions which can be redone, next to redo first.
+    pub fn undone(&self) -> impl Iterator<Item = &str> {
+        self.undone.iter().rev().map(|(action, _)| action.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Snapshots are told apart by their sample handle
+    fn snapshot(id: usize) -> Snapshot {
+        Snapshot {
+            parameters: Parameters::default(),
+            lipid_reference: Rc::from(Vec::new()),
+            reference_path: None,
+            samples: vec![],
+            sample_handle: Some(id),
+            blank_handle: None,
+            dex_handle: None,
+            standard_handle: None,
+            glucose_transformer: None,
+            concentration_multiplier: None,
+        }
+    }
+
+    fn done(history: &History) -> Vec<&str> {
+        history.done().collect()
+    }
+
+    #[test]
+    fn merge_continuous() {
+        let mut history = History::default();
+        history.record("Radius".to_string(), snapshot(0), true);
+        assert!(history.would_merge("Radius", true));
+        assert!(!history.would_merge("Radius", false));
+        assert!(!history.would_merge("Order", true));
+
+        history.record("Radius".to_string(), snapshot(1), true);
+        history.record("Order".to_string(), snapshot(2), true);
+        assert_eq!(done(&history), ["Radius", "Order"]);
+
+        // The merged action restores the state from before its first step
+        history.undo(snapshot(3));
+        assert_eq!(history.undo(snapshot(2)).unwrap().sample_handle, Some(0));
+    }
+
+    #[test]
+    fn discrete_never_merge() {
+        let mut history = History::default();
+        history.record("Clear Anchors".to_string(), snapshot(0), false);
+        assert!(!history.would_merge("Clear Anchors", false));
+        history.record("Clear Anchors".to_string(), snapshot(1), false);
+        assert_eq!(done(&history).len(), 2);
+    }
+
+    #[test]
+    fn end_interaction() {
+        let mut history = History::default();
+        history.record("Radius".to_string(), snapshot(0), true);
+        history.end_interaction();
+        assert!(!history.would_merge("Radius", true));
+
+        history.record("Radius".to_string(), snapshot(1), true);
+        assert_eq!(done(&history), ["Radius", "Radius"]);
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut history = History::default();
+        assert!(history.undo(snapshot(9)).is_none());
+
+        history.record("A".to_string(), snapshot(0), false);
+        history.record("B".to_string(), snapshot(1), false);
+
+        assert_eq!(history.undo(snapshot(2)).unwrap().sample_handle, Some(1));
+        assert_eq!(history.undone().collect::<Vec<_>>(), ["B"]);
+        assert!(history.can_undo() && history.can_redo());
+
+        assert_eq!(history.redo(snapshot(1)).unwrap().sample_handle, Some(2));
+        assert!(!history.can_redo());
+        assert_eq!(done(&history), ["A", "B"]);
+
+        // A new action discards what was undone
+        history.undo(snapshot(2));
+        history.record("C".to_string(), snapshot(1), false);
+        assert!(!history.can_redo());
+        assert_eq!(done(&history), ["A", "C"]);
+    }
+
+    #[test]
+    fn undo_ends_interaction() {
+        let mut history = History::default();
+        history.record("Radius".to_string(), snapshot(0), true);
+        history.record("Other".to_string(), snapshot(1), true);
+        history.undo(snapshot(2));
+        assert!(!history.would_merge("Radius", true));
+    }
+
+    #[test]
+    fn trimmed_at_limit() {
+        let mut history = History::default();
+        for i in 0..LIMIT + 5 {
+            history.record(i.to_string(), snapshot(i), false);
+        }
+
+        assert_eq!(history.done().count(), LIMIT);
+        assert_eq!(history.done().next(), Some("5"));
+    }
+}
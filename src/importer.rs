@@ -0,0 +1,453 @@
+use iced::{
+    Color, Element, Length, Task,
+    alignment::{Horizontal, Vertical},
+    widget::{
+        self, button, checkbox, column, container, pick_list, row, scrollable, text, text_input,
+    },
+    window::{self, Settings},
+};
+use rfd::FileHandle;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use hplc_core::import::{
+    DecimalSeparator, DelimitedData, Delimiter, ImportProfile, LineRole, TimeUnit, decode_text,
+};
+
+use crate::config::Config;
+
+// Lines of the file shown with their role
+const PREVIEW_LINES: usize = 25;
+// Rejected line numbers listed before the count
+const REJECTED_SHOWN: usize = 10;
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    None,
+    QuerySourceFile,
+    SourceFile(FileHandle),
+    ProfileSelect(String),
+    ProfileName(String),
+    SaveProfile,
+    DeleteProfile,
+    DelimiterSelect(Delimiter),
+    DecimalSeparatorSelect(DecimalSeparator),
+    TimeUnitSelect(TimeUnit),
+    MetadataLines(String),
+    SkipLines(String),
+    ColumnTitles(bool),
+    TimeColumn(String),
+    SignalColumns(String),
+    Import,
+}
+
+/// Wizard reading delimited text files of unknown layout with an [`ImportProfile`].
+#[derive(Debug, Default)]
+pub struct Importer {
+    // Owned window.
+    window_id: Option<window::Id>,
+
+    // File being imported.
+    path: Option<PathBuf>,
+    text: String,
+
+    //User-defined state
+    profile: ImportProfile,
+    profiles: Vec<ImportProfile>,
+    metadata_lines_str: String,
+    skip_lines_str: String,
+    time_column_str: String,
+    signal_columns_str: String,
+    status: Option<String>,
+
+    // File read with the profile, updated whenever either changes
+    preview: Vec<(LineRole, String)>,
+    result: Option<Result<DelimitedData, String>>,
+}
+
+impl Importer {
+    pub fn external_view(&self) -> Element<'_, Message> {
+        button("Import Delimited Text")
+            .on_press(Message::QuerySourceFile)
+            .into()
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let red = Color::from_rgb8(255, 0, 0);
+
+        let file = text(match &self.path {
+            Some(path) => format!("File: {}", path.display()),
+            None => "No file selected".to_string(),
+        });
+
+        let profiles = {
+            let names: Vec<String> = self.profiles.iter().map(|p| p.name.clone()).collect();
+            let selected = names
+                .iter()
+                .find(|name| **name == self.profile.name)
+                .cloned();
+            let saved = selected.is_some();
+            row![
+                text("Profile: "),
+                pick_list(names, selected, Message::ProfileSelect),
+                text_input("name", &self.profile.name).on_input(Message::ProfileName),
+                button("Save Profile").on_press(Message::SaveProfile),
+                button("Delete Profile").on_press_maybe(saved.then_some(Message::DeleteProfile)),
+            ]
+            .spacing(5)
+        };
+
+        let separators = row![
+            text("Delimiter: "),
+            pick_list(
+                Delimiter::ALL,
+                Some(self.profile.delimiter),
+                Message::DelimiterSelect
+            ),
+            text("Decimal Separator: "),
+            pick_list(
+                DecimalSeparator::ALL,
+                Some(self.profile.decimal_separator),
+                Message::DecimalSeparatorSelect
+            ),
+        ]
+        .spacing(5);
+
+        let lines = row![
+            text("Metadata Lines: "),
+            text_input("0", &self.metadata_lines_str).on_input(Message::MetadataLines),
+            text("Lines to Skip: "),
+            text_input("0", &self.skip_lines_str).on_input(Message::SkipLines),
+            checkbox("Column Titles", self.profile.column_titles).on_toggle(Message::ColumnTitles),
+        ]
+        .spacing(5);
+
+        let columns = row![
+            text("Time Column: "),
+            text_input("1", &self.time_column_str).on_input(Message::TimeColumn),
+            pick_list(
+                TimeUnit::ALL,
+                Some(self.profile.time_unit),
+                Message::TimeUnitSelect
+            ),
+            text("Signal Columns (empty for all): "),
+            text_input("2, 3", &self.signal_columns_str).on_input(Message::SignalColumns),
+        ]
+        .spacing(5);
+
+        let preview = {
+            let mut lines = column![text("Line  Role      Columns")];
+            for (i, (role, fields)) in self.preview.iter().enumerate() {
+                let content = text(format!("{:>4}  {:<8}  {}", i + 1, role_name(*role), fields));
+                lines = match role {
+                    LineRole::Data | LineRole::Titles => lines.push(content),
+                    _ => lines.push(content.color(Color::from_rgb8(0x80, 0x80, 0x80))),
+                };
+            }
+
+            let direction = widget::scrollable::Direction::Both {
+                vertical: widget::scrollable::Scrollbar::new(),
+                horizontal: widget::scrollable::Scrollbar::new(),
+            };
+
+            scrollable(lines)
+                .direction(direction)
+                .width(700)
+                .height(300)
+        };
+
+        let summary = match &self.result {
+            None => column![],
+            Some(Ok(data)) => {
+                let mut lines = column![];
+                for channel in &data.channels {
+                    let unit = match &channel.unit {
+                        Some(unit) => format!(" ({})", unit),
+                        None => String::new(),
+                    };
+                    let first = channel.data.first().map_or(0.0, |point| point.0);
+                    let last = channel.data.last().map_or(0.0, |point| point.0);
+                    lines = lines.push(text(format!(
+                        "{}{}: {} points from {:.3} to {:.3} minutes",
+                        channel.name,
+                        unit,
+                        channel.data.len(),
+                        first,
+                        last
+                    )));
+                }
+
+                for (key, value) in &data.metadata.headers {
+                    lines = lines.push(text(format!("{}: {}", key, value)));
+                }
+
+                if !data.rejected_lines.is_empty() {
+                    let shown: Vec<String> = data
+                        .rejected_lines
+                        .iter()
+                        .take(REJECTED_SHOWN)
+                        .map(|line| line.to_string())
+                        .collect();
+                    let more = if data.rejected_lines.len() > REJECTED_SHOWN {
+                        ", ..."
+                    } else {
+                        ""
+                    };
+                    lines = lines.push(
+                        text(format!(
+                            "{} lines could not be read: {}{}",
+                            data.rejected_lines.len(),
+                            shown.join(", "),
+                            more
+                        ))
+                        .color(red),
+                    );
+                }
+
+                lines
+            }
+            Some(Err(err)) => column![text(err.clone()).color(red)],
+        };
+
+        let status = self.status.as_ref().map(|status| text(status.clone()));
+
+        let readable = matches!(self.result, Some(Ok(_)));
+        let import = button("Import")
+            .on_press_maybe((readable && self.path.is_some()).then_some(Message::Import));
+
+        let content = column![
+            file, profiles, separators, lines, columns, preview, summary, import
+        ]
+        .push_maybe(status)
+        .spacing(10);
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(Horizontal::Center)
+            .align_y(Vertical::Center)
+            .into()
+    }
+
+    pub fn update(&mut self, msg: Message) -> Task<Message> {
+        match msg {
+            Message::None => Task::none(),
+            Message::QuerySourceFile => {
+                let task = rfd::AsyncFileDialog::new()
+                    .add_filter("text", &["csv", "tsv", "txt", "asc", "dat"])
+                    .add_filter("any", &["*"])
+                    .pick_file();
+
+                Task::perform(task, |maybe_handle| match maybe_handle {
+                    None => Message::None,
+                    Some(handle) => Message::SourceFile(handle),
+                })
+            }
+            Message::SourceFile(handle) => {
+                let path = handle.path().to_path_buf();
+                let text = fs::read(&path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|bytes| decode_text(&bytes));
+
+                match text {
+                    Ok(text) => {
+                        self.text = text;
+                        self.path = Some(path);
+                        self.status = None;
+                    }
+                    Err(err) => {
+                        self.text = String::new();
+                        self.path = None;
+                        self.status = Some(format!("Could not read {}: {}", path.display(), err));
+                    }
+                }
+                self.refresh();
+
+                if self.window_id.is_some() {
+                    return Task::none();
+                }
+
+                let (id, task) = window::open(Settings::default());
+                self.window_id = Some(id);
+                task.map(|_| Message::None)
+            }
+            Message::ProfileSelect(name) => {
+                if let Some(profile) = self.profiles.iter().find(|profile| profile.name == name) {
+                    self.set_profile(profile.clone());
+                }
+                Task::none()
+            }
+            Message::ProfileName(name) => {
+                self.profile.name = name;
+                Task::none()
+            }
+            Message::SaveProfile => {
+                if self.profile.name.trim().is_empty() {
+                    self.status = Some("Name the profile before saving it".to_string());
+                    return Task::none();
+                }
+
+                match self
+                    .profiles
+                    .iter_mut()
+                    .find(|p| p.name == self.profile.name)
+                {
+                    Some(profile) => *profile = self.profile.clone(),
+                    None => self.profiles.push(self.profile.clone()),
+                }
+                self.save_profiles(format!("Saved profile {}", self.profile.name));
+                Task::none()
+            }
+            Message::DeleteProfile => {
+                self.profiles
+                    .retain(|profile| profile.name != self.profile.name);
+                self.save_profiles(format!("Deleted profile {}", self.profile.name));
+                Task::none()
+            }
+            Message::DelimiterSelect(delimiter) => {
+                self.profile.delimiter = delimiter;
+                self.refresh();
+                Task::none()
+            }
+            Message::DecimalSeparatorSelect(separator) => {
+                self.profile.decimal_separator = separator;
+                self.refresh();
+                Task::none()
+            }
+            Message::TimeUnitSelect(unit) => {
+                self.profile.time_unit = unit;
+                self.refresh();
+                Task::none()
+            }
+            Message::MetadataLines(input) => {
+                if let Ok(lines) = input.trim().parse() {
+                    self.profile.metadata_lines = lines;
+                }
+                self.metadata_lines_str = input;
+                self.refresh();
+                Task::none()
+            }
+            Message::SkipLines(input) => {
+                if let Ok(lines) = input.trim().parse() {
+                    self.profile.skip_lines = lines;
+                }
+                self.skip_lines_str = input;
+                self.refresh();
+                Task::none()
+            }
+            Message::ColumnTitles(enable) => {
+                self.profile.column_titles = enable;
+                self.refresh();
+                Task::none()
+            }
+            Message::TimeColumn(input) => {
+                if let Ok(column) = input.trim().parse::<usize>()
+                    && column > 0
+                {
+                    self.profile.time_column = column - 1;
+                }
+                self.time_column_str = input;
+                self.refresh();
+                Task::none()
+            }
+            Message::SignalColumns(input) => {
+                if let Some(columns) = parse_columns(&input) {
+                    self.profile.signal_columns = columns;
+                }
+                self.signal_columns_str = input;
+                self.refresh();
+                Task::none()
+            }
+            Message::Import => match self.window_id.take() {
+                Some(id) => window::close(id),
+                None => Task::none(),
+            },
+        }
+    }
+
+    pub fn owns_window(&self, window_id: window::Id) -> bool {
+        Some(window_id) == self.window_id
+    }
+
+    /// Forgets the window once it is closed, the next file opens a new one.
+    pub fn close(&mut self, window_id: window::Id) {
+        if self.owns_window(window_id) {
+            self.window_id = None;
+        }
+    }
+
+    /// The file and profile to import, `None` until a file is chosen.
+    pub fn get_import(&self) -> Option<(&Path, &ImportProfile)> {
+        self.path.as_deref().map(|path| (path, &self.profile))
+    }
+
+    pub fn get_profiles(&self) -> &[ImportProfile] {
+        &self.profiles
+    }
+
+    pub fn set_profiles(&mut self, profiles: Vec<ImportProfile>) {
+        self.profiles = profiles;
+        self.set_profile(ImportProfile::default());
+    }
+
+    fn set_profile(&mut self, profile: ImportProfile) {
+        self.metadata_lines_str = profile.metadata_lines.to_string();
+        self.skip_lines_str = profile.skip_lines.to_string();
+        self.time_column_str = (profile.time_column + 1).to_string();
+        self.signal_columns_str = profile
+            .signal_columns
+            .iter()
+            .map(|column| (column + 1).to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        self.profile = profile;
+        self.refresh();
+    }
+
+    /// Reads the file again with the current profile.
+    fn refresh(&mut self) {
+        let roles = self.profile.line_roles(&self.text);
+        self.preview = self
+            .text
+            .lines()
+            .zip(roles)
+            .take(PREVIEW_LINES)
+            .map(|(line, role)| (role, self.profile.split(line).join(" | ")))
+            .collect();
+        self.result = Some(self.profile.read(&self.text));
+    }
+
+    /// Writes the profiles into the config, leaving the saved defaults as they are.
+    fn save_profiles(&mut self, done: String) {
+        let mut config = Config::load();
+        config.import_profiles = self.profiles.clone();
+
+        self.status = match config.save() {
+            Ok(_) => Some(done),
+            Err(err) => Some(err),
+        };
+    }
+}
+
+fn role_name(role: LineRole) -> &'static str {
+    match role {
+        LineRole::Blank => "",
+        LineRole::Metadata => "metadata",
+        LineRole::Skipped => "skipped",
+        LineRole::Titles => "titles",
+        LineRole::Data => "data",
+    }
+}
+
+/// Parses `2, 3` (counting from 1) into column indices, `None` if any is not a column number.
+fn parse_columns(input: &str) -> Option<Vec<usize>> {
+    input
+        .split([',', ' '])
+        .filter(|column| !column.is_empty())
+        .map(|column| column.parse::<usize>().ok().filter(|column| *column > 0))
+        .map(|column| column.map(|column| column - 1))
+        .collect()
+}
@@ -1,18 +1,24 @@
 mod app;
 mod chromatogram;
-mod chromatography;
-mod component;
+mod cli;
+mod config;
 mod expandable_slider;
 mod exporter;
-mod reference;
-mod spline;
-mod vector;
+mod history;
+mod importer;
+mod project;
+mod table;
 
 use iced::Theme;
 
 use crate::app::App;
 
 fn main() -> iced::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|command| command == "batch") {
+        std::process::exit(cli::run(&args[1..]));
+    }
+
     iced::daemon("HPLC", App::update, App::view)
         .subscription(App::subscription)
         .theme(|_, _| Theme::Light)
@@ -0,0 +1,430 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use hplc_core::{
+    baseline::BaselineMethod,
+    chromatography::SampleType,
+    deconvolution::PeakModel,
+    derivative::DerivativeMethod,
+    editing::PeakEdit,
+    import::ImportProfile,
+    integration::{IntegrationEvent, IntegrationMode, IntegrationWindow},
+    label::LabelOverride,
+    noise::NoiseMethod,
+    smoothing::SmoothingMethod,
+    vector::Point2D,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{expandable_slider::SliderState, exporter::ExportSettings};
+
+pub const EXTENSION: &str = "hplc";
+const FORMAT_VERSION: u32 = 1;
+
+/// Everything required to reproduce an analysis, stored as toml.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Project {
+    pub version: u32,
+    pub parameters: Parameters,
+    pub export: ExportSettings,
+    pub reference: Option<FileRecord>,
+    #[serde(default)]
+    pub samples: Vec<SampleRecord>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Parameters {
+    pub include_unknowns: bool,
+    pub subtract_blank: bool,
+    pub smoothing: SmoothingMethod,
+    pub derivatives: DerivativeMethod,
+    pub baseline: BaselineMethod,
+    pub integration: IntegrationMode,
+    pub integration_windows: Vec<IntegrationWindow>,
+    pub integration_events: Vec<IntegrationEvent>,
+    pub peak_model: PeakModel,
+    pub noise: NoiseMethod,
+    pub noise_start: f64,
+    pub noise_end: f64,
+    pub height_signal_to_noise: bool,
+    pub injected_volume: f64,
+    pub sample_dilution: f64,
+    pub chart_start: SliderState,
+    pub chart_end: SliderState,
+    pub smoothing_radius: SliderState,
+    pub derivative_radius: SliderState,
+    pub derivative_order: SliderState,
+    pub baseline_smoothness: SliderState,
+    pub baseline_asymmetry: SliderState,
+    pub baseline_iterations: SliderState,
+    pub baseline_radius: SliderState,
+    pub skim_ratio: SliderState,
+    pub height_requirement: SliderState,
+    pub inflection_requirement: SliderState,
+    pub retention_time_tolerance: SliderState,
+    pub glucose_unit_tolerance: SliderState,
+    pub zoom_x: SliderState,
+    pub zoom_y: SliderState,
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        let slider = |value, start, end, step| SliderState {
+            value,
+            start,
+            end,
+            step,
+        };
+
+        Self {
+            include_unknowns: false,
+            subtract_blank: false,
+            smoothing: SmoothingMethod::Mean,
+            derivatives: DerivativeMethod::FiniteDifference,
+            baseline: BaselineMethod::ConvexHull,
+            integration: IntegrationMode::DropLine,
+            integration_windows: vec![],
+            integration_events: vec![],
+            peak_model: PeakModel::None,
+            noise: NoiseMethod::Mad,
+            noise_start: 0.0,
+            noise_end: 1.0,
+            height_signal_to_noise: false,
+            injected_volume: 50.0,
+            sample_dilution: 40.0,
+            chart_start: slider(8.5, 0.0, 60.0, 0.5),
+            chart_end: slider(36.5, 0.0, 60.0, 0.5),
+            smoothing_radius: slider(5.0, 0.0, 20.0, 1.0),
+            derivative_radius: slider(5.0, 1.0, 20.0, 1.0),
+            derivative_order: slider(3.0, 2.0, 6.0, 1.0),
+            baseline_smoothness: slider(6.0, 0.0, 10.0, 0.5),
+            baseline_asymmetry: slider(0.01, 0.0, 0.1, 0.001),
+            baseline_iterations: slider(10.0, 1.0, 50.0, 1.0),
+            baseline_radius: slider(50.0, 1.0, 500.0, 1.0),
+            skim_ratio: slider(10.0, 1.0, 100.0, 1.0),
+            height_requirement: slider(0.3, 0.0, 1.0, 0.01),
+            inflection_requirement: slider(10.0, 0.0, 10.0, 1.0),
+            retention_time_tolerance: slider(0.2, 0.0, 1.0, 0.01),
+            glucose_unit_tolerance: slider(0.02, 0.0, 1.0, 0.01),
+            zoom_x: slider(0.0, 0.0, 100.0, 1.1),
+            zoom_y: slider(0.0, 0.0, 100.0, 1.1),
+        }
+    }
+}
+
+/// A file used by the project, paths are relative to the project file when possible.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SampleRecord {
+    #[serde(flatten)]
+    pub file: FileRecord,
+    pub sample_type: SampleType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub baseline_anchors: Vec<Point2D>,
+    #[serde(default)]
+    pub integration: IntegrationMode,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub label_overrides: Vec<LabelOverride>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub peak_edits: Vec<PeakEdit>,
+    /// Index of the analysed signal column, the first one if missing.
+    #[serde(default, skip_serializing_if = "is_first_channel")]
+    pub channel: usize,
+    /// Layout the file was imported with, detected from its content if missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import_profile: Option<ImportProfile>,
+}
+
+fn is_first_channel(channel: &usize) -> bool {
+    *channel == 0
+}
+
+impl Project {
+    pub fn new(
+        parameters: Parameters,
+        export: ExportSettings,
+        reference: Option<FileRecord>,
+        samples: Vec<SampleRecord>,
+    ) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            parameters,
+            export,
+            reference,
+            samples,
+        }
+    }
+
+    pub fn open<P: AsRef<Path>>(path: &P) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("Could not read {}: {}", path.as_ref().display(), err))?;
+
+        let project: Project = toml::from_str(&content)
+            .map_err(|err| format!("Invalid project {}: {}", path.as_ref().display(), err))?;
+
+        if project.version > FORMAT_VERSION {
+            return Err(format!(
+                "{} was saved by a newer version of HPLC-RS",
+                path.as_ref().display()
+            ));
+        }
+
+        Ok(project)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: &P) -> Result<(), String> {
+        let content = toml::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(path, content)
+            .map_err(|err| format!("Could not write {}: {}", path.as_ref().display(), err))
+    }
+}
+
+impl FileRecord {
+    pub fn new(path: &Path, root: &Path) -> Result<Self, String> {
+        let sha256 = hash_file(path)?;
+        let path = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+
+        Ok(Self { path, sha256 })
+    }
+
+    /// Location of the file, warns if its content changed since the project was saved.
+    pub fn resolve(&self, root: &Path) -> (PathBuf, Option<String>) {
+        let path = root.join(&self.path);
+
+        let warning = match hash_file(&path) {
+            Ok(hash) if hash == self.sha256 => None,
+            Ok(_) => Some(format!(
+                "{} has changed since the project was saved",
+                path.display()
+            )),
+            Err(err) => Some(err),
+        };
+
+        (path, warning)
+    }
+}
+
+fn hash_file(path: &Path) -> Result<String, String> {
+    let content =
+        fs::read(path).map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+
+    let digest = Sha256::digest(&content);
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hplc_core::import::{DecimalSeparator, Delimiter, TimeUnit};
+    use hplc_core::integration::EventKind;
+    use hplc_core::vector::Vector2;
+
+    // Unique per test, the tests run in parallel
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("hplc_rs_{}_{}", std::process::id(), name))
+    }
+
+    fn filled_project() -> Project {
+        let parameters = Parameters {
+            include_unknowns: true,
+            subtract_blank: true,
+            smoothing: SmoothingMethod::SavitzkyGolay,
+            derivatives: DerivativeMethod::SavitzkyGolay,
+            baseline: BaselineMethod::Snip,
+            integration: IntegrationMode::TangentSkim,
+            integration_windows: vec![IntegrationWindow {
+                start: 10.0,
+                end: 12.5,
+                mode: IntegrationMode::ValleyToValley,
+            }],
+            integration_events: vec![
+                IntegrationEvent {
+                    kind: EventKind::HeightRequirement,
+                    start: 5.0,
+                    end: Some(6.0),
+                    value: 0.5,
+                },
+                IntegrationEvent {
+                    kind: EventKind::InhibitIntegration,
+                    start: 30.0,
+                    end: None,
+                    value: 0.0,
+                },
+            ],
+            peak_model: PeakModel::Gaussian,
+            noise: NoiseMethod::QuietRegion,
+            noise_start: 1.0,
+            noise_end: 2.0,
+            height_signal_to_noise: true,
+            injected_volume: 20.0,
+            sample_dilution: 10.0,
+            ..Parameters::default()
+        };
+
+        let export = ExportSettings {
+            glucose_units: true,
+            concentration: true,
+            match_confidence: true,
+            transpose: true,
+            ..ExportSettings::default()
+        };
+
+        let record = |path: &str, sample_type| SampleRecord {
+            file: FileRecord {
+                path: PathBuf::from(path),
+                sha256: "ab".repeat(32),
+            },
+            sample_type,
+            baseline_anchors: vec![],
+            integration: IntegrationMode::default(),
+            label_overrides: vec![],
+            peak_edits: vec![],
+            channel: 0,
+            import_profile: None,
+        };
+
+        let filled = SampleRecord {
+            baseline_anchors: vec![Point2D::new(1.0, 0.5), Point2D::new(40.0, 0.25)],
+            integration: IntegrationMode::ExponentialSkim,
+            label_overrides: vec![
+                LabelOverride {
+                    retention_time: 12.5,
+                    label: Some("GM1".to_string()),
+                },
+                LabelOverride {
+                    retention_time: 14.0,
+                    label: None,
+                },
+            ],
+            peak_edits: vec![
+                PeakEdit::Add {
+                    start: 1.0,
+                    end: 2.0,
+                },
+                PeakEdit::Delete { time: 3.0 },
+                PeakEdit::Split { time: 4.0 },
+                PeakEdit::Merge {
+                    first: 5.0,
+                    second: 6.0,
+                },
+                PeakEdit::Bounds {
+                    time: 7.0,
+                    start: 6.5,
+                    end: 7.5,
+                },
+            ],
+            channel: 2,
+            import_profile: Some(ImportProfile {
+                name: "Plate reader".to_string(),
+                delimiter: Delimiter::Semicolon,
+                decimal_separator: DecimalSeparator::Comma,
+                metadata_lines: 3,
+                skip_lines: 1,
+                column_titles: true,
+                time_column: 1,
+                signal_columns: vec![2, 4],
+                time_unit: TimeUnit::Seconds,
+            }),
+            ..record("data/sample.txt", SampleType::Data)
+        };
+
+        Project::new(
+            parameters,
+            export,
+            Some(FileRecord {
+                path: PathBuf::from("reference.csv"),
+                sha256: "cd".repeat(32),
+            }),
+            vec![filled, record("dex.arw", SampleType::Dex)],
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let project = filled_project();
+        let path = temp_path("round_trip.hplc");
+        project.save(&path).unwrap();
+        let opened = Project::open(&path);
+        fs::remove_file(&path).unwrap();
+        let opened = opened.unwrap();
+
+        assert_eq!(
+            toml::to_string_pretty(&opened).unwrap(),
+            toml::to_string_pretty(&project).unwrap()
+        );
+
+        assert_eq!(opened.version, FORMAT_VERSION);
+        assert_eq!(opened.parameters.integration_events[1].end, None);
+        assert_eq!(opened.parameters.noise, NoiseMethod::QuietRegion);
+        assert!(opened.export.transpose);
+        assert_eq!(
+            opened.reference.unwrap().path,
+            PathBuf::from("reference.csv")
+        );
+
+        let [sample, dex] = opened.samples.as_slice() else {
+            panic!("expected 2 samples");
+        };
+        let expected = &project.samples[0];
+        assert_eq!(sample.file.path, expected.file.path);
+        assert_eq!(sample.file.sha256, expected.file.sha256);
+        assert_eq!(sample.baseline_anchors, expected.baseline_anchors);
+        assert_eq!(sample.integration, IntegrationMode::ExponentialSkim);
+        assert_eq!(sample.label_overrides, expected.label_overrides);
+        assert_eq!(sample.peak_edits, expected.peak_edits);
+        assert_eq!(sample.channel, 2);
+        assert_eq!(sample.import_profile, expected.import_profile);
+
+        // Left out fields fall back to their defaults
+        assert_eq!(dex.sample_type, SampleType::Dex);
+        assert!(dex.label_overrides.is_empty() && dex.peak_edits.is_empty());
+        assert_eq!(dex.channel, 0);
+        assert_eq!(dex.import_profile, None);
+    }
+
+    #[test]
+    fn newer_version_rejected() {
+        let mut project = filled_project();
+        project.version = FORMAT_VERSION + 1;
+        let path = temp_path("newer.hplc");
+        project.save(&path).unwrap();
+        let opened = Project::open(&path);
+        fs::remove_file(&path).unwrap();
+
+        let err = opened.unwrap_err();
+        assert!(err.contains("newer version"), "{}", err);
+
+        let path = temp_path("invalid.hplc");
+        fs::write(&path, "version = \"one\"").unwrap();
+        let opened = Project::open(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(opened.unwrap_err().starts_with("Invalid project"));
+    }
+
+    #[test]
+    fn resolve_warns_on_change() {
+        let root = temp_path("resolve");
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("sample.arw");
+        fs::write(&path, "original").unwrap();
+
+        let record = FileRecord::new(&path, &root).unwrap();
+        assert_eq!(record.path, PathBuf::from("sample.arw"));
+        assert_eq!(record.resolve(&root), (path.clone(), None));
+
+        fs::write(&path, "changed").unwrap();
+        let (_, warning) = record.resolve(&root);
+        assert!(warning.unwrap().contains("has changed"));
+
+        fs::remove_dir_all(&root).unwrap();
+        let (_, warning) = record.resolve(&root);
+        assert!(warning.unwrap().starts_with("Could not read"));
+    }
+}
@@ -0,0 +1,210 @@
+use iced::Element;
+use iced::color;
+use iced::widget::scrollable::{Direction, Scrollbar};
+use iced::widget::{column, container, mouse_area, row, scrollable, text};
+
+use hplc_core::chromatography::{Chromatography, ComponentFilter};
+use hplc_core::component::Component;
+
+/// Table of every component of the sample, clicking a found component produces its retention time.
+pub fn sample_table(sample: &Chromatography, concentration_multiplier: f64) -> Element<'_, f64> {
+    let mut table = column![];
+    let title = text(format!(
+        "Total Area - {}, Noise - {:.3e}",
+        sample.total_area,
+        sample.get_noise()
+    ))
+    .width(950)
+    .center();
+
+    let mut gray = container::Style::default();
+    gray = gray.background(color!(0xaaaaaa));
+
+    let lipid_label = text("Lipid").center().width(200);
+    let retention_label = text("RT (m) (found/expected)").center().width(200);
+    let glucose_unit_label = text("GU (found/expected)").center().width(200);
+    let area_label = text("Area").center().width(150);
+    let concentration_label = text("Concentration (nmol/ml)").center().width(200);
+    let confidence_label = text("Match").center().width(80);
+    let metric_labels = [
+        "W0.5 (m)",
+        "Wbase (m)",
+        "Tailing",
+        "As10%",
+        "Plates",
+        "Rs",
+        "S/N",
+    ];
+
+    let header = row![
+        text("|"),
+        container(lipid_label).style(move |_| gray),
+        text("|"),
+        container(retention_label).style(move |_| gray),
+        text("|"),
+        container(glucose_unit_label).style(move |_| gray),
+        text("|"),
+        container(area_label).style(move |_| gray),
+        text("|"),
+        container(concentration_label).style(move |_| gray),
+        text("|"),
+        container(confidence_label).style(move |_| gray),
+        text("|"),
+    ]
+    .spacing(20);
+
+    let header = metric_labels.into_iter().fold(header, |header, label| {
+        let label = text(label).center().width(80);
+        header
+            .push(container(label).style(move |_| gray))
+            .push(text("|"))
+    });
+
+    let spacer_string = "-".repeat(232 + 17 * metric_labels.len());
+
+    table = table.push(title);
+    table = table.push(text(spacer_string.clone()));
+    table = table.push(header);
+
+    for component in sample.get_components(&ComponentFilter::ALL).iter() {
+        let name = match component {
+            Component::Unknown(peak) if peak.system => "[System]".to_string(),
+            Component::Unknown(peak) if peak.manual => "[Unknown] (manual)".to_string(),
+            Component::Unknown(_) => "[Unknown]".to_string(),
+            Component::Located(peak, reference) => {
+                let name = reference.name.clone().unwrap_or("[Unnamed]".to_string());
+                if peak.manual {
+                    format!("{} (manual)", name)
+                } else {
+                    name
+                }
+            }
+            Component::Reference(reference) => {
+                reference.name.clone().unwrap_or("[Unnamed]".to_string())
+            }
+        };
+
+        let retention_time = {
+            let mut builder = String::new();
+
+            if let Some(experimental) = component.get_experimental_rt() {
+                builder.push_str(&format!("{:.2}", experimental));
+            } else {
+                builder.push_str("None");
+            }
+
+            if let Some(expected) = component.get_expected_rt() {
+                builder.push_str(&format!("/{:.2}", expected));
+            }
+
+            builder
+        };
+
+        let glucose_units = {
+            let mut builder = String::new();
+
+            if let Some(experimental) =
+                component.get_experimental_gu(sample.glucose_transformer.as_ref())
+            {
+                builder.push_str(&format!("{:.2}", experimental));
+            } else {
+                builder.push_str("None");
+            }
+
+            if let Some(expected) = component.get_expected_gu(sample.glucose_transformer.as_ref()) {
+                builder.push_str(&format!("/{:.2}", expected));
+            }
+
+            builder
+        };
+
+        let area = {
+            let mut builder = String::new();
+
+            if let Some(area) = component.get_area() {
+                builder.push_str(&format!("{:.2}", area));
+            } else {
+                builder.push_str("None");
+            }
+
+            if let Some(fit) = component.get_peak().and_then(|peak| peak.fit.as_ref()) {
+                builder.push_str(&format!(" (fit, ±{:.3})", fit.residual));
+            }
+
+            builder
+        };
+
+        let concentration = {
+            let mut builder = String::new();
+
+            if let Some(area) = component.get_area() {
+                builder.push_str(&format!("{:.2}", area * concentration_multiplier));
+            } else {
+                builder.push_str("None");
+            }
+
+            builder
+        };
+
+        // Ambiguous matches need a manual look
+        let confidence = match component.get_confidence() {
+            Some(confidence) if confidence.is_ambiguous() => {
+                format!("{:.0}% (?)", confidence.score * 100.0)
+            }
+            Some(confidence) => format!("{:.0}%", confidence.score * 100.0),
+            None => "None".to_string(),
+        };
+
+        let content = row![
+            text("|"),
+            text(name).center().width(200),
+            text("|"),
+            text(retention_time).center().width(200),
+            text("|"),
+            text(glucose_units).center().width(200),
+            text("|"),
+            text(area).center().width(150),
+            text("|"),
+            text(concentration).center().width(200),
+            text("|"),
+            text(confidence).center().width(80),
+            text("|"),
+        ]
+        .spacing(20);
+
+        let metrics = component.get_peak().map(|peak| peak.metrics);
+        let metric_values = [
+            metrics.and_then(|metrics| metrics.width_half_height),
+            metrics.and_then(|metrics| metrics.width_base),
+            metrics.and_then(|metrics| metrics.tailing),
+            metrics.and_then(|metrics| metrics.asymmetry),
+            metrics.and_then(|metrics| metrics.plates),
+            metrics.and_then(|metrics| metrics.resolution),
+            metrics.and_then(|metrics| metrics.signal_to_noise),
+        ];
+
+        let content = metric_values.into_iter().fold(content, |content, value| {
+            let value = match value {
+                Some(value) if value.abs() >= 100.0 => format!("{:.0}", value),
+                Some(value) => format!("{:.3}", value),
+                None => "None".to_string(),
+            };
+
+            content.push(text(value).center().width(80)).push(text("|"))
+        });
+
+        table = table.push(text(spacer_string.clone()));
+        table = match component.get_experimental_rt() {
+            Some(retention_time) => table.push(mouse_area(content).on_press(retention_time)),
+            None => table.push(content),
+        };
+    }
+
+    table = table.push(text(spacer_string.clone()));
+    let direction = Direction::Both {
+        vertical: Scrollbar::default(),
+        horizontal: Scrollbar::default(),
+    };
+
+    scrollable(table).direction(direction).height(200).into()
+}